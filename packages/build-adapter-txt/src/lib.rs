@@ -1,8 +1,30 @@
+use anyhow::Context;
+use build_adapter::layout::{Cell, Line, ManuscriptLayout, Page};
 use build_adapter::{BuildAdapter, BuildAdapterInitializable, BuildArtifact};
 use parser::ast::{Block, Block::*, Document, Inline, Inline::*};
+use rayon::prelude::*;
+use serde::Deserialize;
+
+const PAGE_BREAK: &str = "\u{c}";
 
 #[derive(Debug, Default)]
-pub struct BuildAdapterTxt();
+pub struct BuildAdapterTxt {
+    options: BuildAdapterTxtOptions,
+}
+
+/// Options of the txt adapter, given as a TOML string in the adapter config:
+///
+/// ```toml
+/// [[adapters]]
+/// name = "txt"
+/// options = "layout = { columns = 20, rows = 20 }"
+/// ```
+///
+/// Without a `layout` the documents are written out as plain text.
+#[derive(Debug, Default, Deserialize)]
+struct BuildAdapterTxtOptions {
+    layout: Option<ManuscriptLayout>,
+}
 
 impl BuildAdapter for BuildAdapterTxt {
     fn name(&self) -> String {
@@ -14,10 +36,19 @@ impl BuildAdapter for BuildAdapterTxt {
         _config: &gokuraku_config::GokurakuConfigInstance,
         documents: &[(String, Document)],
     ) -> anyhow::Result<build_adapter::BuildArtifact> {
-        let content = documents
-            .iter()
-            .map(|(_, doc)| format_document(doc))
-            .collect::<String>();
+        let content = match &self.options.layout {
+            Some(layout) => documents
+                .par_iter()
+                .flat_map(|(_, doc)| layout.pages(doc))
+                .map(|page| format_page(&page))
+                .collect::<Vec<_>>()
+                .join(PAGE_BREAK),
+            None => documents
+                .par_iter()
+                .map(|(_, doc)| format_document(doc))
+                .collect::<Vec<_>>()
+                .concat(),
+        };
 
         Ok(BuildArtifact {
             name: "foo".to_string(),
@@ -31,11 +62,26 @@ impl BuildAdapterInitializable for BuildAdapterTxt {
         "txt".to_string()
     }
 
-    fn init(&mut self, _config: &Option<String>) -> anyhow::Result<()> {
+    fn init(&mut self, config: &Option<String>) -> anyhow::Result<()> {
+        if let Some(config) = config {
+            self.options = toml::from_str(config).context("invalid txt adapter options")?;
+        }
+        if let Some(layout) = &self.options.layout {
+            layout.validate()?;
+        }
+
         Ok(())
     }
 }
 
+fn format_page(page: &Page) -> String {
+    page.0.iter().map(format_line).collect::<String>()
+}
+
+fn format_line(line: &Line) -> String {
+    line.0.iter().map(|Cell(c)| c.as_str()).collect::<String>() + "\n"
+}
+
 fn format_document(doc: &Document) -> String {
     doc.0.iter().map(format_block).collect::<String>()
 }
@@ -45,7 +91,7 @@ fn format_block(block: &Block) -> String {
         EmptyLine => "\n".to_string(),
         Paragraph(inlines) => inlines.iter().map(format_inline).collect::<String>() + "\n",
         ThemanticBreak => "\n---\n".to_string(),
-        PdsScript(value) => format!("@{{{value}}}") + "\n",
+        PdsScript(value, _) => format!("@{{{value}}}") + "\n",
     }
 }
 
@@ -53,6 +99,6 @@ fn format_inline(inline: &Inline) -> String {
     match inline {
         Text(value) => value.to_owned(),
         Number(value) => format!("##{value}##"),
-        Expr(_value) => "".to_string(),
+        Expr(_value, _) => "".to_string(),
     }
 }
@@ -0,0 +1,234 @@
+use anyhow::{anyhow, Result};
+use parser::ast::{Block, Document, Inline};
+use serde::Deserialize;
+
+/// Characters that must not start a line (行頭禁則).
+const NOT_LINE_START: &str = "、。，．,.・：；？！!?ー―…‥々ゝゞヽヾ\
+ぁぃぅぇぉっゃゅょゎゕゖァィゥェォッャュョヮヵヶ\
+」』）〕］｝〉》】〙〗)]}";
+
+/// Characters that must not end a line (行末禁則).
+const NOT_LINE_END: &str = "「『（〔［｛〈《【〘〖([{";
+
+/// Punctuation that may hang below the last square instead of pushing a
+/// character to the next line (ぶら下げ).
+const HANGING: &str = "、。，．";
+
+const OPENING_BRACKETS: &str = "「『（";
+
+const INDENT: &str = "\u{3000}";
+
+/// Layout of manuscript paper (原稿用紙), `columns` squares per line and
+/// `rows` lines per page.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ManuscriptLayout {
+    pub columns: usize,
+    pub rows: usize,
+    pub hanging: bool,
+}
+
+impl Default for ManuscriptLayout {
+    fn default() -> Self {
+        Self {
+            columns: 20,
+            rows: 20,
+            hanging: true,
+        }
+    }
+}
+
+/// One square. Usually a single character, but punctuation followed by a
+/// closing bracket (`。」`) and numbers of up to two digits (縦中横) share a
+/// square.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cell(pub String);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Line(pub Vec<Cell>);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Page(pub Vec<Line>);
+
+impl Cell {
+    fn is(&self, chars: &str) -> bool {
+        self.0.chars().next().is_some_and(|c| chars.contains(c))
+    }
+}
+
+impl ManuscriptLayout {
+    pub fn validate(&self) -> Result<()> {
+        match (self.columns, self.rows) {
+            (columns, _) if columns < 2 => Err(anyhow!("layout needs at least 2 columns")),
+            (_, 0) => Err(anyhow!("layout needs at least 1 row")),
+            _ => Ok(()),
+        }
+    }
+
+    /// Lays the document out on pages. Each document starts on a new page.
+    pub fn pages(&self, doc: &Document) -> Vec<Page> {
+        let lines = self.lines(doc);
+
+        lines
+            .chunks(self.rows)
+            .map(|lines| Page(lines.to_vec()))
+            .collect()
+    }
+
+    pub fn lines(&self, doc: &Document) -> Vec<Line> {
+        doc.iter()
+            .flat_map(|block| match block {
+                Block::Paragraph(inlines) => self.paragraph(&cells(inlines)),
+                Block::EmptyLine | Block::ThemanticBreak => vec![Line::default()],
+                Block::PdsScript(..) => Vec::new(),
+            })
+            .collect()
+    }
+
+    fn paragraph(&self, cells: &[Cell]) -> Vec<Line> {
+        // dialogue starts at the first square, everything else is indented
+        let indent = match cells.first() {
+            Some(cell) if cell.is(OPENING_BRACKETS) => 0,
+            _ => 1,
+        };
+        let mut lines = Vec::new();
+        let mut line = vec![Cell(INDENT.to_string()); indent];
+
+        for cell in cells.iter().cloned() {
+            if line.len() >= self.columns {
+                if cell.is(NOT_LINE_START) {
+                    if self.hanging && cell.is(HANGING) && line.len() == self.columns {
+                        line.push(cell);
+                        continue;
+                    }
+
+                    let carried = push_out(&mut line);
+                    lines.push(Line(std::mem::replace(&mut line, carried)));
+                } else {
+                    lines.push(Line(std::mem::take(&mut line)));
+                }
+            } else if line.len() == self.columns - 1 && cell.is(NOT_LINE_END) {
+                lines.push(Line(std::mem::take(&mut line)));
+            }
+
+            line.push(cell);
+        }
+        lines.push(Line(line));
+
+        lines
+    }
+}
+
+/// Moves characters from the end of a full line to the next one (追い出し), so
+/// that the next line does not start with a prohibited character. At least
+/// one character is left on the line.
+fn push_out(line: &mut Vec<Cell>) -> Vec<Cell> {
+    let keep = (1..line.len())
+        .rev()
+        .find(|&idx| !line[idx].is(NOT_LINE_START) && !line[idx - 1].is(NOT_LINE_END))
+        .unwrap_or(line.len() - 1);
+
+    line.split_off(keep)
+}
+
+fn cells(inlines: &[Inline]) -> Vec<Cell> {
+    inlines.iter().fold(Vec::new(), |mut acc, inline| {
+        match inline {
+            Inline::Text(value) if matches!(value.as_str(), "\r\n" | "\n" | "\r") => {}
+            Inline::Text(value) => value.chars().for_each(|c| match acc.last_mut() {
+                Some(Cell(prev))
+                    if "」』）".contains(c) && prev.chars().all(|p| HANGING.contains(p)) =>
+                {
+                    prev.push(c)
+                }
+                _ => acc.push(Cell(c.to_string())),
+            }),
+            Inline::Number(value) => acc.extend(number(value)),
+            Inline::Expr(..) => {}
+        };
+
+        acc
+    })
+}
+
+/// Numbers of up to two digits are set in one square; longer numbers are
+/// written in kanji numerals, one digit per square.
+fn number(value: &str) -> Vec<Cell> {
+    let value = value.trim();
+    if value.chars().count() <= 2 {
+        return vec![Cell(value.to_string())];
+    }
+
+    value
+        .chars()
+        .map(|c| match c.to_digit(10) {
+            Some(d) => "〇一二三四五六七八九".chars().nth(d as usize).unwrap(),
+            None => c,
+        })
+        .map(|c| Cell(c.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(columns: usize, text: &str) -> Vec<String> {
+        let doc = parser::prose_down_parse(&format!("{text}\n")).unwrap();
+        ManuscriptLayout {
+            columns,
+            rows: 20,
+            hanging: true,
+        }
+        .lines(&doc)
+        .into_iter()
+        .map(|Line(cells)| cells.into_iter().map(|Cell(c)| c).collect())
+        .collect()
+    }
+
+    #[test]
+    fn indent_test() {
+        assert_eq!(layout(5, "あいうえおか"), vec!["　あいうえ", "おか"]);
+        assert_eq!(layout(5, "「あいう」"), vec!["「あいう」"]);
+    }
+
+    #[test]
+    fn hanging_test() {
+        assert_eq!(layout(5, "あいうえ。"), vec!["　あいうえ。"]);
+        assert_eq!(layout(5, "あいうえ。、"), vec!["　あいう", "え。、"]);
+    }
+
+    #[test]
+    fn push_out_test() {
+        assert_eq!(layout(5, "あいうえ」か"), vec!["　あいう", "え」か"]);
+    }
+
+    #[test]
+    fn line_end_test() {
+        assert_eq!(layout(5, "あいう「え」"), vec!["　あいう", "「え」"]);
+    }
+
+    #[test]
+    fn punctuation_and_bracket_test() {
+        assert_eq!(layout(5, "「あいう。」"), vec!["「あいう。」"]);
+    }
+
+    #[test]
+    fn number_test() {
+        assert_eq!(layout(20, "##12##月##2023##年"), vec!["　12月二〇二三年"]);
+        assert_eq!(number("12"), vec![Cell("12".to_string())]);
+    }
+
+    #[test]
+    fn pages_test() {
+        let doc = parser::prose_down_parse("あ\nい\nう\n").unwrap();
+        let pages = ManuscriptLayout {
+            columns: 20,
+            rows: 2,
+            hanging: true,
+        }
+        .pages(&doc);
+
+        assert_eq!(pages.len(), 2);
+    }
+}
@@ -1,3 +1,5 @@
+pub mod layout;
+
 use anyhow::Result;
 use gokuraku_config::GokurakuConfigInstance;
 use parser::ast::Document;
@@ -7,7 +9,9 @@ pub struct BuildArtifact {
     pub content: Vec<u8>,
 }
 
-pub trait BuildAdapter {
+/// Adapters may be run from a thread pool, and are expected to process
+/// documents in parallel where they can.
+pub trait BuildAdapter: Send + Sync {
     fn name(&self) -> String;
     fn build(
         &self,
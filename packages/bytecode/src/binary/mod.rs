@@ -1,3 +1,5 @@
+use num_bigint::BigInt;
+use num_rational::BigRational;
 use std::fmt;
 
 #[derive(Debug, Clone)]
@@ -43,6 +45,10 @@ pub enum Instruction {
 pub enum Literal {
     Int(isize),
     Float(f64),
+    /// A boxed constant, which wasm has no number for
+    Integer(Box<BigInt>),
+    /// A boxed constant, which wasm has no number for
+    Rational(Box<BigRational>),
     String(String),
     Unit,
 }
@@ -3,6 +3,8 @@ use crate::{
     Ir,
 };
 use anyhow::Result;
+use num_bigint::BigInt;
+use num_rational::BigRational;
 use pest::{
     iterators::{Pair, Pairs},
     Parser, RuleType,
@@ -116,10 +118,26 @@ fn parse_literal(pair: Pair<Rule>) -> Expr {
 
     match literal.as_rule() {
         Rule::intLiteral => Expr::Literal(Literal::Int(literal.as_str().parse().unwrap())),
+        Rule::floatLiteral => Expr::Literal(Literal::Float(literal.as_str().parse().unwrap())),
+        Rule::integerLiteral => Expr::Literal(Literal::Integer(Box::new(
+            literal.as_str().trim_end_matches('n').parse().unwrap(),
+        ))),
+        Rule::rationalLiteral => Expr::Literal(Literal::Rational(Box::new(parse_rational(
+            literal.as_str().trim_end_matches('r'),
+        )))),
         _ => unreachable!(),
     }
 }
 
+fn parse_rational(literal: &str) -> BigRational {
+    let (numer, denom) = literal.split_once('/').unwrap_or((literal, "1"));
+
+    BigRational::new(
+        numer.parse::<BigInt>().unwrap(),
+        denom.parse::<BigInt>().unwrap(),
+    )
+}
+
 fn parse_call(pair: Pair<Rule>) -> Vec<Expr> {
     pair.into_inner().map(parse_expr).collect()
 }
@@ -140,6 +158,43 @@ fn main(): Int {
         assert!(ir.is_ok());
     }
 
+    #[test]
+    fn test_parse_float() {
+        let code = r#"
+fn half(): Float {
+    0.5
+}
+        "#;
+
+        let ir = parse(code).unwrap();
+        match ir {
+            Ir::Module(module) => assert!(matches!(
+                module.0.as_slice(),
+                [Ir::FunctionSymbol(_, _, Expr::Literal(Literal::Float(value)))] if *value == 0.5
+            )),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_parse_rational() {
+        let code = r#"
+fn half(): Rational {
+    3/6r
+}
+        "#;
+
+        let ir = parse(code).unwrap();
+        match ir {
+            Ir::Module(module) => assert!(matches!(
+                module.0.as_slice(),
+                [Ir::FunctionSymbol(_, _, Expr::Literal(Literal::Rational(value)))]
+                    if **value == BigRational::new(1.into(), 2.into())
+            )),
+            _ => unreachable!(),
+        }
+    }
+
     #[test]
     fn test_find_main() {
         let code = r#"
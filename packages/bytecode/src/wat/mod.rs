@@ -84,6 +84,7 @@ impl From<TypeSymbol> for WasmType {
     fn from(value: TypeSymbol) -> Self {
         match value.0.as_str() {
             "Int" => WasmType::I64,
+            "Float" => WasmType::F64,
             "()" => WasmType::I32,
             _ => unreachable!(),
         }
@@ -138,6 +139,7 @@ impl From<Literal> for WatExpr {
                 WatExpr::I64Const(i64::try_from(value).expect("Failed to parse literal as i64"))
             }
             Literal::Float(value) => WatExpr::F64Const(value),
+            Literal::Integer(_) | Literal::Rational(_) => unimplemented!("boxed constants"),
             _ => unimplemented!(),
         }
     }
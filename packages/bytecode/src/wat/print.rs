@@ -113,6 +113,9 @@ impl fmt::Display for WatPrinter {
             WatExpr::I64Const(val) => {
                 write!(f, "(i64.const {})", val)
             }
+            WatExpr::F64Const(val) => {
+                write!(f, "(f64.const {:?})", val)
+            }
             WatExpr::I64Add => {
                 write!(f, "{}", "    ".repeat(*indent))?;
                 write!(f, "(i64.add)")
@@ -23,11 +23,22 @@ pub(crate) struct Args {
 pub(crate) enum Commands {
     Build(Build),
     BuildPds(BuildPds),
+    Fmt(Fmt),
+    Lsp(Lsp),
+    Repl(Repl),
     Run(Run),
+    Stats(Stats),
 }
 
 #[derive(Debug, clap::Args)]
-pub(crate) struct Build {}
+pub(crate) struct Build {
+    /// Number of threads used to parse documents. Defaults to the number of CPUs.
+    #[clap(short, long)]
+    pub(crate) jobs: Option<usize>,
+    /// Ignore the build cache in the output directory.
+    #[clap(long)]
+    pub(crate) no_cache: bool,
+}
 
 #[derive(Debug, clap::Args)]
 pub(crate) struct BuildPds {
@@ -35,10 +46,56 @@ pub(crate) struct BuildPds {
     pub(crate) file: PathBuf,
 }
 
+/// Formats scripts in place, or the one on stdin to stdout without files.
+#[derive(Debug, clap::Args)]
+pub(crate) struct Fmt {
+    #[clap()]
+    pub(crate) files: Vec<PathBuf>,
+    /// List the files which are not formatted and fail instead of writing them.
+    #[clap(long)]
+    pub(crate) check: bool,
+}
+
+/// Serves the language server protocol over stdio.
+#[derive(Debug, clap::Args)]
+pub(crate) struct Lsp {
+    #[clap(flatten)]
+    pub(crate) std: StdOptions,
+}
+
+#[derive(Debug, clap::Args)]
+pub(crate) struct Repl {
+    /// A file to load first.
+    #[clap()]
+    pub(crate) file: Option<PathBuf>,
+    #[clap(flatten)]
+    pub(crate) std: StdOptions,
+}
+
 #[derive(Debug, clap::Args)]
 pub(crate) struct Run {
     #[clap()]
     pub(crate) file: PathBuf,
+    #[clap(flatten)]
+    pub(crate) std: StdOptions,
+}
+
+#[derive(Debug, clap::Args)]
+pub(crate) struct StdOptions {
+    /// Directory of the standard library, imported modules not found in the
+    /// project are looked up there. Defaults to the bundled one.
+    #[clap(long, env = "GOKURAKU_STD")]
+    pub(crate) std: Option<PathBuf>,
+    /// Run without a standard library and its prelude.
+    #[clap(long, conflicts_with = "std")]
+    pub(crate) no_std: bool,
+}
+
+#[derive(Debug, clap::Args)]
+pub(crate) struct Stats {
+    /// Print the statistics as JSON.
+    #[clap(long)]
+    pub(crate) json: bool,
 }
 
 pub(crate) fn parse() -> Args {
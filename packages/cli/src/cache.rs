@@ -0,0 +1,403 @@
+use anyhow::{Context, Result};
+use build_adapter::{BuildAdapter, BuildArtifact};
+use gokuraku_config::GokurakuConfigInstance;
+use parser::ast;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+const CACHE_DIR: &str = ".gokuraku-cache";
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// On-disk cache of parsed and evaluated documents and adapter artifacts.
+///
+/// Documents are stored by the hash of their source, so an unchanged file is
+/// never parsed twice. Evaluated documents are stored by that hash together
+/// with the hashes of the modules their scripts import, so a file is evaluated
+/// again only when it or one of those modules changes. Artifacts are stored per
+/// adapter together with the hash of everything they were built from, and are
+/// reused while that hash matches. The documents are hashed as evaluated, so an
+/// artifact is rebuilt when a module they import or the bundled standard
+/// library changes. Every key includes the gokuraku version.
+pub(crate) struct BuildCache {
+    dir: PathBuf,
+    hashes: Mutex<HashMap<String, String>>,
+    evaluated: Mutex<HashMap<String, String>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedArtifact {
+    key: String,
+    name: String,
+    content: Vec<u8>,
+}
+
+impl BuildCache {
+    pub(crate) fn open(output: &Path) -> Result<Self> {
+        let dir = output.join(CACHE_DIR);
+        fs::create_dir_all(dir.join("documents"))
+            .and_then(|_| fs::create_dir_all(dir.join("evaluated")))
+            .and_then(|_| fs::create_dir_all(dir.join("artifacts")))
+            .with_context(|| format!("failed to create cache directory {}", dir.display()))?;
+
+        Ok(Self {
+            dir,
+            hashes: Mutex::new(HashMap::new()),
+            evaluated: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub(crate) fn document(
+        &self,
+        path: &str,
+        source: &str,
+        parse: impl FnOnce(&str) -> Result<ast::Document>,
+    ) -> Result<ast::Document> {
+        let hash = hash([VERSION, source]);
+        let file = self.dir.join("documents").join(format!("{hash}.json"));
+        self.hashes.lock().unwrap().insert(path.to_owned(), hash);
+
+        if let Some(doc) = read_json(&file) {
+            return Ok(doc);
+        }
+
+        let doc = parse(source)?;
+        write_json(&file, &doc)?;
+
+        Ok(doc)
+    }
+
+    /// The document at `path` as evaluated with `imports`, the modules its
+    /// scripts link with. A document this build did not parse through the cache
+    /// is evaluated every time.
+    pub(crate) fn evaluated(
+        &self,
+        path: &str,
+        imports: &[(PathBuf, String)],
+        evaluate: impl FnOnce() -> Result<ast::Document>,
+    ) -> Result<ast::Document> {
+        let source = match self.hashes.lock().unwrap().get(path) {
+            Some(hash) => hash.clone(),
+            None => return evaluate(),
+        };
+        let key = evaluated_key(VERSION, &source, imports);
+        let file = self.dir.join("evaluated").join(format!("{key}.json"));
+        self.evaluated
+            .lock()
+            .unwrap()
+            .insert(path.to_owned(), key.clone());
+
+        if let Some(doc) = read_json(&file) {
+            return Ok(doc);
+        }
+
+        let doc = evaluate()?;
+        write_json(&file, &doc)?;
+
+        Ok(doc)
+    }
+
+    pub(crate) fn artifact(
+        &self,
+        conf: &GokurakuConfigInstance,
+        adapter: &dyn BuildAdapter,
+        documents: &[(String, ast::Document)],
+        build: impl FnOnce() -> Result<BuildArtifact>,
+    ) -> Result<BuildArtifact> {
+        let name = adapter.name();
+        let key = artifact_key(VERSION, conf, &name, documents)?;
+        let file = self.dir.join("artifacts").join(format!("{name}.json"));
+
+        match read_json::<CachedArtifact>(&file) {
+            Some(cached) if cached.key == key => Ok(BuildArtifact {
+                name: cached.name,
+                content: cached.content,
+            }),
+            _ => {
+                let artifact = build()?;
+                write_json(
+                    &file,
+                    &CachedArtifact {
+                        key,
+                        name: artifact.name.clone(),
+                        content: artifact.content.clone(),
+                    },
+                )?;
+
+                Ok(artifact)
+            }
+        }
+    }
+
+    /// Removes the parsed and evaluated documents that were not used by this
+    /// build. Only a build of the whole index uses every document that is
+    /// still in it.
+    pub(crate) fn prune(&self) -> Result<()> {
+        let documents = self.hashes.lock().unwrap().values().cloned().collect();
+        let evaluated = self.evaluated.lock().unwrap().values().cloned().collect();
+
+        prune_dir(&self.dir.join("documents"), &documents)?;
+        prune_dir(&self.dir.join("evaluated"), &evaluated)
+    }
+}
+
+/// Removes the entries of `dir` but those of `used`.
+fn prune_dir(dir: &Path, used: &HashSet<String>) -> Result<()> {
+    fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .filter(|path| {
+            path.file_stem()
+                .and_then(|name| name.to_str())
+                .is_none_or(|name| !used.contains(name))
+        })
+        .try_for_each(fs::remove_file)?;
+
+    Ok(())
+}
+
+fn evaluated_key(version: &str, source: &str, imports: &[(PathBuf, String)]) -> String {
+    let imports = imports
+        .iter()
+        .map(|(path, source)| format!("{}:{}", path.display(), hash([source.as_str()])))
+        .collect::<Vec<_>>();
+
+    hash(
+        [version, source]
+            .into_iter()
+            .chain(imports.iter().map(String::as_str)),
+    )
+}
+
+fn artifact_key(
+    version: &str,
+    conf: &GokurakuConfigInstance,
+    name: &str,
+    documents: &[(String, ast::Document)],
+) -> Result<String> {
+    let options = conf
+        .adapters
+        .iter()
+        .find(|adapter| adapter.name == name)
+        .map(serde_json::to_string)
+        .transpose()?
+        .unwrap_or_default();
+
+    let inputs = documents
+        .iter()
+        .map(|(path, doc)| {
+            Ok(format!(
+                "{path}:{}",
+                hash([serde_json::to_string(doc)?.as_str()])
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(hash(
+        [version, name, &options, &conf.env]
+            .into_iter()
+            .chain(inputs.iter().map(String::as_str)),
+    ))
+}
+
+fn hash<'a>(parts: impl IntoIterator<Item = &'a str>) -> String {
+    let digest = parts
+        .into_iter()
+        .fold(Sha256::new(), |hasher, part| {
+            // length-prefixed so that ("ab", "c") and ("a", "bc") differ
+            hasher
+                .chain_update(part.len().to_le_bytes())
+                .chain_update(part)
+        })
+        .finalize();
+
+    digest.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: &Path) -> Option<T> {
+    // an unreadable or outdated entry is treated as a cache miss
+    fs::read(path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    fs::write(path, serde_json::to_vec(value)?)
+        .with_context(|| format!("failed to write cache entry {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gokuraku_config::{BuildAdapterConfig, GokurakuConfig};
+    use parser::{
+        prose_down_parse, prose_down_script_evaluate, prose_down_script_imports,
+        ProseDownScriptLoader,
+    };
+    use std::{cell::Cell, env};
+
+    struct Adapter;
+
+    impl BuildAdapter for Adapter {
+        fn name(&self) -> String {
+            "txt".to_string()
+        }
+
+        fn build(
+            &self,
+            _: &GokurakuConfigInstance,
+            _: &[(String, ast::Document)],
+        ) -> Result<BuildArtifact> {
+            unreachable!()
+        }
+    }
+
+    fn conf(options: Option<&str>) -> GokurakuConfigInstance {
+        GokurakuConfigInstance::try_from(GokurakuConfig {
+            adapters: vec![BuildAdapterConfig {
+                name: "txt".to_string(),
+                options: options.map(String::from),
+            }],
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    fn documents(source: &str) -> Vec<(String, ast::Document)> {
+        vec![("a.pd".to_string(), prose_down_parse(source).unwrap())]
+    }
+
+    /// An empty directory of its own for each test.
+    fn scratch(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("gokuraku-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    #[test]
+    fn artifact_key_test() {
+        let key = |version, conf: &GokurakuConfigInstance, name, source| {
+            artifact_key(version, conf, name, &documents(source)).unwrap()
+        };
+        let base = key("1", &conf(None), "txt", "a\n");
+
+        assert_eq!(key("1", &conf(None), "txt", "a\n"), base);
+        assert_ne!(key("2", &conf(None), "txt", "a\n"), base);
+        assert_ne!(key("1", &conf(None), "html", "a\n"), base);
+        assert_ne!(key("1", &conf(Some("width = 20")), "txt", "a\n"), base);
+        assert_ne!(key("1", &conf(None), "txt", "b\n"), base);
+
+        let production = GokurakuConfigInstance {
+            env: "production".to_string(),
+            ..conf(None)
+        };
+        assert_ne!(key("1", &production, "txt", "a\n"), base);
+    }
+
+    #[test]
+    fn dependency_test() {
+        let dir = scratch("dependency");
+        let loader = ProseDownScriptLoader::new(&dir);
+        let doc = prose_down_parse("@{\nimport \"./lib\" (name)\n}\n\n{name}\n").unwrap();
+        let key = |name: &str| {
+            fs::write(
+                dir.join("lib.pds"),
+                format!("export name\nname = \"{name}\"\n"),
+            )
+            .unwrap();
+            let doc = prose_down_script_evaluate(&loader, &doc).unwrap();
+
+            artifact_key("1", &conf(None), "txt", &[("a.pd".to_string(), doc)]).unwrap()
+        };
+
+        assert_eq!(key("before"), key("before"));
+        assert_ne!(key("before"), key("after"));
+    }
+
+    #[test]
+    fn evaluated_test() {
+        let dir = scratch("evaluated");
+        let cache = BuildCache::open(&dir).unwrap();
+        let loader = ProseDownScriptLoader::new(&dir);
+        let source = "@{\nimport \"./lib\" (name)\n}\n\n{name}\n";
+        let evaluations = Cell::new(0);
+        let evaluate = |name: &str| {
+            fs::write(
+                dir.join("lib.pds"),
+                format!("export name\nname = \"{name}\"\n"),
+            )
+            .unwrap();
+            let doc = cache.document("a.pd", source, prose_down_parse).unwrap();
+            let imports = prose_down_script_imports(&loader, &doc).unwrap();
+
+            cache
+                .evaluated("a.pd", &imports, || {
+                    evaluations.set(evaluations.get() + 1);
+                    prose_down_script_evaluate(&loader, &doc)
+                })
+                .unwrap()
+        };
+
+        evaluate("before");
+        evaluate("before");
+        assert_eq!(evaluations.get(), 1);
+        evaluate("after");
+        assert_eq!(evaluations.get(), 2);
+    }
+
+    #[test]
+    fn prune_test() {
+        let entries =
+            |cache: &BuildCache, kind| fs::read_dir(cache.dir.join(kind)).unwrap().count();
+        let dir = scratch("prune");
+        let cache = BuildCache::open(&dir).unwrap();
+        ["a\n", "b\n"].iter().for_each(|source| {
+            let doc = cache.document("a.pd", source, prose_down_parse).unwrap();
+            cache.evaluated("a.pd", &[], || Ok(doc)).unwrap();
+        });
+        assert_eq!(entries(&cache, "documents"), 2);
+        assert_eq!(entries(&cache, "evaluated"), 2);
+
+        cache.prune().unwrap();
+        assert_eq!(entries(&cache, "documents"), 1);
+        assert_eq!(entries(&cache, "evaluated"), 1);
+
+        // the entries this build used are still there for the next one
+        let reopened = BuildCache::open(&dir).unwrap();
+        reopened.document("a.pd", "b\n", prose_down_parse).unwrap();
+        reopened.evaluated("a.pd", &[], || unreachable!()).unwrap();
+    }
+
+    #[test]
+    fn artifact_test() {
+        let cache = BuildCache::open(&scratch("artifact")).unwrap();
+        let builds = Cell::new(0);
+        let build = |source| {
+            cache
+                .artifact(&conf(None), &Adapter, &documents(source), || {
+                    builds.set(builds.get() + 1);
+
+                    Ok(BuildArtifact {
+                        name: "a.txt".to_string(),
+                        content: source.as_bytes().to_vec(),
+                    })
+                })
+                .unwrap()
+                .content
+        };
+
+        assert_eq!(build("a\n"), b"a\n");
+        assert_eq!(build("a\n"), b"a\n");
+        assert_eq!(builds.get(), 1);
+        assert_eq!(build("b\n"), b"b\n");
+        assert_eq!(builds.get(), 2);
+    }
+}
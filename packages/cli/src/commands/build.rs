@@ -1,21 +1,44 @@
-use anyhow::{anyhow, Result};
+use crate::args::Build;
+use crate::cache::BuildCache;
+use anyhow::{anyhow, Context, Result};
 use build_adapter::{BuildAdapter, BuildAdapterInitializable};
 use build_adapter_txt::BuildAdapterTxt;
-use gokuraku_config::{GokurakuConfigInstance, IndexTree, IndexTree::*};
-use parser::ast;
-use std::fs;
+use gokuraku_config::{GokurakuConfigInstance, IndexTree};
+use parser::{ast, prose_down_script_evaluate, prose_down_script_imports, ProseDownScriptLoader};
+use rayon::prelude::*;
+use std::{env, fs};
 
-pub fn build(conf: &GokurakuConfigInstance) -> Result<()> {
-    let docs = parse_index_tree(conf.index())?;
+pub(crate) fn build(conf: &GokurakuConfigInstance, options: &Build) -> Result<()> {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(options.jobs.unwrap_or(0))
+        .build()?;
+    let cache = match (&conf.output, options.no_cache) {
+        (Some(output), false) => Some(BuildCache::open(output)?),
+        _ => None,
+    };
+    let docs = pool.install(|| parse_index_tree(&conf.index().resolve()?, cache.as_ref()))?;
+    let docs = pool.install(|| evaluate_documents(docs, cache.as_ref()))?;
+    let adapters = adapters(conf)?;
 
-    adapters(conf)?
-        .iter()
-        .map(|adapter| adapter.build(conf, &docs))
-        .collect::<Result<Vec<_>>>()?
-        .into_iter()
-        .for_each(|artifact| {
-            println!("{}", std::str::from_utf8(&artifact.content).unwrap());
-        });
+    let artifacts = pool.install(|| {
+        adapters
+            .par_iter()
+            .map(|adapter| match &cache {
+                Some(cache) => {
+                    cache.artifact(conf, adapter.as_ref(), &docs, || adapter.build(conf, &docs))
+                }
+                None => adapter.build(conf, &docs),
+            })
+            .collect::<Result<Vec<_>>>()
+    })?;
+    // a build of a single input leaves the entries of the other documents
+    if let (Some(cache), None) = (&cache, &conf.input) {
+        cache.prune()?;
+    }
+
+    artifacts.into_iter().for_each(|artifact| {
+        println!("{}", std::str::from_utf8(&artifact.content).unwrap());
+    });
 
     Ok(())
 }
@@ -43,30 +66,142 @@ fn adapters(conf: &GokurakuConfigInstance) -> Result<Vec<Box<dyn BuildAdapter>>>
     Ok(ret)
 }
 
-fn parse_index_tree(tree: &IndexTree) -> Result<Vec<(String, ast::Document)>> {
-    match tree {
-        Root(nodes) => Ok(nodes
+/// Parses every document of the tree in parallel. The result keeps index order,
+/// and failures are reported all together, also in index order.
+pub(crate) fn parse_index_tree(
+    tree: &IndexTree,
+    cache: Option<&BuildCache>,
+) -> Result<Vec<(String, ast::Document)>> {
+    let (docs, errs): (Vec<_>, Vec<_>) = tree
+        .paths()
+        .into_par_iter()
+        .map(|path| {
+            read_and_parse(path, cache)
+                .map(|doc| (path.to_owned(), doc))
+                .with_context(|| format!("failed to load {path}"))
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+        .partition(Result::is_ok);
+
+    match errs.into_iter().map(Result::unwrap_err).collect::<Vec<_>>() {
+        errs if errs.is_empty() => Ok(docs.into_iter().map(Result::unwrap).collect()),
+        errs => Err(anyhow!(errs
             .iter()
-            .map(parse_index_tree)
-            .collect::<Result<Vec<_>>>()?
-            .into_iter()
-            .flatten()
-            .collect()),
-        Node(path, nodes) => Ok(vec![(path.to_owned(), read_and_parse(path)?)]
-            .into_iter()
-            .chain(
-                nodes
-                    .iter()
-                    .map(parse_index_tree)
-                    .collect::<Result<Vec<_>>>()?
-                    .into_iter()
-                    .flatten(),
-            )
-            .collect()),
-        Leaf(path) => read_and_parse(path).map(|doc| vec![(path.to_owned(), doc)]),
+            .map(|err| format!("{err:#}"))
+            .collect::<Vec<_>>()
+            .join("\n"))),
+    }
+}
+
+/// Evaluates the scripts of every document with the bundled standard library,
+/// in parallel.
+fn evaluate_documents(
+    docs: Vec<(String, ast::Document)>,
+    cache: Option<&BuildCache>,
+) -> Result<Vec<(String, ast::Document)>> {
+    let loader = ProseDownScriptLoader::new(env::current_dir()?).with_bundled_std();
+
+    docs.into_par_iter()
+        .map(|(path, doc)| {
+            let evaluate = || prose_down_script_evaluate(&loader, &doc);
+            let doc = match cache {
+                Some(cache) => prose_down_script_imports(&loader, &doc)
+                    .and_then(|imports| cache.evaluated(&path, &imports, evaluate)),
+                None => evaluate(),
+            }
+            .with_context(|| format!("failed to evaluate {path}"))?;
+
+            Ok((path, doc))
+        })
+        .collect()
+}
+
+fn read_and_parse(path: &str, cache: Option<&BuildCache>) -> Result<ast::Document> {
+    let source = fs::read_to_string(path)?;
+
+    match cache {
+        Some(cache) => cache.document(path, &source, parser::prose_down_parse),
+        None => parser::prose_down_parse(&source),
     }
 }
 
-fn read_and_parse(path: &str) -> Result<ast::Document> {
-    parser::prose_down_parse(&fs::read_to_string(path)?)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::{Path, PathBuf};
+
+    /// An empty directory of its own for each test.
+    fn scratch(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("gokuraku-build-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        dir
+    }
+
+    /// Writes `sources` into `dir` and returns a tree of them in the same order.
+    /// A `None` leaves its file missing.
+    fn tree(dir: &Path, sources: &[Option<String>]) -> IndexTree {
+        IndexTree::Root(
+            sources
+                .iter()
+                .enumerate()
+                .map(|(i, source)| {
+                    let path = dir.join(format!("{i}.pd"));
+                    if let Some(source) = source {
+                        fs::write(&path, source).unwrap();
+                    }
+
+                    IndexTree::Leaf(path.to_string_lossy().to_string())
+                })
+                .collect(),
+        )
+    }
+
+    fn parse_with_threads(tree: &IndexTree) -> Result<Vec<(String, ast::Document)>> {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(4)
+            .build()
+            .unwrap()
+            .install(|| parse_index_tree(tree, None))
+    }
+
+    #[test]
+    fn order_test() {
+        // the first documents are the longest, so they finish parsing last
+        let sources = (0..8)
+            .map(|i| Some(format!("document {i}\n\n").repeat((8 - i) * 500)))
+            .collect::<Vec<_>>();
+        let tree = tree(&scratch("order"), &sources);
+
+        let paths = parse_with_threads(&tree)
+            .unwrap()
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect::<Vec<_>>();
+
+        assert_eq!(paths, tree.paths());
+    }
+
+    #[test]
+    fn errors_test() {
+        // every source parses as prose, so missing files stand in for the failures
+        let sources =
+            [None, Some("a\n"), None, Some("b\n"), None].map(|source| source.map(String::from));
+        let tree = tree(&scratch("errors"), &sources);
+        let paths = tree.paths();
+
+        let err = parse_with_threads(&tree).unwrap_err().to_string();
+        let failed = err
+            .lines()
+            .filter_map(|line| {
+                paths
+                    .iter()
+                    .position(|path| line.starts_with(&format!("failed to load {path}")))
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(failed, vec![0, 2, 4]);
+    }
 }
@@ -0,0 +1,49 @@
+use crate::args::Fmt;
+use anyhow::{bail, Context, Result};
+use gokuraku_config::GokurakuConfigInstance;
+use parser::prose_down_script_format;
+use std::{
+    fs,
+    io::{self, Read},
+};
+
+pub(crate) fn fmt(_conf: &GokurakuConfigInstance, options: &Fmt) -> Result<()> {
+    if options.files.is_empty() {
+        let mut source = String::new();
+        io::stdin().read_to_string(&mut source)?;
+        let formatted = prose_down_script_format(&source)?;
+        if options.check && formatted != source {
+            bail!("stdin is not formatted");
+        }
+        print!("{formatted}");
+
+        return Ok(());
+    }
+
+    let unformatted = options
+        .files
+        .iter()
+        .map(|path| {
+            let source = fs::read_to_string(path)
+                .with_context(|| format!("cannot read {}", path.display()))?;
+            let formatted = prose_down_script_format(&source)
+                .with_context(|| format!("cannot format {}", path.display()))?;
+            match (formatted == source, options.check) {
+                (true, _) => Ok(None),
+                (false, true) => Ok(Some(path)),
+                (false, false) => fs::write(path, formatted).map(|_| None).map_err(Into::into),
+            }
+        })
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+
+    unformatted
+        .iter()
+        .for_each(|path| println!("{}", path.display()));
+    match unformatted.len() {
+        0 => Ok(()),
+        n => bail!("{n} files are not formatted"),
+    }
+}
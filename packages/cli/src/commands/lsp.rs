@@ -0,0 +1,15 @@
+use super::loader;
+use crate::args::Lsp;
+use crate::lsp::serve;
+use anyhow::Result;
+use gokuraku_config::GokurakuConfigInstance;
+use lsp_server::Connection;
+
+pub(crate) fn lsp(_conf: &GokurakuConfigInstance, options: &Lsp) -> Result<()> {
+    let (connection, threads) = Connection::stdio();
+    serve(loader(&options.std)?, &connection)?;
+    drop(connection);
+    threads.join()?;
+
+    Ok(())
+}
@@ -1,7 +1,15 @@
 mod build;
 mod build_pds;
+mod fmt;
+mod lsp;
+mod repl;
 mod run;
+mod stats;
 
 pub(crate) use self::build::*;
 pub(crate) use self::build_pds::*;
+pub(crate) use self::fmt::*;
+pub(crate) use self::lsp::*;
+pub(crate) use self::repl::*;
 pub(crate) use self::run::*;
+pub(crate) use self::stats::*;
@@ -0,0 +1,84 @@
+use super::loader;
+use crate::args::Repl;
+use anyhow::Result;
+use gokuraku_config::GokurakuConfigInstance;
+use parser::{ProseDownScriptReply, ProseDownScriptSession};
+use rustyline::{error::ReadlineError, Config, Editor};
+use std::{env, io, path::PathBuf};
+
+const HISTORY_FILE: &str = ".gokuraku_history";
+const HISTORY_SIZE: usize = 1000;
+
+pub(crate) fn repl(_conf: &GokurakuConfigInstance, options: &Repl) -> Result<()> {
+    let mut session = ProseDownScriptSession::new(loader(&options.std)?)?;
+    let history = env::var_os("HOME").map(|home| PathBuf::from(home).join(HISTORY_FILE));
+    let config = Config::builder()
+        .max_history_size(HISTORY_SIZE)
+        .history_ignore_dups(true)
+        .build();
+    let mut editor = Editor::<()>::with_config(config);
+    if let Some(history) = &history {
+        // there is no history file before the first session
+        let _ = editor.load_history(history);
+    }
+
+    if let Some(file) = &options.file {
+        eval(&mut session, &format!(":load {}", file.display()));
+    }
+    println!(":help lists the commands");
+
+    while let Some(input) = read_input(&mut editor)? {
+        if input.trim().is_empty() {
+            continue;
+        }
+        editor.add_history_entry(input.as_str());
+        if let Some(history) = &history {
+            editor.save_history(history)?;
+        }
+        if let Some(ProseDownScriptReply::Quit) = eval(&mut session, &input) {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// A line, and the lines after it while its braces are not closed.
+fn read_input(editor: &mut Editor<()>) -> Result<Option<String>> {
+    let mut input = match read_line(editor, "pds> ")? {
+        Some(line) => line,
+        None => return Ok(None),
+    };
+    while input.matches('{').count() > input.matches('}').count() {
+        match read_line(editor, "   | ")? {
+            Some(line) => input = input + "\n" + &line,
+            None => break,
+        }
+    }
+
+    Ok(Some(input))
+}
+
+/// The next line, `None` at the end of input and an empty line when it is
+/// interrupted.
+fn read_line(editor: &mut Editor<()>, prompt: &str) -> Result<Option<String>> {
+    match editor.readline(prompt) {
+        Ok(line) => Ok(Some(line)),
+        Err(ReadlineError::Interrupted) => Ok(Some(String::new())),
+        Err(ReadlineError::Eof) => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn eval(session: &mut ProseDownScriptSession, input: &str) -> Option<ProseDownScriptReply> {
+    match session.input(input, &mut io::stdin().lock(), &mut io::stdout()) {
+        Ok(reply) => {
+            print!("{reply}");
+            Some(reply)
+        }
+        Err(err) => {
+            eprintln!("error: {err:#}");
+            None
+        }
+    }
+}
@@ -1,11 +1,23 @@
-use crate::args::Run;
+use crate::args::{Run, StdOptions};
 use anyhow::Result;
 use gokuraku_config::GokurakuConfigInstance;
-use parser::prose_down_script_run;
-use std::fs;
+use parser::{prose_down_script_run_file, ProseDownScriptLoader};
+use std::env;
 
 pub(crate) fn run(_conf: &GokurakuConfigInstance, options: &Run) -> Result<()> {
-    prose_down_script_run(&fs::read_to_string(&options.file)?)?;
+    prose_down_script_run_file(&loader(&options.std)?, &options.file)?;
 
     Ok(())
 }
+
+/// A loader rooted at the working directory.
+pub(crate) fn loader(options: &StdOptions) -> Result<ProseDownScriptLoader> {
+    let loader = ProseDownScriptLoader::new(env::current_dir()?);
+    let loader = match (&options.std, options.no_std) {
+        (Some(std), _) => loader.with_std(std),
+        (None, false) => loader.with_bundled_std(),
+        (None, true) => loader,
+    };
+
+    Ok(loader)
+}
@@ -0,0 +1,245 @@
+use super::build::parse_index_tree;
+use crate::args::Stats;
+use anyhow::Result;
+use build_adapter::layout::ManuscriptLayout;
+use gokuraku_config::GokurakuConfigInstance;
+use parser::ast::{Block, Document, Inline};
+use serde::Serialize;
+
+#[derive(Debug, Default, Serialize)]
+struct DocumentStats {
+    path: String,
+    characters: usize,
+    dialogue_characters: usize,
+    paragraphs: usize,
+    lines: usize,
+    pages: usize,
+    dialogue_ratio: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct ManuscriptStats {
+    documents: Vec<DocumentStats>,
+    total: DocumentStats,
+}
+
+pub(crate) fn stats(conf: &GokurakuConfigInstance, options: &Stats) -> Result<()> {
+    let documents = parse_index_tree(&conf.index().resolve()?, None)?
+        .iter()
+        .map(|(path, doc)| document_stats(path, doc, &ManuscriptLayout::default()))
+        .collect::<Vec<_>>();
+    let total = total_stats(&documents);
+    let stats = ManuscriptStats { documents, total };
+
+    match options.json {
+        true => println!("{}", serde_json::to_string_pretty(&stats)?),
+        false => println!("{}", table(&stats)),
+    };
+
+    Ok(())
+}
+
+/// Counts a document. Lines and pages are those of the document laid out on
+/// 400-character genkō yōshi.
+fn document_stats(path: &str, doc: &Document, layout: &ManuscriptLayout) -> DocumentStats {
+    let stats = doc.iter().fold(
+        DocumentStats {
+            path: path.to_owned(),
+            ..Default::default()
+        },
+        |mut acc, block| {
+            match block {
+                Block::Paragraph(inlines) => {
+                    let text = strip_ruby(&inlines.iter().map(inline_text).collect::<String>());
+                    let characters = text.chars().count();
+
+                    acc.characters += characters;
+                    acc.dialogue_characters += dialogue_characters(&text);
+                    acc.paragraphs += 1;
+                }
+                Block::EmptyLine | Block::ThemanticBreak | Block::PdsScript(..) => {}
+            };
+
+            acc
+        },
+    );
+
+    let pages = layout.pages(doc);
+
+    with_ratio(DocumentStats {
+        lines: pages.iter().map(|page| page.0.len()).sum(),
+        pages: pages.len(),
+        ..stats
+    })
+}
+
+fn total_stats(documents: &[DocumentStats]) -> DocumentStats {
+    let total = documents.iter().fold(
+        DocumentStats {
+            path: "total".to_string(),
+            ..Default::default()
+        },
+        |mut acc, doc| {
+            acc.characters += doc.characters;
+            acc.dialogue_characters += doc.dialogue_characters;
+            acc.paragraphs += doc.paragraphs;
+            acc.lines += doc.lines;
+            // every document starts on a new page
+            acc.pages += doc.pages;
+
+            acc
+        },
+    );
+
+    with_ratio(total)
+}
+
+fn with_ratio(stats: DocumentStats) -> DocumentStats {
+    DocumentStats {
+        dialogue_ratio: match stats.characters {
+            0 => 0.0,
+            n => stats.dialogue_characters as f64 / n as f64,
+        },
+        ..stats
+    }
+}
+
+fn inline_text(inline: &Inline) -> String {
+    match inline {
+        Inline::Text(value) if matches!(value.as_str(), "\r\n" | "\n" | "\r") => String::new(),
+        Inline::Text(value) => value.to_owned(),
+        Inline::Number(value) => value.to_owned(),
+        Inline::Expr(..) => String::new(),
+    }
+}
+
+/// Removes ruby annotations, `《かんじ》`, and the `|` / `｜` that marks the
+/// start of the text they annotate. A bar is kept unless the text after it
+/// runs up to a closed annotation.
+fn strip_ruby(text: &str) -> String {
+    let mut ret = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        let at = c.len_utf8();
+        let (kept, len) = match c {
+            '|' | '｜' => {
+                ruby_base(&rest[at..]).map_or((&rest[..at], at), |(base, len)| (base, at + len))
+            }
+            '《' => annotation(rest).map_or((&rest[..at], at), |len| ("", len)),
+            _ => (&rest[..at], at),
+        };
+        ret.push_str(kept);
+        rest = &rest[len..];
+    }
+
+    ret
+}
+
+/// The text a bar marks and its length together with the annotation right
+/// after it.
+fn ruby_base(text: &str) -> Option<(&str, usize)> {
+    let end = text.find(['|', '｜', '《', '》', '\n'])?;
+    let len = annotation(&text[end..])?;
+
+    (end > 0).then(|| (&text[..end], end + len))
+}
+
+/// The length of the `《ruby》` `text` starts with.
+fn annotation(text: &str) -> Option<usize> {
+    let ruby = text.strip_prefix('《')?;
+    let end = ruby.find(['《', '》', '\n'])?;
+
+    ruby[end..]
+        .starts_with('》')
+        .then(|| '《'.len_utf8() + end + '》'.len_utf8())
+}
+
+/// Counts the characters of dialogue, brackets included.
+fn dialogue_characters(text: &str) -> usize {
+    text.chars()
+        .fold((0, 0), |(count, depth), c| match c {
+            '「' | '『' => (count + 1, depth + 1),
+            '」' | '』' if depth > 0 => (count + 1, depth - 1),
+            _ if depth > 0 => (count + 1, depth),
+            _ => (count, depth),
+        })
+        .0
+}
+
+fn table(stats: &ManuscriptStats) -> String {
+    let header = format!(
+        "{:<40} {:>10} {:>10} {:>8} {:>6} {:>9}",
+        "path", "characters", "paragraphs", "lines", "pages", "dialogue"
+    );
+
+    stats
+        .documents
+        .iter()
+        .chain(std::iter::once(&stats.total))
+        .fold(header, |table, doc| {
+            format!(
+                "{table}\n{:<40} {:>10} {:>10} {:>8} {:>6} {:>8.1}%",
+                doc.path,
+                doc.characters,
+                doc.paragraphs,
+                doc.lines,
+                doc.pages,
+                doc.dialogue_ratio * 100.0
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(path: &str, text: &str) -> DocumentStats {
+        let doc = parser::prose_down_parse(text).unwrap();
+
+        document_stats(path, &doc, &ManuscriptLayout::default())
+    }
+
+    #[test]
+    fn strip_ruby_test() {
+        assert_eq!(strip_ruby("｜漢字《かんじ》を読む"), "漢字を読む");
+        assert_eq!(strip_ruby("漢字《かんじ》|ルビ《るび》"), "漢字ルビ");
+        assert_eq!(strip_ruby("a|b の後に｜漢字《かんじ》"), "a|b の後に漢字");
+        assert_eq!(strip_ruby("｜《》と《閉じない"), "｜と《閉じない");
+        assert_eq!(strip_ruby("|bar》"), "|bar》");
+    }
+
+    #[test]
+    fn dialogue_characters_test() {
+        assert_eq!(dialogue_characters("彼は「はい」と言った"), 4);
+        assert_eq!(dialogue_characters("『「あ」』い"), 5);
+        assert_eq!(dialogue_characters("」あ"), 0);
+    }
+
+    #[test]
+    fn document_stats_test() {
+        let doc = stats("a.pd", "「｜漢字《かんじ》」と言う\n\nあいう\n");
+
+        assert_eq!(
+            (doc.characters, doc.dialogue_characters, doc.paragraphs),
+            (10, 4, 2)
+        );
+        assert_eq!((doc.lines, doc.pages), (3, 1));
+        assert!((doc.dialogue_ratio - 4.0 / 10.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn table_test() {
+        let documents = vec![stats("a.pd", "「あ」い\n"), stats("b.pd", "う\n")];
+        let total = total_stats(&documents);
+        let table = table(&ManuscriptStats { documents, total });
+        let rows = table.lines().collect::<Vec<_>>();
+
+        assert_eq!(rows.len(), 4);
+        assert!(rows[0].starts_with("path"));
+        assert_eq!(
+            rows[3].split_whitespace().collect::<Vec<_>>(),
+            ["total", "5", "2", "2", "2", "60.0%"]
+        );
+    }
+}
@@ -0,0 +1,789 @@
+use anyhow::Result;
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::{
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+        PublishDiagnostics,
+    },
+    request::{
+        Completion, DocumentSymbolRequest, Formatting, GotoDefinition, HoverRequest, Request as _,
+        SemanticTokensFullRequest,
+    },
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams, CompletionResponse,
+    Diagnostic, DiagnosticSeverity, DocumentFormattingParams, DocumentSymbol, DocumentSymbolParams,
+    DocumentSymbolResponse, GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverContents,
+    HoverParams, HoverProviderCapability, InitializeResult, Location, MarkupContent, MarkupKind,
+    OneOf, Position, PublishDiagnosticsParams, Range, SemanticToken, SemanticTokenType,
+    SemanticTokens, SemanticTokensFullOptions, SemanticTokensLegend, SemanticTokensOptions,
+    SemanticTokensParams, SemanticTokensResult, SemanticTokensServerCapabilities,
+    ServerCapabilities, ServerInfo, SymbolKind, TextDocumentPositionParams,
+    TextDocumentSyncCapability, TextDocumentSyncKind, TextEdit, Url,
+};
+use parser::{
+    ast::Span, ir::resolve::Namespace, ProseDownScriptAnalysis, ProseDownScriptLoader,
+    ProseDownScriptSeverity, ProseDownScriptSymbol, ProseDownScriptToken, ProseDownScriptTokenKind,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::{collections::HashMap, fs};
+
+/// The semantic token types, in the order the kinds of tokens are numbered.
+const TOKEN_TYPES: [SemanticTokenType; 12] = [
+    SemanticTokenType::KEYWORD,
+    SemanticTokenType::COMMENT,
+    SemanticTokenType::VARIABLE,
+    SemanticTokenType::EVENT,
+    SemanticTokenType::MACRO,
+    SemanticTokenType::TYPE,
+    SemanticTokenType::TYPE_PARAMETER,
+    SemanticTokenType::INTERFACE,
+    SemanticTokenType::NAMESPACE,
+    SemanticTokenType::OPERATOR,
+    SemanticTokenType::STRING,
+    SemanticTokenType::NUMBER,
+];
+
+/// A language server for scripts and documents. Documents are analyzed as a
+/// whole whenever they change, the modules they import as they are saved.
+pub(crate) struct Server {
+    loader: ProseDownScriptLoader,
+    documents: HashMap<Url, Document>,
+}
+
+struct Document {
+    text: String,
+    analysis: ProseDownScriptAnalysis,
+}
+
+/// Serves the messages of `connection` from `initialize` until `exit`.
+pub(crate) fn serve(loader: ProseDownScriptLoader, connection: &Connection) -> Result<()> {
+    let (id, _) = connection.initialize_start()?;
+    connection.initialize_finish(id, serde_json::to_value(initialize_result())?)?;
+    let mut server = Server {
+        loader,
+        documents: HashMap::new(),
+    };
+
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) if connection.handle_shutdown(&request)? => break,
+            Message::Request(request) => connection.sender.send(server.respond(request).into())?,
+            Message::Notification(notification) => {
+                if let Some(diagnostics) = server.notify(notification) {
+                    connection.sender.send(diagnostics.into())?;
+                }
+            }
+            Message::Response(_) => {}
+        }
+    }
+
+    Ok(())
+}
+
+impl Server {
+    fn respond(&mut self, request: Request) -> Response {
+        let id = request.id.clone();
+        let result = match request.method.as_str() {
+            HoverRequest::METHOD => self.on::<HoverRequest>(request, Self::hover),
+            GotoDefinition::METHOD => self.on::<GotoDefinition>(request, Self::definition),
+            Completion::METHOD => self.on::<Completion>(request, Self::completion),
+            DocumentSymbolRequest::METHOD => {
+                self.on::<DocumentSymbolRequest>(request, Self::outline)
+            }
+            SemanticTokensFullRequest::METHOD => {
+                self.on::<SemanticTokensFullRequest>(request, Self::semantic_tokens)
+            }
+            Formatting::METHOD => self.on::<Formatting>(request, Self::formatting),
+            method => Err((ErrorCode::MethodNotFound, format!("no method {method}"))),
+        };
+
+        match result {
+            Ok(result) => Response::new_ok(id, result),
+            Err((code, message)) => Response::new_err(id, code as i32, message),
+        }
+    }
+
+    /// The result of `handle` for the params of `request`.
+    fn on<R>(
+        &self,
+        request: Request,
+        handle: impl FnOnce(&Self, R::Params) -> R::Result,
+    ) -> Result<Value, (ErrorCode, String)>
+    where
+        R: lsp_types::request::Request,
+        R::Params: DeserializeOwned,
+        R::Result: Serialize,
+    {
+        let (_, params) = request
+            .extract::<R::Params>(R::METHOD)
+            .map_err(|err| (ErrorCode::InvalidParams, err.to_string()))?;
+
+        serde_json::to_value(handle(self, params))
+            .map_err(|err| (ErrorCode::InternalError, err.to_string()))
+    }
+
+    /// The diagnostics of the document a notification changes.
+    fn notify(&mut self, notification: Notification) -> Option<Notification> {
+        let (uri, diagnostics) = match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params = extract::<DidOpenTextDocument>(notification)?;
+                let uri = params.text_document.uri;
+                let diagnostics = self.open(&uri, params.text_document.text);
+
+                (uri, diagnostics)
+            }
+            // the whole text is synchronized, the last change has it
+            DidChangeTextDocument::METHOD => {
+                let params = extract::<DidChangeTextDocument>(notification)?;
+                let text = params.content_changes.into_iter().last()?.text;
+                let uri = params.text_document.uri;
+                let diagnostics = self.open(&uri, text);
+
+                (uri, diagnostics)
+            }
+            DidCloseTextDocument::METHOD => {
+                let uri = extract::<DidCloseTextDocument>(notification)?
+                    .text_document
+                    .uri;
+                self.documents.remove(&uri);
+
+                (uri, Vec::new())
+            }
+            _ => return None,
+        };
+
+        Some(Notification::new(
+            PublishDiagnostics::METHOD.to_string(),
+            PublishDiagnosticsParams {
+                uri,
+                diagnostics,
+                version: None,
+            },
+        ))
+    }
+
+    fn open(&mut self, uri: &Url, text: String) -> Vec<Diagnostic> {
+        let analysis = match uri.to_file_path() {
+            Ok(_) if is_document(uri) => {
+                parser::prose_down_script_analyze_document(&self.loader, &text)
+            }
+            Ok(path) => parser::prose_down_script_analyze(&self.loader, &path, &text),
+            Err(_) => ProseDownScriptAnalysis::default(),
+        };
+        let diagnostics = analysis
+            .diagnostics
+            .iter()
+            .map(|diagnostic| Diagnostic {
+                range: range(&text, diagnostic.span),
+                severity: Some(match diagnostic.severity {
+                    ProseDownScriptSeverity::Error => DiagnosticSeverity::ERROR,
+                    ProseDownScriptSeverity::Warning => DiagnosticSeverity::WARNING,
+                }),
+                source: Some("gokuraku".to_string()),
+                message: diagnostic.message.clone(),
+                ..Default::default()
+            })
+            .collect();
+        self.documents
+            .insert(uri.clone(), Document { text, analysis });
+
+        diagnostics
+    }
+
+    fn hover(&self, params: HoverParams) -> Option<Hover> {
+        let symbol = self.symbol_at(&params.text_document_position_params)?;
+        let signature = match &symbol.ty {
+            Some(ty) => format!("{} :: {ty}", symbol.ident),
+            None => symbol.ident.clone(),
+        };
+
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: format!("```pds\n{signature}\n```\n{}", symbol.namespace),
+            }),
+            range: None,
+        })
+    }
+
+    fn definition(&self, params: GotoDefinitionParams) -> Option<GotoDefinitionResponse> {
+        let at = params.text_document_position_params;
+        let symbol = self.symbol_at(&at)?;
+        let location = match &symbol.path {
+            None => Location {
+                range: range(&self.documents[&at.text_document.uri].text, symbol.span),
+                uri: at.text_document.uri,
+            },
+            // the bundled standard library is not in a file
+            Some(path) => Location {
+                range: range(&fs::read_to_string(path).ok()?, symbol.span),
+                uri: Url::from_file_path(path).ok()?,
+            },
+        };
+
+        Some(GotoDefinitionResponse::Scalar(location))
+    }
+
+    fn completion(&self, params: CompletionParams) -> Option<CompletionResponse> {
+        let document = self
+            .documents
+            .get(&params.text_document_position.text_document.uri)?;
+        let items = symbols(&document.analysis)
+            .map(|symbol| CompletionItem {
+                label: symbol.ident.clone(),
+                kind: Some(completion_kind(symbol.namespace)),
+                detail: symbol.ty.clone(),
+                ..Default::default()
+            })
+            .collect();
+
+        Some(CompletionResponse::Array(items))
+    }
+
+    /// The sections of a document and the names a file defines.
+    fn outline(&self, params: DocumentSymbolParams) -> Option<DocumentSymbolResponse> {
+        let Document { text, analysis } = self.documents.get(&params.text_document.uri)?;
+        let sections = analysis.sections.iter().map(|section| {
+            symbol(
+                text,
+                section.title.clone(),
+                None,
+                SymbolKind::NAMESPACE,
+                section.span,
+            )
+        });
+        let definitions = analysis.definitions.iter().map(|definition| {
+            symbol(
+                text,
+                definition.ident.clone(),
+                definition.ty.clone(),
+                symbol_kind(definition.namespace),
+                definition.span,
+            )
+        });
+
+        Some(DocumentSymbolResponse::Nested(
+            sections.chain(definitions).collect(),
+        ))
+    }
+
+    /// The tokens as the relative positions, lengths and types LSP encodes
+    /// them in, none while the text does not parse.
+    fn semantic_tokens(&self, params: SemanticTokensParams) -> Option<SemanticTokensResult> {
+        let uri = &params.text_document.uri;
+        let Document { text, .. } = self.documents.get(uri)?;
+        let tokens = match is_document(uri) {
+            true => parser::prose_down_document_tokens(text),
+            false => parser::prose_down_script_tokens(text),
+        }
+        .unwrap_or_default();
+
+        let lines = text.lines().collect::<Vec<_>>();
+        let data = tokens
+            .iter()
+            .filter_map(|ProseDownScriptToken { span, kind }| {
+                let line = lines.get(span.start.0 - 1)?;
+                let units = |columns: usize| -> u32 {
+                    line.chars()
+                        .take(columns - 1)
+                        .map(|c| c.len_utf16() as u32)
+                        .sum()
+                };
+                let start = units(span.start.1);
+
+                Some((
+                    span.start.0 as u32 - 1,
+                    start,
+                    units(span.end.1) - start,
+                    *kind,
+                ))
+            })
+            .scan((0, 0), |previous, (line, start, length, kind)| {
+                let delta_start = match line == previous.0 {
+                    true => start - previous.1,
+                    false => start,
+                };
+                let delta_line = line - previous.0;
+                *previous = (line, start);
+
+                Some(SemanticToken {
+                    delta_line,
+                    delta_start,
+                    length,
+                    token_type: token_type(kind),
+                    token_modifiers_bitset: 0,
+                })
+            })
+            .collect();
+
+        Some(SemanticTokensResult::Tokens(SemanticTokens {
+            result_id: None,
+            data,
+        }))
+    }
+
+    /// The whole text formatted as one edit, none for a document or a script
+    /// which does not parse.
+    fn formatting(&self, params: DocumentFormattingParams) -> Option<Vec<TextEdit>> {
+        let uri = &params.text_document.uri;
+        let Document { text, .. } = self.documents.get(uri)?;
+        if is_document(uri) {
+            return None;
+        }
+        let formatted = match parser::prose_down_script_format(text).ok()? {
+            formatted if formatted == *text => return Some(Vec::new()),
+            formatted => formatted,
+        };
+
+        let last = text.rsplit('\n').next().unwrap_or_default();
+        let end = Position {
+            line: text.matches('\n').count() as u32,
+            character: last.chars().map(|c| c.len_utf16() as u32).sum(),
+        };
+
+        Some(vec![TextEdit {
+            range: Range {
+                start: Position::default(),
+                end,
+            },
+            new_text: formatted,
+        }])
+    }
+
+    fn symbol_at(&self, at: &TextDocumentPositionParams) -> Option<&ProseDownScriptSymbol> {
+        let document = self.documents.get(&at.text_document.uri)?;
+        let line = document.text.lines().nth(at.position.line as usize)?;
+        let word = word_at(line, at.position.character as usize)?;
+
+        // `Text.words` is `words` when `Text` is not an alias
+        let unqualified = word.rsplit_once('.').map(|(_, ident)| ident);
+        let symbol = [Some(word.as_str()), unqualified]
+            .into_iter()
+            .flatten()
+            .find_map(|word| symbols(&document.analysis).find(|symbol| symbol.ident == word));
+
+        symbol
+    }
+}
+
+fn initialize_result() -> InitializeResult {
+    InitializeResult {
+        capabilities: ServerCapabilities {
+            // the whole text on every change
+            text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+            hover_provider: Some(HoverProviderCapability::Simple(true)),
+            definition_provider: Some(OneOf::Left(true)),
+            completion_provider: Some(CompletionOptions {
+                trigger_characters: Some(vec!["*".to_string(), ".".to_string()]),
+                ..Default::default()
+            }),
+            document_symbol_provider: Some(OneOf::Left(true)),
+            document_formatting_provider: Some(OneOf::Left(true)),
+            semantic_tokens_provider: Some(
+                SemanticTokensServerCapabilities::SemanticTokensOptions(SemanticTokensOptions {
+                    legend: SemanticTokensLegend {
+                        token_types: TOKEN_TYPES.to_vec(),
+                        token_modifiers: Vec::new(),
+                    },
+                    full: Some(SemanticTokensFullOptions::Bool(true)),
+                    ..Default::default()
+                }),
+            ),
+            ..Default::default()
+        },
+        server_info: Some(ServerInfo {
+            name: "gokuraku".to_string(),
+            version: Some(env!("CARGO_PKG_VERSION").to_string()),
+        }),
+    }
+}
+
+fn extract<N>(notification: Notification) -> Option<N::Params>
+where
+    N: lsp_types::notification::Notification,
+    N::Params: DeserializeOwned,
+{
+    notification.extract(N::METHOD).ok()
+}
+
+/// A document rather than a script, by its extension.
+fn is_document(uri: &Url) -> bool {
+    uri.path().ends_with(".pd")
+}
+
+fn symbol(
+    text: &str,
+    name: String,
+    detail: Option<String>,
+    kind: SymbolKind,
+    span: Span,
+) -> DocumentSymbol {
+    #[allow(deprecated)]
+    DocumentSymbol {
+        name,
+        detail,
+        kind,
+        tags: None,
+        deprecated: None,
+        range: range(text, span),
+        selection_range: range(text, span),
+        children: None,
+    }
+}
+
+/// The names a document defines, then those it imports.
+fn symbols(analysis: &ProseDownScriptAnalysis) -> impl Iterator<Item = &ProseDownScriptSymbol> {
+    analysis.definitions.iter().chain(&analysis.imports)
+}
+
+fn token_type(kind: ProseDownScriptTokenKind) -> u32 {
+    match kind {
+        ProseDownScriptTokenKind::Keyword => 0,
+        ProseDownScriptTokenKind::Comment => 1,
+        ProseDownScriptTokenKind::Variable => 2,
+        ProseDownScriptTokenKind::Handler => 3,
+        ProseDownScriptTokenKind::Inst => 4,
+        ProseDownScriptTokenKind::Type => 5,
+        ProseDownScriptTokenKind::TypeVariable => 6,
+        ProseDownScriptTokenKind::Trait => 7,
+        ProseDownScriptTokenKind::Module => 8,
+        ProseDownScriptTokenKind::Operator => 9,
+        ProseDownScriptTokenKind::Text => 10,
+        ProseDownScriptTokenKind::Number => 11,
+    }
+}
+
+fn completion_kind(namespace: Namespace) -> CompletionItemKind {
+    match namespace {
+        Namespace::Value | Namespace::Inst => CompletionItemKind::FUNCTION,
+        Namespace::Constructor => CompletionItemKind::CONSTRUCTOR,
+        Namespace::Trait => CompletionItemKind::INTERFACE,
+        Namespace::Type => CompletionItemKind::STRUCT,
+        Namespace::Handler => CompletionItemKind::EVENT,
+    }
+}
+
+fn symbol_kind(namespace: Namespace) -> SymbolKind {
+    match namespace {
+        Namespace::Value | Namespace::Inst => SymbolKind::FUNCTION,
+        Namespace::Trait => SymbolKind::INTERFACE,
+        Namespace::Constructor => SymbolKind::ENUM_MEMBER,
+        Namespace::Type => SymbolKind::STRUCT,
+        Namespace::Handler => SymbolKind::EVENT,
+    }
+}
+
+/// The identifier or the operator at the UTF-16 `character` of `line`, a
+/// handler with its `*`.
+fn word_at(line: &str, character: usize) -> Option<String> {
+    let chars = line.chars().collect::<Vec<_>>();
+    let at = chars
+        .iter()
+        .scan(0, |units, c| {
+            *units += c.len_utf16();
+            Some(*units)
+        })
+        .take_while(|units| *units <= character)
+        .count();
+
+    let before = at.checked_sub(1).and_then(|before| chars.get(before));
+    let ident = [chars.get(at), before]
+        .into_iter()
+        .flatten()
+        .find_map(|c| match c {
+            c if is_ident(c) => Some(true),
+            c if is_operator(c) => Some(false),
+            _ => None,
+        })?;
+    let class = |c: &char| match ident {
+        true => is_ident(c),
+        false => is_operator(c),
+    };
+    let start = chars[..at]
+        .iter()
+        .rposition(|c| !class(c))
+        .map_or(0, |position| position + 1);
+    let end = chars[at..]
+        .iter()
+        .position(|c| !class(c))
+        .map_or(chars.len(), |position| at + position);
+    let start = match ident && start > 0 && chars[start - 1] == '*' {
+        true => start - 1,
+        false => start,
+    };
+
+    Some(chars[start..end].iter().collect::<String>()).filter(|word| !word.is_empty())
+}
+
+fn is_ident(c: &char) -> bool {
+    c.is_alphanumeric() || *c == '.'
+}
+
+fn is_operator(c: &char) -> bool {
+    "+-*/%<>=!&|.^$:".contains(*c)
+}
+
+/// The LSP range of `span` in `text`, the rest of the line when it ends where
+/// it starts.
+fn range(text: &str, span: Span) -> Range {
+    let end = match span.end > span.start {
+        true => span.end,
+        false => (
+            span.start.0,
+            text.lines()
+                .nth(span.start.0.saturating_sub(1))
+                .map_or(span.start.1, |line| line.chars().count() + 1)
+                .max(span.start.1),
+        ),
+    };
+
+    Range {
+        start: position(text, span.start),
+        end: position(text, end),
+    }
+}
+
+/// The 0-based line and UTF-16 column of the 1-based line and column.
+fn position(text: &str, (line, column): (usize, usize)) -> Position {
+    let character =
+        text.lines()
+            .nth(line.saturating_sub(1))
+            .map_or(column.saturating_sub(1), |line| {
+                line.chars()
+                    .take(column.saturating_sub(1))
+                    .map(char::len_utf16)
+                    .sum()
+            });
+
+    Position {
+        line: line.saturating_sub(1) as u32,
+        character: character as u32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lsp_server::{RequestId, ResponseError};
+    use lsp_types::{
+        notification::{Exit, Initialized},
+        request::{Initialize, Shutdown},
+        DidOpenTextDocumentParams, InitializeParams, InitializedParams, TextDocumentIdentifier,
+        TextDocumentItem,
+    };
+    use std::{env, thread::JoinHandle};
+
+    /// A client of a server on a thread of its own, connected in memory.
+    struct Client {
+        connection: Connection,
+        server: JoinHandle<Result<()>>,
+        requests: i32,
+    }
+
+    impl Client {
+        fn initialized() -> Self {
+            let (server, connection) = Connection::memory();
+            let loader = ProseDownScriptLoader::default().with_bundled_std();
+            let mut client = Self {
+                connection,
+                server: std::thread::spawn(move || serve(loader, &server)),
+                requests: 0,
+            };
+            let result = client.request::<Initialize>(InitializeParams::default());
+            assert_eq!(result.unwrap(), initialize_result());
+            client.notify::<Initialized>(InitializedParams {});
+
+            client
+        }
+
+        fn request<R: lsp_types::request::Request>(
+            &mut self,
+            params: R::Params,
+        ) -> Result<R::Result, ResponseError> {
+            self.requests += 1;
+            let id = RequestId::from(self.requests);
+            let request = Request::new(id.clone(), R::METHOD.to_string(), params);
+            self.connection.sender.send(request.into()).unwrap();
+
+            match self.connection.receiver.recv().unwrap() {
+                Message::Response(response) if response.id == id => match response.error {
+                    Some(error) => Err(error),
+                    None => Ok(serde_json::from_value(response.result.unwrap()).unwrap()),
+                },
+                message => panic!("{message:?} is not the response to {id}"),
+            }
+        }
+
+        fn notify<N: lsp_types::notification::Notification>(&self, params: N::Params) {
+            let notification = Notification::new(N::METHOD.to_string(), params);
+            self.connection.sender.send(notification.into()).unwrap();
+        }
+
+        /// Opens a script, and the diagnostics the server publishes for it.
+        fn open(&self, uri: &Url, text: &str) -> Vec<Diagnostic> {
+            self.notify::<DidOpenTextDocument>(DidOpenTextDocumentParams {
+                text_document: TextDocumentItem {
+                    uri: uri.clone(),
+                    language_id: "pds".to_string(),
+                    version: 0,
+                    text: text.to_string(),
+                },
+            });
+
+            match self.connection.receiver.recv().unwrap() {
+                Message::Notification(notification) => {
+                    let params = notification
+                        .extract::<PublishDiagnosticsParams>(PublishDiagnostics::METHOD)
+                        .unwrap();
+                    assert_eq!(&params.uri, uri);
+                    params.diagnostics
+                }
+                message => panic!("{message:?} is not a notification"),
+            }
+        }
+
+        fn shutdown(mut self) {
+            self.request::<Shutdown>(()).unwrap();
+            self.notify::<Exit>(());
+
+            self.server.join().unwrap().unwrap();
+        }
+    }
+
+    const SCRIPT: &str = "double :: Int -> Int\ndouble x = x * 2\n\nmain = double 21\n";
+
+    fn script_uri() -> Url {
+        Url::from_file_path(env::temp_dir().join("gokuraku-lsp").join("a.pds")).unwrap()
+    }
+
+    fn at(uri: &Url, line: u32, character: u32) -> TextDocumentPositionParams {
+        TextDocumentPositionParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            position: Position { line, character },
+        }
+    }
+
+    fn range((start_line, start): (u32, u32), (end_line, end): (u32, u32)) -> Range {
+        Range {
+            start: Position::new(start_line, start),
+            end: Position::new(end_line, end),
+        }
+    }
+
+    #[test]
+    fn diagnostics_test() {
+        let client = Client::initialized();
+        let diagnostics = client.open(&script_uri(), "main = missing 1\n");
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start, Position::new(0, 0));
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::ERROR));
+        assert!(diagnostics[0]
+            .message
+            .contains("undefined reference missing"));
+        assert!(client.open(&script_uri(), SCRIPT).is_empty());
+
+        client.shutdown();
+    }
+
+    #[test]
+    fn hover_test() {
+        let mut client = Client::initialized();
+        let uri = script_uri();
+        client.open(&uri, SCRIPT);
+
+        let hover = client.request::<HoverRequest>(HoverParams {
+            text_document_position_params: at(&uri, 3, 9),
+            work_done_progress_params: Default::default(),
+        });
+        let contents = match hover.unwrap().unwrap().contents {
+            HoverContents::Markup(contents) => contents.value,
+            contents => panic!("{contents:?}"),
+        };
+        assert_eq!(contents, "```pds\ndouble :: Int -> Int\n```\nreference");
+
+        let nothing = client.request::<HoverRequest>(HoverParams {
+            text_document_position_params: at(&uri, 2, 0),
+            work_done_progress_params: Default::default(),
+        });
+        assert_eq!(nothing.unwrap(), None);
+
+        client.shutdown();
+    }
+
+    #[test]
+    fn definition_test() {
+        let mut client = Client::initialized();
+        let uri = script_uri();
+        client.open(&uri, SCRIPT);
+
+        let definition = client.request::<GotoDefinition>(GotoDefinitionParams {
+            text_document_position_params: at(&uri, 3, 7),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        });
+        assert_eq!(
+            definition.unwrap(),
+            Some(GotoDefinitionResponse::Scalar(Location {
+                uri,
+                range: range((0, 0), (0, 20)),
+            }))
+        );
+
+        client.shutdown();
+    }
+
+    #[test]
+    fn semantic_tokens_test() {
+        let mut client = Client::initialized();
+        let uri = script_uri();
+        client.open(&uri, "main = double 21\n");
+
+        let tokens = client.request::<SemanticTokensFullRequest>(SemanticTokensParams {
+            text_document: TextDocumentIdentifier { uri },
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        });
+        let data = match tokens.unwrap() {
+            Some(SemanticTokensResult::Tokens(tokens)) => tokens.data,
+            tokens => panic!("{tokens:?}"),
+        };
+        let token = |delta_start, length, kind| SemanticToken {
+            delta_line: 0,
+            delta_start,
+            length,
+            token_type: token_type(kind),
+            token_modifiers_bitset: 0,
+        };
+        assert_eq!(
+            data,
+            [
+                token(0, 4, ProseDownScriptTokenKind::Variable),
+                token(5, 1, ProseDownScriptTokenKind::Operator),
+                token(2, 6, ProseDownScriptTokenKind::Variable),
+                token(7, 2, ProseDownScriptTokenKind::Number),
+            ]
+        );
+
+        client.shutdown();
+    }
+
+    #[test]
+    fn method_not_found_test() {
+        let mut client = Client::initialized();
+
+        let error = client
+            .request::<lsp_types::request::References>(lsp_types::ReferenceParams {
+                text_document_position: at(&script_uri(), 0, 0),
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+                context: lsp_types::ReferenceContext {
+                    include_declaration: true,
+                },
+            })
+            .unwrap_err();
+        assert_eq!(error.code, ErrorCode::MethodNotFound as i32);
+
+        client.shutdown();
+    }
+}
@@ -1,5 +1,7 @@
 mod args;
+mod cache;
 mod commands;
+mod lsp;
 
 use anyhow::{Context, Result};
 use args::Args;
@@ -17,9 +19,13 @@ fn main() {
     };
 
     match args.command {
-        Commands::Build(_) => commands::build(&config),
+        Commands::Build(options) => commands::build(&config, &options),
+        Commands::Fmt(options) => commands::fmt(&config, &options),
+        Commands::Lsp(options) => commands::lsp(&config, &options),
+        Commands::Repl(options) => commands::repl(&config, &options),
         Commands::Run(options) => commands::run(&config, &options),
         Commands::BuildPds(options) => commands::build_pds(&config, &options),
+        Commands::Stats(options) => commands::stats(&config, &options),
     }
     .unwrap();
 }
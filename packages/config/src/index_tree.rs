@@ -0,0 +1,336 @@
+use crate::{IndexGlob, IndexTree};
+use anyhow::{anyhow, Context, Result};
+use glob::Pattern;
+use std::{
+    cmp::Ordering,
+    fs,
+    path::{Path, PathBuf},
+};
+
+impl IndexTree {
+    /// Expands every glob and directory entry, leaving only `Root`, `Node` and
+    /// `Leaf` entries that name concrete files.
+    pub fn resolve(&self) -> Result<IndexTree> {
+        match self {
+            IndexTree::Root(nodes) => Ok(IndexTree::Root(resolve_nodes(nodes)?)),
+            IndexTree::Node(path, nodes) => {
+                Ok(IndexTree::Node(path.to_owned(), resolve_nodes(nodes)?))
+            }
+            IndexTree::Glob(glob) => glob.resolve(),
+            IndexTree::Leaf(path) if is_glob_pattern(path) || Path::new(path).is_dir() => {
+                IndexGlob::from(path.as_str()).resolve()
+            }
+            IndexTree::Leaf(path) => Ok(IndexTree::Leaf(path.to_owned())),
+        }
+    }
+
+    /// Paths of all documents in index order.
+    pub fn paths(&self) -> Vec<&str> {
+        match self {
+            IndexTree::Root(nodes) => nodes.iter().flat_map(IndexTree::paths).collect(),
+            IndexTree::Node(path, nodes) => std::iter::once(path.as_str())
+                .chain(nodes.iter().flat_map(IndexTree::paths))
+                .collect(),
+            IndexTree::Glob(glob) => vec![glob.path.as_str()],
+            IndexTree::Leaf(path) => vec![path.as_str()],
+        }
+    }
+}
+
+fn resolve_nodes(nodes: &[IndexTree]) -> Result<Vec<IndexTree>> {
+    nodes.iter().map(IndexTree::resolve).collect()
+}
+
+impl From<&str> for IndexGlob {
+    fn from(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            order: None,
+        }
+    }
+}
+
+impl IndexGlob {
+    pub fn resolve(&self) -> Result<IndexTree> {
+        let include = compile_patterns(&self.include)?;
+        let exclude = compile_patterns(&self.exclude)?;
+        let order_file = self.order.as_ref().map(PathBuf::from);
+
+        let mut paths = self
+            .candidates()?
+            .into_iter()
+            .filter(|path| Some(path) != order_file.as_ref())
+            .filter(|path| {
+                let name = file_name(path);
+
+                (include.is_empty() || include.iter().any(|p| p.matches(name)))
+                    && !exclude.iter().any(|p| p.matches(name))
+            })
+            .collect::<Vec<_>>();
+        if paths.is_empty() {
+            return Err(anyhow!("index entry {} matched no files", self.path));
+        }
+
+        paths.sort_by(|lhs, rhs| natural_cmp(&lhs.to_string_lossy(), &rhs.to_string_lossy()));
+        let paths = match &order_file {
+            Some(order_file) => apply_order(paths, order_file)?,
+            None => paths,
+        };
+
+        Ok(IndexTree::Root(
+            paths
+                .into_iter()
+                .map(|path| IndexTree::Leaf(path.to_string_lossy().into_owned()))
+                .collect(),
+        ))
+    }
+
+    fn candidates(&self) -> Result<Vec<PathBuf>> {
+        let path = Path::new(&self.path);
+        if path.is_dir() {
+            return walk_dir(path);
+        }
+
+        glob::glob(&self.path)
+            .with_context(|| format!("invalid index pattern {}", self.path))?
+            .filter_map(|entry| match entry {
+                Ok(path) if path.is_file() => Some(Ok(path)),
+                Ok(_) => None,
+                Err(err) => Some(Err(err.into())),
+            })
+            .collect()
+    }
+}
+
+fn walk_dir(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut entries = fs::read_dir(dir)
+        .with_context(|| format!("failed to read directory {}", dir.display()))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by(|lhs, rhs| natural_cmp(file_name(lhs), file_name(rhs)));
+
+    entries
+        .into_iter()
+        .filter(|path| !file_name(path).starts_with('.'))
+        .try_fold(Vec::new(), |mut acc, path| {
+            match path.is_dir() {
+                true => acc.extend(walk_dir(&path)?),
+                false => acc.push(path),
+            };
+
+            Ok(acc)
+        })
+}
+
+/// Moves the files listed in `order_file` to the front, in the listed order.
+/// Each non-empty line that does not start with `#` names a file, either by
+/// file name or by path.
+fn apply_order(mut paths: Vec<PathBuf>, order_file: &Path) -> Result<Vec<PathBuf>> {
+    let order = fs::read_to_string(order_file)
+        .with_context(|| format!("failed to read order file {}", order_file.display()))?;
+
+    let mut ordered = Vec::new();
+    for name in order
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+    {
+        let idx = paths
+            .iter()
+            .position(|path| path.ends_with(name))
+            .ok_or_else(|| {
+                anyhow!(
+                    "{} lists {name}, which is not matched by the index entry",
+                    order_file.display()
+                )
+            })?;
+        ordered.push(paths.remove(idx));
+    }
+    ordered.extend(paths);
+
+    Ok(ordered)
+}
+
+fn compile_patterns(patterns: &[String]) -> Result<Vec<Pattern>> {
+    patterns
+        .iter()
+        .map(|p| Pattern::new(p).with_context(|| format!("invalid index pattern {p}")))
+        .collect()
+}
+
+fn is_glob_pattern(path: &str) -> bool {
+    path.contains(['*', '?', '['])
+}
+
+fn file_name(path: &Path) -> &str {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default()
+}
+
+/// Compares strings so that runs of digits are ordered by their numeric value,
+/// e.g. `ch2` < `ch10`.
+fn natural_cmp(lhs: &str, rhs: &str) -> Ordering {
+    let mut lhs = Chunks(lhs);
+    let mut rhs = Chunks(rhs);
+
+    loop {
+        match (lhs.next(), rhs.next()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(l), Some(r)) => {
+                let ord = match (is_digits(l), is_digits(r)) {
+                    (true, true) => {
+                        let (l, r) = (l.trim_start_matches('0'), r.trim_start_matches('0'));
+                        l.len().cmp(&r.len()).then_with(|| l.cmp(r))
+                    }
+                    _ => l.cmp(r),
+                };
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+            }
+        }
+    }
+}
+
+fn is_digits(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_digit())
+}
+
+/// Splits a string into alternating runs of digits and non-digits.
+struct Chunks<'a>(&'a str);
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let digits = is_digits(self.0);
+        let len = self
+            .0
+            .find(|c: char| c.is_ascii_digit() != digits)
+            .unwrap_or(self.0.len());
+        if len == 0 {
+            return None;
+        }
+
+        let (chunk, rest) = self.0.split_at(len);
+        self.0 = rest;
+
+        Some(chunk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(tree: IndexTree) -> Vec<String> {
+        tree.paths().into_iter().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn natural_cmp_test() {
+        let mut names = vec!["ch10.pd", "ch2.pd", "ch1.pd", "ch02a.pd", "appendix.pd"];
+        names.sort_by(|l, r| natural_cmp(l, r));
+
+        assert_eq!(
+            names,
+            vec!["appendix.pd", "ch1.pd", "ch2.pd", "ch02a.pd", "ch10.pd"]
+        );
+    }
+
+    #[test]
+    fn resolve_directory_test() {
+        let tree = IndexTree::Leaf("fixtures/chapters".to_string())
+            .resolve()
+            .unwrap();
+
+        assert_eq!(
+            leaves(tree),
+            vec![
+                "fixtures/chapters/ch1.pd",
+                "fixtures/chapters/ch2.pd",
+                "fixtures/chapters/ch10.pd",
+                "fixtures/chapters/draft.pd",
+                "fixtures/chapters/notes.txt",
+                "fixtures/chapters/order.txt",
+            ]
+        );
+    }
+
+    #[test]
+    fn resolve_glob_test() {
+        let tree = IndexTree::Glob(IndexGlob {
+            path: "fixtures/chapters/*.pd".to_string(),
+            include: Vec::new(),
+            exclude: vec!["draft*".to_string()],
+            order: None,
+        })
+        .resolve()
+        .unwrap();
+
+        assert_eq!(
+            leaves(tree),
+            vec![
+                "fixtures/chapters/ch1.pd",
+                "fixtures/chapters/ch2.pd",
+                "fixtures/chapters/ch10.pd",
+            ]
+        );
+    }
+
+    #[test]
+    fn resolve_order_test() {
+        let tree = IndexTree::Glob(IndexGlob {
+            path: "fixtures/chapters".to_string(),
+            include: vec!["*.pd".to_string()],
+            exclude: Vec::new(),
+            order: Some("fixtures/chapters/order.txt".to_string()),
+        })
+        .resolve()
+        .unwrap();
+
+        assert_eq!(
+            leaves(tree),
+            vec![
+                "fixtures/chapters/ch10.pd",
+                "fixtures/chapters/ch1.pd",
+                "fixtures/chapters/ch2.pd",
+                "fixtures/chapters/draft.pd",
+            ]
+        );
+    }
+
+    #[test]
+    fn resolve_unmatched_test() {
+        assert!(IndexTree::Leaf("fixtures/chapters/*.md".to_string())
+            .resolve()
+            .is_err());
+    }
+
+    #[test]
+    fn deserialize_glob_test() {
+        let conf = crate::GokurakuConfig::from_toml_str(
+            r#"
+index = [
+  "preface.pd",
+  { path = "chapters", include = ["*.pd"], exclude = ["draft*"], order = "chapters/order.txt" },
+]
+adapters = []
+"#,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            conf.index,
+            IndexTree::Root(ref nodes) if matches!(
+                nodes.as_slice(),
+                [IndexTree::Leaf(_), IndexTree::Glob(IndexGlob { order: Some(_), .. })]
+            )
+        ));
+    }
+}
@@ -1,3 +1,5 @@
+mod index_tree;
+
 use std::path::PathBuf;
 
 use anyhow::{Context, Result};
@@ -22,9 +24,25 @@ pub struct BuildAdapterConfig {
 pub enum IndexTree {
     Root(Vec<IndexTree>),
     Node(String, Vec<IndexTree>),
+    Glob(IndexGlob),
     Leaf(String),
 }
 
+/// A leaf that expands to every file matched by `path`.
+///
+/// `path` is either a glob pattern or a directory. Matches are filtered by
+/// `include` / `exclude` and sorted naturally (`ch2` before `ch10`), except that
+/// the file names listed in `order` come first, in the listed order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexGlob {
+    pub path: String,
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    pub order: Option<String>,
+}
+
 impl GokurakuConfig {
     pub fn from_toml_str(toml: &str) -> Result<Self> {
         toml::from_str::<GokurakuConfig>(toml).context("config parse error")
@@ -106,10 +124,7 @@ impl TryFrom<(GokurakuConfig, CLIArgs)> for GokurakuConfigInstance {
                 .unwrap_or(Vec::new()),
             output: [conf.output, args.output].into_iter().flatten().last(),
             input: args.input.map(IndexTree::Leaf),
-            adapters: vec![BuildAdapterConfig {
-                name: "txt".to_string(),
-                options: None,
-            }],
+            adapters: conf.adapters,
         })
     }
 }
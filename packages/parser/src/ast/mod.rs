@@ -2,10 +2,12 @@ mod prose_down;
 
 pub use self::prose_down::*;
 pub use self::prose_down::{Block, Document, Inline};
+use num_bigint::BigInt;
+use num_rational::BigRational;
 use serde::{Deserialize, Serialize};
 use std::fmt::{Debug, Display};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Module {
     pub statements: Vec<Statement>,
 }
@@ -25,7 +27,20 @@ impl IntoIterator for Module {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Where a statement or an application is in the source, as 1-based `(line, column)`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Span {
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.start.0, self.start.1)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Statement {
     Assign(Assign),
     AssignDef(AssignDef),
@@ -35,29 +50,105 @@ pub enum Statement {
     ImplTrait(ImplTrait),
     InstDef(InstDef),
     DataAssign(DataAssign),
+    FixityDef(FixityDef),
+    Import(Import),
+    Export(Export),
     LineComment(LineComment),
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl Statement {
+    pub fn span(&self) -> Span {
+        match self {
+            Statement::Assign(assign) => assign.span,
+            Statement::AssignDef(def) => def.span,
+            Statement::HandlerDef(def) => def.span,
+            Statement::HandlerAssign(assign) => assign.span,
+            Statement::TraitDef(def) => def.span,
+            Statement::ImplTrait(def) => def.span,
+            Statement::InstDef(def) => def.span,
+            Statement::DataAssign(def) => def.span,
+            Statement::FixityDef(def) => def.span,
+            Statement::Import(import) => import.span,
+            Statement::Export(export) => export.span,
+            Statement::LineComment(comment) => comment.span,
+        }
+    }
+}
+
+/// `import "lib/text" as Text (words, Show)`. Without the names everything the
+/// module exports is imported.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Import {
+    pub path: String,
+    pub alias: Option<ExistsIdent>,
+    pub names: Option<Vec<ModuleName>>,
+    pub span: Span,
+}
+
+/// `export greet, Mood, *log`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Export {
+    pub names: Vec<ModuleName>,
+    pub span: Span,
+}
+
+/// A name a module exports or imports. A data type stands for its
+/// constructors as well, and a trait for its methods.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ModuleName {
+    Value(Ident),
+    Handler(HandlerIdent),
+    Type(ExistsIdent),
+}
+
+impl Display for ModuleName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModuleName::Value(ident) => write!(f, "{}", ident.0),
+            ModuleName::Handler(ident) => write!(f, "{}", ident.0),
+            ModuleName::Type(ident) => write!(f, "{}", ident.0),
+        }
+    }
+}
+
+/// `infixl 6 +, -`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FixityDef {
+    pub associativity: Associativity,
+    pub precedence: usize,
+    pub operators: Vec<Ident>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Associativity {
+    Left,
+    Right,
+    None,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HandlerDef {
     pub ident: HandlerIdent,
     pub expr: HandlerTypeDefExpr,
+    pub span: Span,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HandlerTypeDefExpr {
     pub trait_constraints: Vec<TraitConstraint>,
     pub eta_envs: EtaEnvs,
     pub expr: TypeAbstructionExpr,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InstDef {
     pub ident: InstIdent,
     pub expr: TypeExpr,
+    pub span: Span,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EtaEnvs(pub Vec<EtaEnv>);
 
 impl EtaEnvs {
@@ -75,21 +166,22 @@ impl IntoIterator for EtaEnvs {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EtaEnv {
     pub ident: HandlerIdent,
     pub expr: CoroutineType,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImplTrait {
     pub constraints: Vec<TraitConstraint>,
     pub ident: TypeIdent,
     pub args: Vec<TypeIdent>,
     pub where_clause: Module,
+    pub span: Span,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Expr {
     Apply(Apply),
     ApplyInst(ApplyInst),
@@ -97,100 +189,128 @@ pub enum Expr {
     InstIdent(InstIdent),
     Ident(Ident),
     HandlerIdent(HandlerIdent),
+    TypeIdent(TypeIdent),
     Literal(Literal),
-    Abstruction(Abstruction),
+    Abstruction(Lambda),
+    Handle(Handle),
+    /// `(a + b)`, an operator application in parentheses, which fixity
+    /// declarations do not reassociate.
+    Group(Box<Expr>),
+}
+
+/// `\x y -> expr`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lambda {
+    pub params: Vec<PatternExpr>,
+    pub expr: Box<Expr>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// `handle expr where { *write s = ... }`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Handle {
+    pub expr: Box<Expr>,
+    pub handlers: Vec<HandlerAssign>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Abstruction {
     pub arg: Option<Box<Expr>>,
     pub expr: Box<Expr>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CoroutineType {
     pub resume: TypeAbstructionExpr,
     pub ret: TypeAbstructionExpr,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TraitDef {
     pub trait_constraints: Vec<TraitConstraint>,
     pub constructor: DataConstructor,
     pub where_clause: Module,
+    pub span: Span,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TraitConstraint {
     pub ident: TraitIdent,
-    pub arg: ForallIdent,
+    pub args: Vec<ForallIdent>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// `f x`, or `lhs op rhs` when the abstraction has an argument. The span of an
+/// operator application is that of the operator.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Apply {
     pub abstruction: Abstruction,
     pub expr: Box<Expr>,
+    pub span: Span,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApplyInst {
     pub ident: InstIdent,
     pub expr: Box<Expr>,
+    pub span: Span,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApplyEff {
     pub ident: HandlerIdent,
     pub expr: Box<Expr>,
+    pub span: Span,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TypeExpr {
     pub trait_constraints: Vec<TraitConstraint>,
     pub eta_envs: EtaEnvs,
     pub expr: TypeAbstructionExpr,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TypeAbstructionExpr {
     Arrow(Box<TypeAbstructionExpr>, Box<TypeAbstructionExpr>),
     Literal(TypeLiteral),
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum TypeLiteral {
     Top,
     Array(Box<TypeAbstructionExpr>),
     Constructor(DataConstructor),
     Tuple(usize, Vec<TypeAbstructionExpr>),
     Coroutine(Box<CoroutineType>),
-    // Abstruction
+    Abstruction(Box<TypeAbstructionExpr>),
     Bottom,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Assign {
     pub ident: Ident,
     pub args: AssignArgs,
     pub expr: Expr,
     pub where_clause: Module,
+    pub span: Span,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HandlerAssign {
     pub ident: HandlerIdent,
     pub args: AssignArgs,
     pub expr: Expr,
     pub where_clause: Module,
+    pub span: Span,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DataAssign {
     pub modifier: Option<DataModifier>,
     pub constraints: Vec<TraitConstraint>,
     pub ident: TypeIdent,
     pub args: Vec<TypeIdent>,
     pub expr: DataExpr,
+    pub span: Span,
 }
 
 impl DataAssign {
@@ -207,13 +327,13 @@ impl DataAssign {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum DataModifier {
     Nominal,
     Structual,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum DataExpr {
     Or(Box<DataExpr>, Box<DataExpr>),
     Value(DataValue),
@@ -244,20 +364,20 @@ impl IntoIterator for DataExpr {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum DataValue {
     Constructor(DataConstructor),
     Unit,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DataConstructor {
     pub modifier: Option<DataModifier>,
     pub ident: TypeIdent,
     pub args: Vec<TypeLiteral>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AssignArgs {
     pub patterns: Vec<PatternExpr>,
 }
@@ -277,33 +397,38 @@ impl IntoIterator for AssignArgs {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum PatternExpr {
     Or(Box<PatternExpr>, Box<PatternExpr>),
     Literal(Literal),
     Bind(Ident),
-    ListHead(),
+    ListHead(Box<PatternExpr>, Box<PatternExpr>),
     Constructor(DataConstructor),
     Tuple(usize, Vec<PatternExpr>),
     Any,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AssignDef {
     pub ident: Ident,
     pub expr: TypeExpr,
+    pub span: Span,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct LineComment(pub String);
+/// `// text`, the text from `//` to the end of the line.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LineComment {
+    pub text: String,
+    pub span: Span,
+}
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ParameterCondition {
     type_class: TypeClass,
     ident: Ident,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TypeClass {}
 
 #[derive(Debug, Serialize, Deserialize, Hash, PartialEq, Eq, Clone)]
@@ -339,11 +464,16 @@ pub struct InstIdent(pub String);
 #[derive(Debug, Serialize, Deserialize, Hash, PartialEq, Eq, Clone)]
 pub struct TraitIdent(pub String);
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Literal {
     Char(char),
     Text(String),
     Int(isize),
+    Float(f64),
+    /// `123n`
+    Integer(BigInt),
+    /// `3/4r`
+    Rational(BigRational),
     Unit,
     Array(Vec<Expr>),
     Tuple(usize, Vec<Expr>),
@@ -12,7 +12,8 @@ impl Document {
 
 #[derive(Clone, Serialize, Deserialize)]
 pub enum Block {
-    PdsScript(String),
+    /// The code of a `@{...}` block, and the line and column it starts at.
+    PdsScript(String, (usize, usize)),
     Paragraph(Vec<Inline>),
     EmptyLine,
     ThemanticBreak,
@@ -21,7 +22,7 @@ pub enum Block {
 impl Block {
     pub fn iter(&self) -> impl Iterator<Item = &Inline> {
         match self {
-            Self::PdsScript(_) => std::iter::empty(),
+            Self::PdsScript(..) => std::iter::empty(),
             Self::Paragraph(value) => std::iter::empty(),
             Self::ThemanticBreak => std::iter::empty(),
             Self::EmptyLine => std::iter::empty(),
@@ -32,7 +33,7 @@ impl Block {
 impl Debug for Block {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::PdsScript(value) => write!(f, "PdsScript({value})"),
+            Self::PdsScript(value, _) => write!(f, "PdsScript({value})"),
             Self::Paragraph(value) => {
                 write!(f, "Paragraph(\n\t")?;
                 value.iter().try_for_each(|item| item.fmt(f))?;
@@ -48,7 +49,9 @@ impl Debug for Block {
 pub enum Inline {
     Text(String),
     Number(String),
-    Expr(String),
+    /// The expression of a `{expr}` inline, and the line and column it starts
+    /// at.
+    Expr(String, (usize, usize)),
 }
 
 impl Inline {
@@ -56,7 +59,7 @@ impl Inline {
         match self {
             Self::Text(_) => std::iter::empty(),
             Self::Number(_) => std::iter::empty(),
-            Self::Expr(_) => std::iter::empty(),
+            Self::Expr(..) => std::iter::empty(),
         }
     }
 }
@@ -69,7 +72,7 @@ impl Debug for Inline {
             }
             Self::Text(value) => write!(f, "{value}"),
             Self::Number(value) => write!(f, "Number(##{value}##)"),
-            Self::Expr(value) => write!(f, "Expr({{{value}}})"),
+            Self::Expr(value, _) => write!(f, "Expr({{{value}}})"),
         }
     }
 }
@@ -0,0 +1,181 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    hash::Hash,
+};
+
+/// Nodes and the nodes each depends on, in the order they are added.
+#[derive(Debug)]
+pub(crate) struct Graph<T> {
+    nodes: Vec<T>,
+    edges: HashMap<T, Vec<T>>,
+}
+
+impl<T> Default for Graph<T> {
+    fn default() -> Self {
+        Self {
+            nodes: Vec::new(),
+            edges: HashMap::new(),
+        }
+    }
+}
+
+impl<T: Clone + Eq + Hash> Graph<T> {
+    pub fn node(&mut self, node: T) {
+        if !self.edges.contains_key(&node) {
+            self.nodes.push(node.clone());
+            self.edges.insert(node, Vec::new());
+        }
+    }
+
+    /// An edge to a node that is not in the graph is ignored.
+    pub fn edge(&mut self, from: T, to: T) {
+        self.node(from.clone());
+        self.edges.get_mut(&from).unwrap().push(to);
+    }
+
+    fn successors<'a>(&'a self, node: &T) -> impl Iterator<Item = &'a T> {
+        self.edges[node]
+            .iter()
+            .filter(|next| self.edges.contains_key(*next))
+    }
+
+    /// The strongly connected components, each after the ones it depends on,
+    /// nodes in the order they are added.
+    pub fn components(&self) -> Vec<Vec<T>> {
+        let mut tarjan = Tarjan {
+            graph: self,
+            index: HashMap::new(),
+            low: HashMap::new(),
+            stack: Vec::new(),
+            components: Vec::new(),
+        };
+        self.nodes.iter().for_each(|node| {
+            if !tarjan.index.contains_key(node) {
+                tarjan.visit(node);
+            }
+        });
+
+        tarjan.components
+    }
+
+    /// A shortest cycle through the first node of each component that depends
+    /// on itself, without repeating the node at the end.
+    pub fn cycles(&self) -> Vec<Vec<T>> {
+        self.components()
+            .into_iter()
+            .filter_map(|component| self.cycle(&component[0]))
+            .collect()
+    }
+
+    fn cycle(&self, start: &T) -> Option<Vec<T>> {
+        let mut previous = HashMap::<&T, &T>::new();
+        let mut queue = VecDeque::from([start]);
+
+        while let Some(node) = queue.pop_front() {
+            for next in self.successors(node) {
+                if next == start {
+                    let mut cycle = std::iter::successors(Some(node), |node| {
+                        (*node != start).then(|| previous[node])
+                    })
+                    .cloned()
+                    .collect::<Vec<_>>();
+                    cycle.reverse();
+
+                    return Some(cycle);
+                }
+                if !previous.contains_key(next) {
+                    previous.insert(next, node);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+struct Tarjan<'a, T> {
+    graph: &'a Graph<T>,
+    index: HashMap<&'a T, usize>,
+    low: HashMap<&'a T, usize>,
+    stack: Vec<&'a T>,
+    components: Vec<Vec<T>>,
+}
+
+impl<'a, T: Clone + Eq + Hash> Tarjan<'a, T> {
+    fn visit(&mut self, node: &'a T) {
+        let index = self.index.len();
+        self.index.insert(node, index);
+        self.low.insert(node, index);
+        self.stack.push(node);
+
+        let graph = self.graph;
+        graph.successors(node).for_each(|next| {
+            let low = match self.index.get(next) {
+                None => {
+                    self.visit(next);
+                    self.low[next]
+                }
+                Some(_) if self.stack.contains(&next) => self.index[next],
+                Some(_) => return,
+            };
+            let current = self.low[node];
+            self.low.insert(node, current.min(low));
+        });
+
+        if self.low[node] == index {
+            let at = self.stack.iter().rposition(|n| *n == node).unwrap();
+            let members = self.stack.split_off(at).into_iter().collect::<HashSet<_>>();
+            self.components.push(
+                self.graph
+                    .nodes
+                    .iter()
+                    .filter(|node| members.contains(node))
+                    .cloned()
+                    .collect(),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Graph;
+
+    fn graph(edges: &[(&'static str, &'static str)]) -> Graph<&'static str> {
+        edges
+            .iter()
+            .fold(Graph::default(), |mut graph, &(from, to)| {
+                graph.node(from);
+                graph.node(to);
+                graph.edge(from, to);
+
+                graph
+            })
+    }
+
+    #[test]
+    fn components_test() {
+        let graph = graph(&[("a", "b"), ("b", "a"), ("b", "c"), ("d", "d"), ("d", "a")]);
+
+        assert_eq!(
+            graph.components(),
+            vec![vec!["c"], vec!["a", "b"], vec!["d"]]
+        );
+    }
+
+    #[test]
+    fn cycles_test() {
+        let graph = graph(&[("a", "b"), ("b", "c"), ("c", "a"), ("c", "b"), ("d", "d")]);
+
+        assert_eq!(graph.cycles(), vec![vec!["a", "b", "c"], vec!["d"]]);
+    }
+
+    #[test]
+    fn acyclic_test() {
+        let mut graph = graph(&[("a", "b"), ("b", "c")]);
+        graph.edge("c", "undefined");
+
+        assert!(graph.cycles().is_empty());
+    }
+}
@@ -1,51 +1,76 @@
 use super::symbol_table1::SymbolTable1;
 use crate::{
     ast,
-    ir::{HandlerIdent, Ident, InstIdent, TypeIdent},
+    ir::{ExistsIdent, HandlerIdent, Ident, InstIdent, TypeIdent},
 };
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct IR1 {
+    /// Functions and the methods of traits.
     pub ident_symbols: SymbolTable1<Ident>,
     pub data_symbols: SymbolTable1<TypeIdent>,
+    /// `nominal data True = ()` and the constructors of a union.
+    pub constructor_symbols: SymbolTable1<ExistsIdent>,
     pub handler_symbols: SymbolTable1<HandlerIdent>,
     pub trait_symbols: SymbolTable1<TypeIdent>,
     pub inst_symbols: SymbolTable1<InstIdent>,
 }
 
-// TODO: detect cyclic dependency
 pub fn transform1(ast: &ast::Module) -> IR1 {
     ast.iter().fold(IR1::default(), |mut acc, stmt| {
         match stmt {
-            ast::Statement::DataAssign(ast::DataAssign { ident, .. }) => {
-                acc.data_symbols.insert(ident.clone().into());
+            ast::Statement::DataAssign(ast::DataAssign {
+                ident, expr, span, ..
+            }) => {
+                acc.data_symbols.insert(ident.clone().into(), *span);
+                if let ast::TypeIdent::ExistsIdent(ident) = ident {
+                    acc.constructor_symbols.insert(ident.clone().into(), *span);
+                }
+                expr.iter().for_each(|value| {
+                    if let ast::DataValue::Constructor(ast::DataConstructor {
+                        ident: ast::TypeIdent::ExistsIdent(ident),
+                        ..
+                    }) = value
+                    {
+                        acc.constructor_symbols.insert(ident.clone().into(), *span);
+                    }
+                });
             }
-            ast::Statement::Assign(ast::Assign { ident, .. }) => {
-                acc.ident_symbols.insert(ident.clone().into());
+            ast::Statement::Assign(ast::Assign { ident, span, .. }) => {
+                acc.ident_symbols.insert(ident.clone().into(), *span);
             }
-            ast::Statement::AssignDef(ast::AssignDef { ident, .. }) => {
-                acc.ident_symbols.insert(ident.clone().into());
+            ast::Statement::AssignDef(ast::AssignDef { ident, span, .. }) => {
+                acc.ident_symbols.insert(ident.clone().into(), *span);
             }
-            ast::Statement::HandlerDef(ast::HandlerDef { ident, .. }) => {
-                acc.handler_symbols.insert(ident.clone().into());
+            ast::Statement::HandlerDef(ast::HandlerDef { ident, span, .. }) => {
+                acc.handler_symbols.insert(ident.clone().into(), *span);
             }
-            ast::Statement::HandlerAssign(ast::HandlerAssign { ident, .. }) => {
-                acc.handler_symbols.insert(ident.clone().into());
+            ast::Statement::HandlerAssign(ast::HandlerAssign { ident, span, .. }) => {
+                acc.handler_symbols.insert(ident.clone().into(), *span);
             }
             ast::Statement::TraitDef(ast::TraitDef {
                 constructor: ast::DataConstructor { ident, .. },
+                where_clause,
+                span,
                 ..
             }) => {
-                acc.trait_symbols.insert(ident.clone().into());
-            }
-            ast::Statement::ImplTrait(ast::ImplTrait { ident, .. }) => {
-                acc.trait_symbols.insert(ident.clone().into());
+                acc.trait_symbols.insert(ident.clone().into(), *span);
+                where_clause.iter().for_each(|statement| {
+                    if let ast::Statement::AssignDef(ast::AssignDef { ident, .. }) = statement {
+                        acc.ident_symbols.insert(ident.clone().into(), *span);
+                    }
+                });
             }
-            ast::Statement::InstDef(ast::InstDef { ident, .. }) => {
-                acc.inst_symbols.insert(ident.clone().into());
+            ast::Statement::InstDef(ast::InstDef { ident, span, .. }) => {
+                acc.inst_symbols.insert(ident.clone().into(), *span);
             }
-            ast::Statement::LineComment(_) => {}
+            // an impl refers to its trait, it does not define one
+            ast::Statement::ImplTrait(_)
+            | ast::Statement::FixityDef(_)
+            | ast::Statement::Import(_)
+            | ast::Statement::Export(_)
+            | ast::Statement::LineComment(_) => {}
         };
 
         acc
@@ -1,8 +1,10 @@
+use crate::ast::Span;
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, hash::Hash};
 
+/// Symbols with where they are first defined.
 #[derive(Debug, Serialize, Deserialize)]
-pub struct SymbolTable1<T: Hash + Eq>(HashMap<T, ()>);
+pub struct SymbolTable1<T: Hash + Eq>(HashMap<T, Span>);
 
 impl<T: Hash + Eq> Default for SymbolTable1<T> {
     fn default() -> Self {
@@ -11,22 +13,30 @@ impl<T: Hash + Eq> Default for SymbolTable1<T> {
 }
 
 impl<T: Hash + Eq> SymbolTable1<T> {
-    pub fn insert(&mut self, ident: T) -> bool {
-        self.0.insert(ident, ()).is_none()
+    /// Keeps the span of an earlier definition.
+    pub fn insert(&mut self, ident: T, span: Span) -> bool {
+        match self.0.contains_key(&ident) {
+            true => false,
+            false => self.0.insert(ident, span).is_none(),
+        }
     }
 
     pub fn contains(&self, ident: &T) -> bool {
         self.0.contains_key(ident)
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = &T> {
-        self.0.keys()
+    pub fn find(&self, ident: &T) -> Option<&Span> {
+        self.0.get(ident)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&T, &Span)> {
+        self.0.iter()
     }
 }
 
 impl<T: Hash + Eq> IntoIterator for SymbolTable1<T> {
-    type Item = <HashMap<T, ()> as IntoIterator>::Item;
-    type IntoIter = <HashMap<T, ()> as IntoIterator>::IntoIter;
+    type Item = <HashMap<T, Span> as IntoIterator>::Item;
+    type IntoIter = <HashMap<T, Span> as IntoIterator>::IntoIter;
 
     fn into_iter(self) -> Self::IntoIter {
         self.0.into_iter()
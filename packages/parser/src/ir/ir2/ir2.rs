@@ -1,9 +1,9 @@
 use super::symbol_table2::SymbolTable2;
 use crate::ast;
-use crate::ir::ir1::IR1;
+use crate::ir::ir1::{transform1, IR1};
 use crate::ir::{
-    DataExpr, Function, HandlerFunction, HandlerIdent, Ident, InstIdent, TypeAbstructionEnv,
-    TypeIdent,
+    DataDef, Expr, Function, HandlerFunction, HandlerIdent, Ident, ImplDef, InstIdent, TraitDef,
+    TraitIdent, TypeAbstructionEnv, TypeIdent,
 };
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
@@ -11,14 +11,62 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct IR2 {
     pub ident_symbols: SymbolTable2<Ident, (Vec<Function<IR2>>, Option<TypeAbstructionEnv>)>,
-    pub data_symbols: SymbolTable2<TypeIdent, DataExpr>,
+    pub data_symbols: SymbolTable2<TypeIdent, DataDef>,
     pub handler_symbols:
         SymbolTable2<HandlerIdent, (Vec<HandlerFunction<IR2>>, Option<TypeAbstructionEnv>)>,
-    // TODO: traitはTypeAbstructionではない
-    pub trait_symbols: SymbolTable2<TypeIdent, TypeAbstructionEnv>,
+    pub trait_symbols: SymbolTable2<TraitIdent, TraitDef>,
+    /// In source order, overlapping impls are reported by the type checker.
+    pub impls: Vec<ImplDef>,
     pub inst_symbols: SymbolTable2<InstIdent, TypeAbstructionEnv>,
 }
 
+/// The functions of a where-clause.
+impl TryFrom<ast::Module> for IR2 {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ast::Module) -> Result<Self> {
+        transform2(transform1(&value), value)
+    }
+}
+
+impl IR2 {
+    /// The functions in source order.
+    pub fn functions(&self) -> Vec<(&Ident, &Vec<Function<IR2>>)> {
+        let mut functions = self
+            .ident_symbols
+            .iter()
+            .map(|(ident, (functions, _))| (ident, functions))
+            .collect::<Vec<_>>();
+        functions.sort_by_key(|(_, functions)| functions.first().map(|f| f.span.start));
+
+        functions
+    }
+
+    /// Whether an equation of a where-clause, or of a where-clause in it,
+    /// mentions `ident`.
+    pub fn mentions(&self, ident: &Ident) -> bool {
+        self.ident_symbols
+            .iter()
+            .flat_map(|(_, (functions, _))| functions)
+            .any(|f| f.expr.expr.mentions(ident) || f.where_clause.mentions(ident))
+    }
+
+    /// The `Expr::Reference`s in the equations of a where-clause, after the
+    /// ones of the equation it belongs to.
+    pub fn references(&self) -> Vec<&Expr> {
+        self.functions()
+            .into_iter()
+            .flat_map(|(_, functions)| functions)
+            .flat_map(|f| {
+                let mut references = f.expr.expr.references();
+                references.extend(f.where_clause.references());
+
+                references
+            })
+            .collect()
+    }
+}
+
 pub fn transform2(_ir1: IR1, ast: ast::Module) -> Result<IR2> {
     let (ir2, errs) =
         ast.into_iter()
@@ -26,7 +74,7 @@ pub fn transform2(_ir1: IR1, ast: ast::Module) -> Result<IR2> {
                 match item {
                     ast::Statement::DataAssign(data_assign) => {
                         let ident = TypeIdent::from(data_assign.ident.clone());
-                        let value = match DataExpr::try_from(data_assign){
+                        let value = match DataDef::try_from(data_assign){
                             Ok(value)=>value,
                             Err(err)=>{errs.push(err);return (acc,errs);},
                         };
@@ -64,8 +112,9 @@ pub fn transform2(_ir1: IR1, ast: ast::Module) -> Result<IR2> {
                                     Some((abstractions, None)) => {
                                         acc.ident_symbols.insert(ident, (abstractions, Some(value)));
                                     },
-                                    Some((_abstractions, Some(_))) => {
-                                        unreachable!("Should not be possible to have multiple type abstractions for the same ident");
+                                    Some((abstractions, Some(sig))) => {
+                                        errs.push(anyhow!("{}: {ident} already has a signature", value.span));
+                                        acc.ident_symbols.insert(ident, (abstractions, Some(sig)));
                                     },
                                     None => {
                                         acc.ident_symbols.insert(ident, (vec![], Some(value)));
@@ -95,8 +144,14 @@ acc.handler_symbols.find_or_default_mut(&ident).1 = Some(value);
 
                     }
                     ast::Statement::TraitDef(trait_def) => {
-                        let ident = TypeIdent::from(trait_def.constructor.ident.clone());
-                        let value = match TypeAbstructionEnv::try_from(trait_def){
+                        let ident = match &trait_def.constructor.ident {
+                            ast::TypeIdent::ExistsIdent(ident) => TraitIdent(ident.0.clone()),
+                            ast::TypeIdent::ForallIdent(ident) => {
+                                errs.push(anyhow!("{} is not a trait", ident.0));
+                                return (acc, errs);
+                            }
+                        };
+                        let value = match TraitDef::try_from(trait_def){
                             Ok(value)=>{
                                 value
                             },
@@ -105,8 +160,11 @@ acc.handler_symbols.find_or_default_mut(&ident).1 = Some(value);
 
                         acc.trait_symbols.insert(ident, value);
                     }
-                    ast::Statement::ImplTrait(_impl_trait) => {
-                        // TODO: impl trait
+                    ast::Statement::ImplTrait(impl_trait) => {
+                        match ImplDef::try_from(impl_trait) {
+                            Ok(value) => acc.impls.push(value),
+                            Err(err) => errs.push(err),
+                        }
                     }
                     ast::Statement::InstDef(inst_def) => {
                         let ident = InstIdent::from(inst_def.ident.clone());
@@ -118,109 +176,19 @@ acc.handler_symbols.find_or_default_mut(&ident).1 = Some(value);
                         };
 
                     }
-                    ast::Statement::LineComment(_) => {}
+                    ast::Statement::FixityDef(_)
+                    | ast::Statement::Import(_)
+                    | ast::Statement::Export(_)
+                    | ast::Statement::LineComment(_) => {}
                 };
 
                 (acc, errs)
             });
     if !errs.is_empty() {
-        return Err(anyhow!("Errors: {:?}", errs));
+        // Debug of an error carries its backtrace
+        let errs = errs.iter().map(ToString::to_string).collect::<Vec<_>>();
+        return Err(anyhow!("Errors: [{}]", errs.join(", ")));
     }
 
     Ok(ir2)
 }
-
-// fn build_data_table(
-//     symbols: SymbolTable1<TypeIdent>,
-//     stmts: Vec<ast::DataAssign>,
-// ) -> Result<BoundedDataLattice> {
-//     let symbols = symbols
-//         .into_iter()
-//         .map(|(ident, _)| ident)
-//         .collect::<VecDeque<_>>();
-
-//     let mut lattice = DataLattice::default();
-//     let a = build_data_lattice_from_symbols(symbols, stmts, &mut lattice);
-//     let mut bounded = BoundedDataLattice::default();
-//     bounded.union(lattice);
-
-//     Ok(bounded)
-// }
-
-// fn build_data_lattice_from_symbols(
-//     mut symbols: VecDeque<TypeIdent>,
-//     stmts: Vec<ast::DataAssign>,
-//     lattice: &mut DataLattice,
-// ) -> Result<(VecDeque<TypeIdent>, Vec<ast::DataAssign>, &mut DataLattice)> {
-//     let symbol = match symbols.pop_front() {
-//         Some(sym) => sym,
-//         None => return Ok((symbols, stmts, lattice)),
-//     };
-
-//     let (mut matched, rest): (Vec<ast::DataAssign>, Vec<ast::DataAssign>) = stmts
-//         .into_iter()
-//         .partition(|item| TypeIdent::from(item.ident.clone()) == symbol);
-//     let def = match &matched.as_slice() {
-//         &[] => return Err(anyhow!("No data assign for symbol {}", symbol)),
-//         &[_] => matched.pop().unwrap(),
-//         _ => return Err(anyhow!("Multiple data assign for symbol {}", symbol)),
-//     };
-
-//     dbg!(&def.expr);
-//     match (def.modifier.into(), def.ident) {
-//         (DataModifier::Nominal, ast::TypeIdent::ExistsIdent(ident)) => {
-//             let mut l = DataLattice::default();
-//             // TODO: ここで、型変数を束縛する
-//             dbg!(build_data_lattice_from_expr(def.expr, lattice));
-//             // l.element = DataTerm::Constructor(ident.into(), Vec::new());
-//             // lattice.union(l);
-//             todo!()
-//         }
-//         (DataModifier::Nominal, ast::TypeIdent::ForallIdent(_ident)) => {
-//             // TODO: forallの場合は、型変数を束縛する
-//         }
-//         (DataModifier::Structual, ident) => {
-//             dbg!("structual", &ident);
-//             // TODO: 構造体とタプルの中身で新しい型が出現しないかチェックする
-//         }
-//     }
-
-//     let l = build_data_lattice_from_expr(def.expr, lattice);
-//     lattice.union(l);
-
-//     //     match def.expr {
-//     //         ast::DataExpr::Or(lhs, rhs) => {
-//     //             let (symbols, rest, lattice) = build_data_lattice_from_symbols(symbols, rest, lattice)?;
-//     //             let (symbols, rest, lattice) = build_data_lattice_from_symbols(symbols, rest, lattice)?;
-
-//     // build_data_lattice_from_expr(lhs, lattice)
-
-//     //             lattice.union(lhs);
-
-//     //             build_data_lattice_from_symbols(symbols, rest, lattice)
-//     //         }
-//     //         ast::DataExpr::Value(value) => match value {
-//     //             ast::DataValue::Unit => build_data_lattice_from_symbols(symbols, rest, lattice),
-//     //             ast::DataValue::Constructor(ident, args) => {
-//     //                 dbg!(&ident, &args);
-
-//     //                 build_data_lattice_from_symbols(symbols, rest, lattice)
-//     //             }
-//     //         },
-//     //     }
-
-//     build_data_lattice_from_symbols(symbols, rest, lattice)
-// }
-
-// fn build_data_lattice_from_expr(expr: ast::DataExpr, lattice: &mut DataLattice) -> DataLattice {
-//     match expr {
-//         ast::DataExpr::Or(lhs, rhs) => {
-//             let mut l = DataLattice::default();
-//             l.union(build_data_lattice_from_expr(*lhs, lattice));
-//             l.union(build_data_lattice_from_expr(*rhs, lattice));
-
-//             l
-//         }
-//         ast::DataExpr::Value(value) => DataTerm::from(value).into(),
-//     }
-// }
@@ -1,4 +1,5 @@
 mod ir2;
 mod symbol_table2;
 
-pub use ir2::transform2;
+pub use ir2::{transform2, IR2};
+pub use symbol_table2::SymbolTable2;
@@ -1,9 +1,29 @@
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, hash::Hash};
+use std::{collections::HashMap, fmt::Debug, hash::Hash};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Serialize, Deserialize)]
 pub struct SymbolTable2<T: Hash + Eq, U>(HashMap<T, U>);
 
+// entries are sorted so that snapshots do not depend on the hash order
+impl<T: Hash + Eq + Debug, U: Debug> Debug for SymbolTable2<T, U> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        struct Entries<'a, T, U>(Vec<(&'a T, &'a U)>);
+
+        impl<T: Debug, U: Debug> Debug for Entries<'_, T, U> {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.debug_map().entries(self.0.iter().copied()).finish()
+            }
+        }
+
+        let mut entries = self.0.iter().collect::<Vec<_>>();
+        entries.sort_by_cached_key(|(ident, _)| format!("{ident:?}"));
+
+        f.debug_tuple("SymbolTable2")
+            .field(&Entries(entries))
+            .finish()
+    }
+}
+
 impl<T: Hash + Eq, U> Default for SymbolTable2<T, U> {
     fn default() -> Self {
         Self(HashMap::new())
@@ -39,4 +59,17 @@ impl<T: Hash + Eq + Clone, U> SymbolTable2<T, U> {
     pub fn remove(&mut self, ident: &T) -> Option<U> {
         self.0.remove(ident)
     }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&T, &U)> {
+        self.0.iter()
+    }
+}
+
+impl<T: Hash + Eq, U> IntoIterator for SymbolTable2<T, U> {
+    type Item = <HashMap<T, U> as IntoIterator>::Item;
+    type IntoIter = <HashMap<T, U> as IntoIterator>::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
 }
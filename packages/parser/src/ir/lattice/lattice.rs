@@ -1,355 +1,198 @@
-use super::super::{DataModifier, DataTerm, ExistsIdent, TypeIdent};
-use crate::ast;
-use anyhow::anyhow;
-use serde::{Deserialize, Serialize};
-use std::{
-    borrow::{Borrow, BorrowMut},
-    cell::RefCell,
-    collections::HashMap,
-    rc::Rc,
-};
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct BoundedDataLattice {
-    top: Vec<Rc<RefCell<DataLattice>>>,
-    botom: Vec<Rc<RefCell<DataLattice>>>,
+use super::semi_lattice::{JoinSemiLattice, MeetSemiLattice, SemiLattice};
+use crate::ir::ExistsIdent;
+use std::{cmp::Ordering, collections::HashMap};
+
+/// An element of the data lattice, a data type or one of the bounds.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DataElem {
+    Top,
+    Bottom,
+    Data(ExistsIdent),
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+/// Data types ordered by the unions they are members of. `nominal data True =
+/// ()` is below `data Bool = True | False`, as is a constructor the union
+/// introduces itself. A data type is a member of at most one union, so the
+/// unions form trees under `Top`, and `Bottom` is below everything.
+#[derive(Debug, Default)]
 pub struct DataLattice {
-    pub element: DataTerm,
-    pub join_element: Option<Rc<RefCell<DataLattice>>>,
-    pub meet_elements: Vec<Rc<RefCell<DataLattice>>>,
+    unions: HashMap<ExistsIdent, ExistsIdent>,
 }
 
-impl Default for DataLattice {
-    fn default() -> Self {
-        Self {
-            element: DataTerm::Unit,
-            join_element: None,
-            meet_elements: Vec::new(),
+impl DataLattice {
+    /// Puts `member` below `union`, or returns the union it is already a
+    /// member of. A member that is above the union already is left out, the
+    /// order stays acyclic.
+    pub fn insert(&mut self, member: ExistsIdent, union: ExistsIdent) -> Result<(), ExistsIdent> {
+        if let Some(first) = self.unions.get(&member) {
+            return match first == &union {
+                true => Ok(()),
+                false => Err(first.clone()),
+            };
         }
-    }
-}
-
-// #[derive(Debug, Serialize, Deserialize, PartialEq)]
-// pub enum DataElement {
-//     // Nominal(ExistsIdent, DataValue),
-//     Constructor(ExistsIdent, Vec<DataValue>),
-//     Unit,
-// }
-
-// #[derive(Debug, Serialize, Deserialize, PartialEq)]
-// pub enum DataValue {
-//     Context(TypeIdent, Vec<TypeIdent>),
-//     TypeIdent(TypeIdent),
-//     Unit,
-//     Unknown,
-//     Top,
-//     Bottom,
-//     Parameter,
-// }
-
-impl Default for BoundedDataLattice {
-    fn default() -> Self {
-        let lattice = DataLattice::default();
-
-        let mut ret = Self {
-            top: Vec::new(),
-            botom: Vec::new(),
-        };
-
-        ret.union(lattice);
-
-        ret
-    }
-}
-
-trait Lattice {
-    type Elem;
-
-    fn is_disjoint(&self, lattice: &Self) -> bool;
-    fn union(&mut self, lattice: Self);
-    fn intersection(&mut self, lattice: Self);
-}
-
-impl BoundedDataLattice {
-    pub fn is_disjoint(&self, lattice: &DataLattice) -> bool {
-        !self
-            .top
-            .iter()
-            .any(|l| l.as_ref().borrow().is_disjoint(lattice))
-    }
-
-    pub fn union(&mut self, lattice: DataLattice) {
-        if !self.is_disjoint(&lattice) {
-            return;
+        if self.ancestors(&union).contains(&&member) {
+            return Ok(());
         }
 
-        let elem = Rc::new(RefCell::new(lattice));
+        self.unions.insert(member, union);
 
-        self.top.push(elem.clone());
-        self.botom.push(elem.clone());
+        Ok(())
     }
 
-    pub fn remove(&mut self, ident: &ExistsIdent) -> Option<Rc<RefCell<DataLattice>>> {
-        let idx = self
-            .top
-            .iter()
-            .position(|l| DataLattice::lookup_from_top(l.clone(), ident).is_some());
-
-        match idx {
-            Some(idx) => {
-                let lattice = self.top.remove(idx);
-                self.botom.remove(idx);
-
-                Some(lattice)
-            }
-            None => self
-                .top
-                .iter()
-                .find_map(|l| DataLattice::remove(l.clone(), ident)),
-        }
-    }
-
-    pub fn lookup_from_top(&self, ident: &ExistsIdent) -> Option<Rc<RefCell<DataLattice>>> {
-        self.top
-            .iter()
-            .find_map(|l| DataLattice::lookup_from_top(l.clone(), ident))
+    /// `ident` and the unions above it, nearest first.
+    fn ancestors<'a>(&'a self, ident: &'a ExistsIdent) -> Vec<&'a ExistsIdent> {
+        std::iter::successors(Some(ident), |ident| self.unions.get(*ident)).collect()
     }
 }
 
-impl PartialOrd for DataLattice {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        if self == other {
-            return Some(std::cmp::Ordering::Equal);
-        }
-        if self.is_disjoint(other) {
-            return None;
-        }
-
-        match self.element {
-            DataTerm::Top => Some(std::cmp::Ordering::Greater),
-            DataTerm::Bottom => Some(std::cmp::Ordering::Less),
-            _ => todo!(),
+impl SemiLattice for DataLattice {
+    type Elem = DataElem;
+
+    fn partial_cmp(&self, lhs: &DataElem, rhs: &DataElem) -> Option<Ordering> {
+        match (lhs, rhs) {
+            (lhs, rhs) if lhs == rhs => Some(Ordering::Equal),
+            (DataElem::Top, _) | (_, DataElem::Bottom) => Some(Ordering::Greater),
+            (_, DataElem::Top) | (DataElem::Bottom, _) => Some(Ordering::Less),
+            (DataElem::Data(lhs), DataElem::Data(rhs)) => {
+                if self.ancestors(lhs).contains(&rhs) {
+                    Some(Ordering::Less)
+                } else if self.ancestors(rhs).contains(&lhs) {
+                    Some(Ordering::Greater)
+                } else {
+                    None
+                }
+            }
         }
     }
 }
 
-impl Ord for DataLattice {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.partial_cmp(other).unwrap()
-    }
-}
-
-impl DataLattice {
-    pub fn top() -> Self {
-        Self {
-            element: DataTerm::Top,
-            join_element: None,
-            meet_elements: Vec::new(),
-        }
-    }
-
-    pub fn is_disjoint(&self, lattice: &DataLattice) -> bool {
-        // PartialOrd::partial_cmpで比較してNoneのとき互いに素
-
-        if self != lattice {
-            return true;
-        } else {
-            self.meet_elements
-                .iter()
-                .all(|l| l.as_ref().borrow().is_disjoint(lattice))
-        }
-    }
-
-    pub fn union(&mut self, mut lattice: DataLattice) {
-        // dbg!("union", self.is_disjoint(&lattice), &self, &lattice);
-        if !self.is_disjoint(&lattice) {
-            return;
-        }
-
-        if self != &lattice {
-            self.meet_elements.push(Rc::new(RefCell::new(lattice)));
-            return;
-        }
-
-        let min = self.min(&mut lattice);
-        let max = (&mut lattice).max(self);
-
-        // self.meet_elements
-        //     .iter()
-        //     .for_each(|lhs| lhs.as_ref().borrow_mut().union(lattice));
-
-        // self.meet_elements.push(Rc::new(RefCell::new(lattice)));
-    }
-
-    pub fn intersection(&mut self, lattice: DataLattice) {
-        if !self.is_disjoint(&lattice) {
-            return;
+impl JoinSemiLattice for DataLattice {
+    /// The nearest union both are below, `Top` if they are in different
+    /// trees.
+    fn join(&self, lhs: &DataElem, rhs: &DataElem) -> DataElem {
+        match (lhs, rhs) {
+            (DataElem::Top, _) | (_, DataElem::Top) => DataElem::Top,
+            (DataElem::Bottom, elem) | (elem, DataElem::Bottom) => elem.clone(),
+            (DataElem::Data(lhs), DataElem::Data(rhs)) => {
+                let above = self.ancestors(rhs);
+
+                self.ancestors(lhs)
+                    .into_iter()
+                    .find(|ident| above.contains(ident))
+                    .map_or(DataElem::Top, |ident| DataElem::Data(ident.clone()))
+            }
         }
-
-        self.meet_elements = Vec::new();
     }
+}
 
-    pub fn remove(
-        l: Rc<RefCell<DataLattice>>,
-        ident: &ExistsIdent,
-    ) -> Option<Rc<RefCell<DataLattice>>> {
-        let idx = l
-            .as_ref()
-            .borrow()
-            .meet_elements
-            .iter()
-            .position(|l| Self::lookup_from_top(l.clone(), ident).is_some());
-
-        if let Some(idx) = idx {
-            Some(l.as_ref().borrow_mut().meet_elements.remove(idx))
-        } else {
-            None
+impl MeetSemiLattice for DataLattice {
+    /// The lower of the two, `Bottom` if they are disjoint. In a tree nothing
+    /// else is below two elements that are not comparable.
+    fn meet(&self, lhs: &DataElem, rhs: &DataElem) -> DataElem {
+        if self.is_disjoint(lhs, rhs) {
+            return DataElem::Bottom;
         }
-    }
-
-    pub fn lookup_from_top(
-        lattice: Rc<RefCell<DataLattice>>,
-        ident: &ExistsIdent,
-    ) -> Option<Rc<RefCell<DataLattice>>> {
-        match lattice.as_ref().borrow().element {
-            // TODO: パラメータのチェック
-            DataTerm::Constructor(ref _modifier, ref i, _) if i == ident => {
-                return Some(lattice.clone())
-            }
-            _ => {}
-        };
 
-        lattice
-            .as_ref()
-            .borrow()
-            .meet_elements
-            .iter()
-            .find_map(|l| Self::lookup_from_top(l.clone(), ident))
-    }
-}
-
-impl From<DataTerm> for DataLattice {
-    fn from(term: DataTerm) -> Self {
-        Self {
-            element: term,
-            join_element: None,
-            meet_elements: Vec::new(),
+        match self.le(lhs, rhs) {
+            true => lhs.clone(),
+            false => rhs.clone(),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::cmp::Ordering;
-
     use super::*;
 
-    #[test]
-    fn union_test1() {
-        let t = DataLattice::from(DataTerm::Constructor(
-            DataModifier::Nominal,
-            ExistsIdent("True".to_string()),
-            Vec::new(),
-        ));
-        let f = DataLattice::from(DataTerm::Constructor(
-            DataModifier::Nominal,
-            ExistsIdent("False".to_string()),
-            Vec::new(),
-        ));
+    fn data(ident: &str) -> DataElem {
+        DataElem::Data(ExistsIdent::from(ident))
+    }
 
-        let l = Rc::new(RefCell::new(DataLattice::top()));
-        l.as_ref().borrow_mut().union(t);
-        l.as_ref().borrow_mut().union(f);
+    // data Bool = True | False
+    // data Answer = Bool | Unknown
+    // data Color = Red | Green
+    fn lattice() -> DataLattice {
+        [
+            ("True", "Bool"),
+            ("False", "Bool"),
+            ("Bool", "Answer"),
+            ("Unknown", "Answer"),
+            ("Red", "Color"),
+            ("Green", "Color"),
+        ]
+        .into_iter()
+        .fold(DataLattice::default(), |mut lattice, (member, union)| {
+            lattice
+                .insert(ExistsIdent::from(member), ExistsIdent::from(union))
+                .unwrap();
 
-        assert!(matches!(
-            DataLattice::lookup_from_top(l.clone(), &ExistsIdent::from("True")),
-            Some(_)
-        ));
-        assert!(matches!(
-            DataLattice::lookup_from_top(l.clone(), &ExistsIdent::from("False")),
-            Some(_)
-        ));
+            lattice
+        })
     }
 
     #[test]
-    fn union_test2() {
-        let t = DataLattice::from(DataTerm::Constructor(
-            DataModifier::Nominal,
-            ExistsIdent("True".to_string()),
-            Vec::new(),
-        ));
-        let f = DataLattice::from(DataTerm::Constructor(
-            DataModifier::Nominal,
-            ExistsIdent("False".to_string()),
-            Vec::new(),
-        ));
-
-        let l1 = Rc::new(RefCell::new(DataLattice::top()));
-        l1.as_ref().borrow_mut().union(t);
-        l1.as_ref().borrow_mut().union(f);
-
-        let i = DataLattice::from(DataTerm::Constructor(
-            DataModifier::Nominal,
-            ExistsIdent("Int".to_string()),
-            Vec::new(),
-        ));
-
-        let l2 = Rc::new(RefCell::new(DataLattice::top()));
-        l2.as_ref().borrow_mut().union(i);
+    fn partial_ord_test() {
+        let lattice = lattice();
+
+        assert_eq!(
+            lattice.partial_cmp(&data("True"), &data("True")),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(
+            lattice.partial_cmp(&data("True"), &data("Answer")),
+            Some(Ordering::Less)
+        );
+        assert_eq!(
+            lattice.partial_cmp(&data("Bool"), &data("False")),
+            Some(Ordering::Greater)
+        );
+        assert!(lattice.is_disjoint(&data("True"), &data("False")));
+        assert!(lattice.is_disjoint(&data("True"), &data("Red")));
+        assert!(lattice.le(&DataElem::Bottom, &data("Red")));
+        assert!(lattice.le(&data("Red"), &DataElem::Top));
+    }
 
-        let l3 = Rc::new(RefCell::new(DataLattice::top()));
-        dbg!(&l1);
-        dbg!(&l2);
-        dbg!(&l1.as_ref().borrow().is_disjoint(&l2.as_ref().borrow()));
-        l3.as_ref().borrow_mut().union(l1.take());
-        l3.as_ref().borrow_mut().union(l2.take());
-        dbg!("l3", &l3);
-        assert!(false);
+    #[test]
+    fn join_test() {
+        let lattice = lattice();
 
-        assert!(matches!(
-            DataLattice::lookup_from_top(l3.clone(), &ExistsIdent::from("True")),
-            Some(_)
-        ));
-        assert!(matches!(
-            DataLattice::lookup_from_top(l3.clone(), &ExistsIdent::from("False")),
-            Some(_)
-        ));
-        assert!(matches!(
-            DataLattice::lookup_from_top(l3.clone(), &ExistsIdent::from("Int")),
-            Some(_)
-        ));
+        assert_eq!(lattice.join(&data("True"), &data("False")), data("Bool"));
+        assert_eq!(
+            lattice.join(&data("True"), &data("Unknown")),
+            data("Answer")
+        );
+        assert_eq!(lattice.join(&data("True"), &data("Bool")), data("Bool"));
+        assert_eq!(lattice.join(&data("True"), &data("Red")), DataElem::Top);
+        assert_eq!(lattice.join(&DataElem::Bottom, &data("Red")), data("Red"));
     }
 
     #[test]
-    fn partial_ord_test() {
-        let t = DataLattice::from(DataTerm::Constructor(
-            DataModifier::Nominal,
-            ExistsIdent("True".to_string()),
-            Vec::new(),
-        ));
-        let f = DataLattice::from(DataTerm::Constructor(
-            DataModifier::Nominal,
-            ExistsIdent("False".to_string()),
-            Vec::new(),
-        ));
+    fn meet_test() {
+        let lattice = lattice();
 
-        assert!(matches!(t.partial_cmp(&f), None));
-        assert!(matches!(t.partial_cmp(&t), Some(Ordering::Equal)));
-
-        let mut bool = DataLattice::top();
-        bool.union(t);
-        bool.union(f);
+        assert_eq!(lattice.meet(&data("True"), &data("Answer")), data("True"));
+        assert_eq!(
+            lattice.meet(&data("True"), &data("False")),
+            DataElem::Bottom
+        );
+        assert_eq!(lattice.meet(&DataElem::Top, &data("Red")), data("Red"));
+    }
 
-        assert!(matches!(
-            bool.partial_cmp(&DataLattice::from(DataTerm::Constructor(
-                DataModifier::Nominal,
-                ExistsIdent("True".to_string()),
-                Vec::new(),
-            ))),
-            Some(Ordering::Less)
-        ));
+    #[test]
+    fn insert_test() {
+        let mut lattice = lattice();
+
+        assert_eq!(
+            lattice.insert(ExistsIdent::from("True"), ExistsIdent::from("Truthy")),
+            Err(ExistsIdent::from("Bool"))
+        );
+        assert_eq!(
+            lattice.insert(ExistsIdent::from("Answer"), ExistsIdent::from("True")),
+            Ok(())
+        );
+        assert_eq!(
+            lattice.partial_cmp(&data("Answer"), &data("True")),
+            Some(Ordering::Greater)
+        );
     }
 }
@@ -1,4 +1,6 @@
-// mod lattice;
+#[allow(clippy::module_inception)]
+mod lattice;
 mod semi_lattice;
 
-// pub use lattice::*;
+pub use lattice::*;
+pub use semi_lattice::*;
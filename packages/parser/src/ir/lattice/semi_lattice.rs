@@ -1,59 +1,29 @@
-use crate::ir::DataTerm;
+use std::cmp::Ordering;
 
-trait SemiLattice: PartialOrd {
+/// A partial order over `Elem`, held by the structure rather than the elements.
+pub trait SemiLattice {
     type Elem;
 
-    fn is_disjoint(&self, other: &Self) -> bool {
-        self.partial_cmp(other).is_none()
-    }
-
-    fn union(self, other: &Self) -> Self;
-}
+    fn partial_cmp(&self, lhs: &Self::Elem, rhs: &Self::Elem) -> Option<Ordering>;
 
-trait JoinSemiLattice: SemiLattice {
-    fn join(&self, other: &Self) -> Self;
-}
-
-#[derive(Debug, PartialEq)]
-struct DataJoinSemiLattice {}
-
-impl PartialOrd for DataJoinSemiLattice {
-    fn partial_cmp(&self, _other: &Self) -> Option<std::cmp::Ordering> {
-        unimplemented!()
+    fn is_disjoint(&self, lhs: &Self::Elem, rhs: &Self::Elem) -> bool {
+        self.partial_cmp(lhs, rhs).is_none()
     }
-}
 
-impl SemiLattice for DataJoinSemiLattice {
-    type Elem = DataTerm;
-
-    fn union(self, _other: &Self) -> Self {
-        unimplemented!()
+    fn le(&self, lhs: &Self::Elem, rhs: &Self::Elem) -> bool {
+        matches!(
+            self.partial_cmp(lhs, rhs),
+            Some(Ordering::Less | Ordering::Equal)
+        )
     }
 }
 
-impl JoinSemiLattice for DataJoinSemiLattice {
-    fn join(&self, _other: &Self) -> Self {
-        unimplemented!()
-    }
+/// Any two elements have a least upper bound.
+pub trait JoinSemiLattice: SemiLattice {
+    fn join(&self, lhs: &Self::Elem, rhs: &Self::Elem) -> Self::Elem;
 }
 
-#[cfg(test)]
-mod tests {
-    
-    
-
-    #[test]
-    fn test_join() {
-        // let ast1 = prose_down_script_parse("nominal True = ()").unwrap();
-        // let ast2 = prose_down_script_parse("nominal True = ()").unwrap();
-        // let ir1 = super::super::super::ir1::transform1(&ast1);
-        // let ir2 = super::super::super::ir2::transform2(ir1, ast1);
-
-        // let a = DataJoinSemiLattice::try_from(ast1).unwrap();
-        // let b = DataJoinSemiLattice::new();
-
-        // let c = a.join(&b);
-
-        // assert_eq!(c, DataLattice::new());
-    }
+/// Any two elements have a greatest lower bound.
+pub trait MeetSemiLattice: SemiLattice {
+    fn meet(&self, lhs: &Self::Elem, rhs: &Self::Elem) -> Self::Elem;
 }
@@ -0,0 +1,100 @@
+use super::{
+    default, heads,
+    pattern::{product, Head, Pat},
+    specialize, Constructors, Row,
+};
+use crate::ir::PatternExpr;
+use std::iter::once;
+
+/// Which equation the arguments of a definition select. A switch tests the
+/// value at `path`, the index of the argument and then of the fields in it,
+/// and each value is tested at most once on the way to a leaf.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Decision {
+    /// No equation matches.
+    Fail,
+    Leaf(usize),
+    Switch {
+        path: Vec<usize>,
+        cases: Vec<(Head, Decision)>,
+        /// The values none of the cases match, `None` when the cases cover the
+        /// type.
+        default: Option<Box<Decision>>,
+    },
+}
+
+impl Decision {
+    /// Compiles the patterns of each equation, the first equation that matches
+    /// is selected.
+    pub fn compile(equations: &[Vec<&PatternExpr>], constructors: &Constructors) -> Self {
+        let arity = equations.first().map_or(0, Vec::len);
+        let rows = equations
+            .iter()
+            .enumerate()
+            .filter(|(_, patterns)| patterns.len() == arity)
+            .flat_map(|(equation, patterns)| {
+                let pats = patterns.iter().copied().map(Pat::from).collect::<Vec<_>>();
+
+                product(&pats)
+                    .into_iter()
+                    .map(move |pats| Row { pats, equation })
+            })
+            .collect();
+
+        compile(
+            (0..arity).map(|idx| vec![idx]).collect(),
+            rows,
+            constructors,
+        )
+    }
+}
+
+/// Tests the first column the first row does not bind, the first row is
+/// selected once it only binds.
+fn compile(
+    mut paths: Vec<Vec<usize>>,
+    mut rows: Vec<Row>,
+    constructors: &Constructors,
+) -> Decision {
+    let first = match rows.first() {
+        Some(row) => row,
+        None => return Decision::Fail,
+    };
+    let column = match first.pats.iter().position(|pat| pat != &Pat::Any) {
+        Some(column) => column,
+        None => return Decision::Leaf(first.equation),
+    };
+    paths.swap(0, column);
+    rows.iter_mut().for_each(|row| row.pats.swap(0, column));
+
+    let (path, rest) = paths.split_first().unwrap();
+    let heads = heads(&rows);
+    let cases = heads
+        .iter()
+        .map(|(head, arity)| {
+            let paths = (0..*arity)
+                .map(|idx| path.iter().copied().chain(once(idx)).collect())
+                .chain(rest.iter().cloned())
+                .collect();
+
+            (
+                head.clone(),
+                compile(paths, specialize(&rows, head, *arity), constructors),
+            )
+        })
+        .collect();
+    let default = match constructors.signature(&heads) {
+        Some(_) => None,
+        None => Some(Box::new(compile(
+            rest.to_vec(),
+            default(&rows),
+            constructors,
+        ))),
+    };
+
+    Decision::Switch {
+        path: path.clone(),
+        cases,
+        default,
+    }
+}
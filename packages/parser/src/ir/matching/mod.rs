@@ -0,0 +1,396 @@
+mod decision;
+mod pattern;
+mod warning;
+
+pub use self::decision::Decision;
+pub use self::pattern::{Head, Pat};
+pub use self::warning::MatchWarning;
+
+use self::pattern::product;
+use super::{
+    ir2::IR2, type_check::ImplKey, Abstruction, Apply, ApplyEff, ApplyInst, DataConstructor,
+    DataExpr, DataTerm, ExistsIdent, Expr, Handle, Literal, PatternExpr, Span, TypeIdent,
+};
+use std::{collections::HashMap, iter::once, rc::Rc};
+
+/// The constructors of the data types, by each of their constructors.
+#[derive(Debug, Default)]
+pub struct Constructors(HashMap<ExistsIdent, Rc<Vec<(ExistsIdent, usize)>>>);
+
+/// A row of the pattern matrix, the patterns of an equation without `Or`.
+#[derive(Debug, Clone)]
+struct Row {
+    pats: Vec<Pat>,
+    equation: usize,
+}
+
+type Equations<'a> = Vec<(&'a Abstruction, Span)>;
+
+/// Checks the equations of each definition and the clauses of each `handle`
+/// against the constructors of the data types, for values no equation matches
+/// and equations that match nothing the ones before them do not. Warnings are
+/// in source order.
+pub fn check_matches(ir: &IR2) -> Vec<MatchWarning> {
+    let constructors = Constructors::from(ir);
+    let functions = ir.ident_symbols.iter().map(|(ident, (functions, _))| {
+        let equations = functions.iter().map(|f| (&f.expr, f.span)).collect();
+
+        (ident.0.clone(), equations)
+    });
+    let handlers = ir.handler_symbols.iter().map(|(ident, (functions, _))| {
+        let equations = functions.iter().map(|f| (&f.expr, f.span)).collect();
+
+        (ident.0.clone(), equations)
+    });
+    let methods = ir.impls.iter().flat_map(|def| {
+        let key = ImplKey::of(def).map_or(def.ident.0.clone(), |key| key.to_string());
+
+        group(def.methods.iter().map(|f| (&f.ident, &f.expr, f.span)))
+            .into_iter()
+            .map(move |(ident, equations)| (format!("{ident} of impl {key}"), equations))
+    });
+    let where_clauses = ir
+        .ident_symbols
+        .iter()
+        .flat_map(|(_, (functions, _))| functions.iter().map(|f| &f.where_clause))
+        .chain(
+            ir.handler_symbols
+                .iter()
+                .flat_map(|(_, (functions, _))| functions.iter().map(|f| &f.where_clause)),
+        )
+        .chain(
+            ir.impls
+                .iter()
+                .flat_map(|def| def.methods.iter().map(|f| &f.where_clause)),
+        )
+        .flat_map(locals);
+    let definitions = functions
+        .chain(handlers)
+        .chain(methods)
+        .chain(where_clauses)
+        .collect::<Vec<(String, Equations)>>();
+    let clauses = definitions
+        .iter()
+        .flat_map(|(_, equations)| equations)
+        .flat_map(|(abstruction, _)| handles(&abstruction.expr))
+        .flat_map(|Handle { handlers, .. }| {
+            group(handlers.iter().map(|f| (&f.ident, &f.expr, f.span)))
+                .into_iter()
+                .map(|(ident, equations)| (ident.0.clone(), equations))
+        })
+        .collect::<Vec<_>>();
+
+    let mut warnings = definitions
+        .iter()
+        .chain(&clauses)
+        .flat_map(|(name, equations)| check(name, equations, &constructors))
+        .collect::<Vec<_>>();
+    warnings.sort_by_key(|warning| warning.span().start);
+
+    warnings
+}
+
+fn check(name: &str, equations: &Equations, constructors: &Constructors) -> Vec<MatchWarning> {
+    let rows = equations
+        .iter()
+        .map(|(abstruction, _)| {
+            params(abstruction)
+                .into_iter()
+                .map(Pat::from)
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    // the type checker reports equations with different numbers of arguments
+    let arity = match rows.first() {
+        Some(row) if rows.iter().all(|other| other.len() == row.len()) => row.len(),
+        _ => return Vec::new(),
+    };
+
+    let (mut warnings, matrix) = rows.iter().zip(equations).enumerate().fold(
+        (Vec::new(), Vec::new()),
+        |(mut warnings, mut matrix), (equation, (row, (_, span)))| {
+            if missing(&matrix, row, constructors).is_none() {
+                warnings.push(MatchWarning::Unreachable {
+                    span: *span,
+                    name: name.to_string(),
+                });
+            }
+            matrix.extend(product(row).into_iter().map(|pats| Row { pats, equation }));
+
+            (warnings, matrix)
+        },
+    );
+    if let Some(patterns) = missing(&matrix, &vec![Pat::Any; arity], constructors) {
+        warnings.push(MatchWarning::NonExhaustive {
+            span: equations[0].1,
+            name: name.to_string(),
+            patterns,
+        });
+    }
+
+    warnings
+}
+
+/// Values `row` matches that no row of `rows` does, as a pattern for each
+/// column. `None` when there are none.
+fn missing(rows: &[Row], row: &[Pat], constructors: &Constructors) -> Option<Vec<Pat>> {
+    let (first, rest) = match row.split_first() {
+        Some(split) => split,
+        None => return rows.is_empty().then(Vec::new),
+    };
+    let missing_with = |head: &Head, args: Vec<Pat>| {
+        let arity = args.len();
+        let row = args
+            .into_iter()
+            .chain(rest.iter().cloned())
+            .collect::<Vec<_>>();
+
+        missing(&specialize(rows, head, arity), &row, constructors)
+            .map(|witness| rebuild(head, arity, witness))
+    };
+
+    match first {
+        Pat::Head(head, args) => missing_with(head, args.clone()),
+        Pat::Or(lhs, rhs) => [lhs, rhs].into_iter().find_map(|alternative| {
+            let row = once(alternative.as_ref().clone())
+                .chain(rest.iter().cloned())
+                .collect::<Vec<_>>();
+
+            missing(rows, &row, constructors)
+        }),
+        Pat::Any => {
+            let heads = heads(rows);
+            if let Some(signature) = constructors.signature(&heads) {
+                return signature
+                    .iter()
+                    .find_map(|(head, arity)| missing_with(head, vec![Pat::Any; *arity]));
+            }
+
+            let witness = missing(&default(rows), rest, constructors)?;
+            let first = match constructors.absent(&heads) {
+                Some((head, arity)) => Pat::Head(head, vec![Pat::Any; arity]),
+                None => Pat::Any,
+            };
+
+            Some(once(first).chain(witness).collect())
+        }
+    }
+}
+
+/// `C p1 .. pn` from the first `n` patterns of `witness`.
+fn rebuild(head: &Head, arity: usize, mut witness: Vec<Pat>) -> Vec<Pat> {
+    let rest = witness.split_off(arity);
+
+    once(Pat::Head(head.clone(), witness)).chain(rest).collect()
+}
+
+/// The heads of the first column and their arities, in order.
+fn heads(rows: &[Row]) -> Vec<(Head, usize)> {
+    rows.iter()
+        .fold(Vec::new(), |mut heads: Vec<(Head, usize)>, row| {
+            if let Some(Pat::Head(head, args)) = row.pats.first() {
+                if !heads.iter().any(|(other, _)| other == head) {
+                    heads.push((head.clone(), args.len()));
+                }
+            }
+
+            heads
+        })
+}
+
+/// The rows that match `head` in the first column, with its fields in place of
+/// it.
+fn specialize(rows: &[Row], head: &Head, arity: usize) -> Vec<Row> {
+    rows.iter()
+        .filter_map(|row| {
+            let (first, rest) = row.pats.split_first()?;
+            let fields = match first {
+                Pat::Head(other, args) if other == head && args.len() == arity => args.clone(),
+                Pat::Head(_, _) => return None,
+                _ => vec![Pat::Any; arity],
+            };
+
+            Some(Row {
+                pats: fields.into_iter().chain(rest.iter().cloned()).collect(),
+                equation: row.equation,
+            })
+        })
+        .collect()
+}
+
+/// The rows that match any value in the first column, without it.
+fn default(rows: &[Row]) -> Vec<Row> {
+    rows.iter()
+        .filter(|row| !matches!(row.pats.first(), Some(Pat::Head(_, _))))
+        .map(|row| Row {
+            pats: row.pats[1..].to_vec(),
+            equation: row.equation,
+        })
+        .collect()
+}
+
+/// The argument patterns of curried abstractions.
+fn params(abstruction: &Abstruction) -> Vec<&PatternExpr> {
+    match abstruction {
+        Abstruction {
+            arg: Some(pattern),
+            expr: Expr::Abstruction(inner),
+        } => once(pattern).chain(params(inner)).collect(),
+        Abstruction {
+            arg: Some(pattern), ..
+        } => vec![pattern],
+        Abstruction { arg: None, .. } => Vec::new(),
+    }
+}
+
+/// The equations of each ident, in order.
+/// The functions of a where-clause and of the where-clauses of their
+/// equations.
+fn locals(where_clause: &IR2) -> Vec<(String, Equations<'_>)> {
+    where_clause
+        .functions()
+        .into_iter()
+        .flat_map(|(ident, functions)| {
+            let equations = functions.iter().map(|f| (&f.expr, f.span)).collect();
+
+            once((ident.0.clone(), equations))
+                .chain(functions.iter().flat_map(|f| locals(&f.where_clause)))
+        })
+        .collect()
+}
+
+fn group<'a, T: PartialEq>(
+    equations: impl Iterator<Item = (T, &'a Abstruction, Span)>,
+) -> Vec<(T, Equations<'a>)> {
+    equations.fold(
+        Vec::new(),
+        |mut groups: Vec<(T, Equations)>, (ident, expr, span)| {
+            match groups.iter_mut().find(|(other, _)| other == &ident) {
+                Some((_, equations)) => equations.push((expr, span)),
+                None => groups.push((ident, vec![(expr, span)])),
+            }
+
+            groups
+        },
+    )
+}
+
+/// The `handle`s in `expr`, outer ones first.
+fn handles(expr: &Expr) -> Vec<&Handle> {
+    match expr {
+        Expr::Literal(Literal::Tuple(_, items) | Literal::List(items)) => {
+            items.iter().flat_map(handles).collect()
+        }
+        Expr::Abstruction(abstruction) => handles(&abstruction.expr),
+        Expr::Apply(Apply {
+            abstruction, expr, ..
+        }) => handles(&abstruction.expr)
+            .into_iter()
+            .chain(handles(expr))
+            .collect(),
+        Expr::ApplyEmbedded(ApplyInst { expr, .. }) | Expr::ApplyEff(ApplyEff { expr, .. }) => {
+            handles(expr)
+        }
+        Expr::Handle(handle) => once(handle)
+            .chain(handles(&handle.expr))
+            .chain(
+                handle
+                    .handlers
+                    .iter()
+                    .flat_map(|handler| handles(&handler.expr.expr)),
+            )
+            .collect(),
+        Expr::Literal(_)
+        | Expr::Reference(_)
+        | Expr::ReferenceInst(_)
+        | Expr::ReferenceHandler(_)
+        | Expr::ReferenceData(_)
+        | Expr::Pattern(_) => Vec::new(),
+    }
+}
+
+impl Constructors {
+    pub fn arity(&self, ident: &ExistsIdent) -> Option<usize> {
+        self.0
+            .get(ident)?
+            .iter()
+            .find(|(constructor, _)| constructor == ident)
+            .map(|(_, arity)| *arity)
+    }
+
+    /// Every head of the type of `heads`, when `heads` has each of them.
+    fn signature(&self, heads: &[(Head, usize)]) -> Option<Vec<(Head, usize)>> {
+        match heads.first()? {
+            (Head::Tuple(_), _) => Some(heads[..1].to_vec()),
+            (Head::Nil | Head::Cons, _) => {
+                let siblings = vec![(Head::Nil, 0), (Head::Cons, 2)];
+
+                (heads.len() == siblings.len()).then_some(siblings)
+            }
+            (Head::Constructor(ident), _) => {
+                let siblings = self
+                    .0
+                    .get(ident)?
+                    .iter()
+                    .map(|(ident, arity)| (Head::Constructor(ident.clone()), *arity))
+                    .collect::<Vec<_>>();
+
+                siblings
+                    .iter()
+                    .all(|(sibling, _)| heads.iter().any(|(head, _)| head == sibling))
+                    .then_some(siblings)
+            }
+            _ => None,
+        }
+    }
+
+    /// A constructor of the type of `heads` that is not in it.
+    fn absent(&self, heads: &[(Head, usize)]) -> Option<(Head, usize)> {
+        match heads.first()? {
+            (Head::Nil, _) => Some((Head::Cons, 2)),
+            (Head::Cons, _) => Some((Head::Nil, 0)),
+            (Head::Constructor(ident), _) => self
+                .0
+                .get(ident)?
+                .iter()
+                .map(|(ident, arity)| (Head::Constructor(ident.clone()), *arity))
+                .find(|(sibling, _)| !heads.iter().any(|(head, _)| head == sibling)),
+            _ => None,
+        }
+    }
+}
+
+/// The constructors of a union win over a `nominal data True = ()` of the same
+/// name.
+impl From<&IR2> for Constructors {
+    fn from(ir: &IR2) -> Self {
+        let (values, unions): (Vec<_>, Vec<_>) = ir
+            .data_symbols
+            .iter()
+            .partition(|(_, def)| matches!(def.expr(), DataExpr::Term(DataTerm::Unit)));
+
+        let mut constructors = HashMap::new();
+        values.into_iter().for_each(|(ident, _)| {
+            if let TypeIdent::ExistsIdent(ident) = ident {
+                constructors.insert(ident.clone(), Rc::new(vec![(ident.clone(), 0)]));
+            }
+        });
+        unions.into_iter().for_each(|(_, def)| {
+            let siblings = Rc::new(
+                def.expr()
+                    .constructors()
+                    .into_iter()
+                    .filter_map(|DataConstructor(_, ident, args)| match ident {
+                        TypeIdent::ExistsIdent(ident) => Some((ident.clone(), args.len())),
+                        TypeIdent::ForallIdent(_) => None,
+                    })
+                    .collect::<Vec<_>>(),
+            );
+            siblings.iter().for_each(|(ident, _)| {
+                constructors.insert(ident.clone(), siblings.clone());
+            });
+        });
+
+        Self(constructors)
+    }
+}
@@ -0,0 +1,200 @@
+use crate::ir::{DataConstructor, DataTerm, ExistsIdent, Expr, Literal, PatternExpr, TypeIdent};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use std::fmt::Display;
+
+/// A pattern as the match compiler sees it. Binds are `Any`, the interpreter
+/// binds them once an equation is selected.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pat {
+    Any,
+    Head(Head, Vec<Pat>),
+    Or(Box<Pat>, Box<Pat>),
+}
+
+/// What a switch of a decision tree tests a value for. Literals are the
+/// constructors of types that have infinitely many of them. Lists, and `Text`
+/// as `[Char]`, are `Nil` or a `Cons` of the head and the tail.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Head {
+    Constructor(ExistsIdent),
+    Tuple(usize),
+    Nil,
+    Cons,
+    Char(char),
+    Int(isize),
+    /// The bits of a float, which is `Eq` as them.
+    Float(u64),
+    Integer(BigInt),
+    Rational(BigRational),
+}
+
+impl Pat {
+    /// The patterns without `Or`, in the order they are tried.
+    pub(super) fn alternatives(&self) -> Vec<Pat> {
+        match self {
+            Pat::Any => vec![Pat::Any],
+            Pat::Or(lhs, rhs) => lhs
+                .alternatives()
+                .into_iter()
+                .chain(rhs.alternatives())
+                .collect(),
+            Pat::Head(head, args) => product(args)
+                .into_iter()
+                .map(|args| Pat::Head(head.clone(), args))
+                .collect(),
+        }
+    }
+}
+
+/// The rows of `pats` without `Or`, `(A | B) C` is `A C` and `B C`.
+pub(super) fn product(pats: &[Pat]) -> Vec<Vec<Pat>> {
+    pats.iter().fold(vec![Vec::new()], |rows, pat| {
+        let alternatives = pat.alternatives();
+
+        rows.into_iter()
+            .flat_map(|row| {
+                alternatives.iter().map(move |alternative| {
+                    let mut row = row.clone();
+                    row.push(alternative.clone());
+
+                    row
+                })
+            })
+            .collect()
+    })
+}
+
+impl From<&PatternExpr> for Pat {
+    fn from(pattern: &PatternExpr) -> Self {
+        match pattern {
+            PatternExpr::Or(lhs, rhs) => {
+                Pat::Or(Box::new(lhs.as_ref().into()), Box::new(rhs.as_ref().into()))
+            }
+            PatternExpr::Literal(literal) => literal.into(),
+            PatternExpr::Bind(_) | PatternExpr::Any => Pat::Any,
+            PatternExpr::Constructor(DataConstructor(_, TypeIdent::ExistsIdent(ident), args)) => {
+                Pat::Head(
+                    Head::Constructor(ident.clone()),
+                    args.iter().map(Pat::from).collect(),
+                )
+            }
+            PatternExpr::Constructor(_) => Pat::Any,
+            PatternExpr::Tuple(_, items) => Pat::Head(
+                Head::Tuple(items.len()),
+                items.iter().map(Pat::from).collect(),
+            ),
+            PatternExpr::ListHead(head, tail) => {
+                Pat::Head(Head::Cons, vec![head.as_ref().into(), tail.as_ref().into()])
+            }
+        }
+    }
+}
+
+impl From<&Literal> for Pat {
+    fn from(literal: &Literal) -> Self {
+        let items = |items: &[Expr]| {
+            items
+                .iter()
+                .map(|item| match item {
+                    Expr::Literal(literal) => literal.into(),
+                    Expr::ReferenceData(TypeIdent::ExistsIdent(ident)) => {
+                        Pat::Head(Head::Constructor(ident.clone()), Vec::new())
+                    }
+                    _ => Pat::Any,
+                })
+                .collect()
+        };
+
+        match literal {
+            Literal::Char(value) => Pat::Head(Head::Char(*value), Vec::new()),
+            Literal::Text(value) => list(
+                value
+                    .chars()
+                    .map(|value| Pat::Head(Head::Char(value), Vec::new()))
+                    .collect(),
+            ),
+            Literal::Int(value) => Pat::Head(Head::Int(*value), Vec::new()),
+            Literal::Float(value) => Pat::Head(Head::Float(value.to_bits()), Vec::new()),
+            Literal::Integer(value) => Pat::Head(Head::Integer(value.clone()), Vec::new()),
+            Literal::Rational(value) => Pat::Head(Head::Rational(value.clone()), Vec::new()),
+            Literal::Tuple(_, values) => Pat::Head(Head::Tuple(values.len()), items(values)),
+            Literal::List(values) => list(items(values)),
+        }
+    }
+}
+
+/// `[a b]` is `Cons a (Cons b Nil)`.
+fn list(items: Vec<Pat>) -> Pat {
+    items
+        .into_iter()
+        .rev()
+        .fold(Pat::Head(Head::Nil, Vec::new()), |tail, head| {
+            Pat::Head(Head::Cons, vec![head, tail])
+        })
+}
+
+/// Constructor patterns are parsed as type expressions, a lower-case ident
+/// binds and an upper-case one matches.
+impl From<&DataTerm> for Pat {
+    fn from(term: &DataTerm) -> Self {
+        match term {
+            DataTerm::Constructor(DataConstructor(_, TypeIdent::ExistsIdent(ident), args)) => {
+                Pat::Head(
+                    Head::Constructor(ident.clone()),
+                    args.iter().map(Pat::from).collect(),
+                )
+            }
+            DataTerm::Unit => Pat::Head(Head::Tuple(0), Vec::new()),
+            _ => Pat::Any,
+        }
+    }
+}
+
+/// The patterns of the arguments of an equation, `Some _ Nil`.
+pub(super) fn join(pats: &[Pat]) -> String {
+    pats.iter()
+        .map(|pat| Atom(pat).to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+struct Atom<'a>(&'a Pat);
+
+impl Display for Atom<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Pat::Head(Head::Constructor(_), args) if !args.is_empty() => write!(f, "({})", self.0),
+            Pat::Or(_, _) => write!(f, "({})", self.0),
+            pat => write!(f, "{pat}"),
+        }
+    }
+}
+
+impl Display for Pat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let join = |args: &[Pat], sep| {
+            args.iter()
+                .map(|arg| arg.to_string())
+                .collect::<Vec<_>>()
+                .join(sep)
+        };
+
+        match self {
+            Pat::Any => write!(f, "_"),
+            Pat::Or(lhs, rhs) => write!(f, "{lhs} | {rhs}"),
+            Pat::Head(Head::Constructor(ident), args) => {
+                write!(f, "{}", ident.0)?;
+                args.iter().try_for_each(|arg| write!(f, " {}", Atom(arg)))
+            }
+            Pat::Head(Head::Tuple(_), args) => write!(f, "({})", join(args, ", ")),
+            Pat::Head(Head::Nil, _) => write!(f, "[]"),
+            Pat::Head(Head::Cons, args) => write!(f, "[{}]", join(args, " : ")),
+            Pat::Head(Head::Char(value), _) => write!(f, "{value:?}"),
+            Pat::Head(Head::Int(value), _) => write!(f, "{value}"),
+            Pat::Head(Head::Float(value), _) => write!(f, "{:?}", f64::from_bits(*value)),
+            Pat::Head(Head::Integer(value), _) => write!(f, "{value}n"),
+            Pat::Head(Head::Rational(value), _) => write!(f, "{value}r"),
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use super::pattern::{join, Pat};
+use crate::ir::Span;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum MatchWarning {
+    #[error("{span}: in {name}: {} is not matched", join(.patterns))]
+    NonExhaustive {
+        span: Span,
+        name: String,
+        patterns: Vec<Pat>,
+    },
+    #[error("{span}: in {name}: the equation is unreachable")]
+    Unreachable { span: Span, name: String },
+}
+
+impl MatchWarning {
+    pub fn span(&self) -> Span {
+        match self {
+            MatchWarning::NonExhaustive { span, .. } | MatchWarning::Unreachable { span, .. } => {
+                *span
+            }
+        }
+    }
+}
@@ -1,18 +1,27 @@
+mod graph;
 pub mod ir1;
 pub mod ir2;
 mod lattice;
+pub mod matching;
+pub mod resolve;
 mod seq_gen;
+pub mod type_check;
+
+pub use crate::ast::Span;
 
 use crate::ast::{self};
 use anyhow::anyhow;
+use num_bigint::BigInt;
+use num_rational::BigRational;
 use serde::{Deserialize, Serialize};
-use std::{fmt::Display, rc::Rc};
+use std::{collections::HashSet, fmt::Display, rc::Rc};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Function<T: Default> {
     pub ident: Ident,
     pub expr: Abstruction,
     pub where_clause: T,
+    pub span: Span,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -20,6 +29,7 @@ pub struct HandlerFunction<T: Default> {
     pub ident: HandlerIdent,
     pub expr: Abstruction,
     pub where_clause: T,
+    pub span: Span,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -37,7 +47,10 @@ impl From<ast::Abstruction> for Abstruction {
     }
 }
 
-impl<T: Default> TryFrom<ast::Assign> for Function<T> {
+impl<T> TryFrom<ast::Assign> for Function<T>
+where
+    T: Default + TryFrom<ast::Module, Error = anyhow::Error>,
+{
     type Error = anyhow::Error;
 
     fn try_from(value: ast::Assign) -> Result<Self, Self::Error> {
@@ -46,11 +59,35 @@ impl<T: Default> TryFrom<ast::Assign> for Function<T> {
         Ok(Self {
             ident: value.ident.into(),
             expr,
-            where_clause: T::default(),
+            where_clause: where_clause(value.where_clause)?,
+            span: value.span,
         })
     }
 }
 
+/// The functions a where-clause defines are local to the equation, they see
+/// its arguments and each other.
+fn where_clause<T>(module: ast::Module) -> Result<T, anyhow::Error>
+where
+    T: TryFrom<ast::Module, Error = anyhow::Error>,
+{
+    let local = module.iter().find(|statement| {
+        !matches!(
+            statement,
+            ast::Statement::Assign(_)
+                | ast::Statement::AssignDef(_)
+                | ast::Statement::LineComment(_)
+        )
+    });
+    if let Some(statement) = local {
+        return Err(anyhow!(
+            "where-clauses only define functions: {statement:?}"
+        ));
+    }
+
+    T::try_from(module)
+}
+
 fn build_abstruction(mut args: Vec<ast::PatternExpr>, expr: ast::Expr) -> Abstruction {
     let pat = match args.pop() {
         Some(pat) => pat,
@@ -68,7 +105,10 @@ fn build_abstruction(mut args: Vec<ast::PatternExpr>, expr: ast::Expr) -> Abstru
     }
 }
 
-impl<T: Default> TryFrom<ast::HandlerAssign> for HandlerFunction<T> {
+impl<T> TryFrom<ast::HandlerAssign> for HandlerFunction<T>
+where
+    T: Default + TryFrom<ast::Module, Error = anyhow::Error>,
+{
     type Error = anyhow::Error;
 
     fn try_from(value: ast::HandlerAssign) -> Result<Self, Self::Error> {
@@ -77,7 +117,8 @@ impl<T: Default> TryFrom<ast::HandlerAssign> for HandlerFunction<T> {
         Ok(Self {
             ident: value.ident.into(),
             expr,
-            where_clause: T::default(),
+            where_clause: where_clause(value.where_clause)?,
+            span: value.span,
         })
     }
 }
@@ -85,7 +126,7 @@ impl<T: Default> TryFrom<ast::HandlerAssign> for HandlerFunction<T> {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TypeExpr {
     pub constraints: Vec<TraitConstraint>,
-    pub eta_env: Vec<CoroutineType>,
+    pub eta_env: Vec<HandlerConstraint>,
     pub type_abstruction: TypeAbstruction,
 }
 
@@ -99,8 +140,11 @@ impl TryFrom<ast::TypeExpr> for TypeExpr {
                 .into_iter()
                 .map(TraitConstraint::try_from)
                 .collect::<Result<Vec<_>, _>>()?,
-            // TDOO
-            eta_env: Vec::new(),
+            eta_env: value
+                .eta_envs
+                .into_iter()
+                .map(HandlerConstraint::from)
+                .collect(),
             type_abstruction: TypeAbstruction::try_from(value.expr)?,
         })
     }
@@ -109,14 +153,14 @@ impl TryFrom<ast::TypeExpr> for TypeExpr {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TraitConstraint {
     pub ident: TraitIdent,
-    pub arg: ForallIdent,
+    pub args: Vec<ForallIdent>,
 }
 
 impl From<ast::TraitConstraint> for TraitConstraint {
     fn from(value: ast::TraitConstraint) -> Self {
         Self {
             ident: value.ident.into(),
-            arg: value.arg.into(),
+            args: value.args.into_iter().map(ForallIdent::from).collect(),
         }
     }
 }
@@ -157,11 +201,61 @@ pub enum PatternExpr {
     Literal(Literal),
     Bind(Ident),
     Constructor(DataConstructor),
-    ListHead(),
+    ListHead(Box<PatternExpr>, Box<PatternExpr>),
     Tuple(usize, Vec<PatternExpr>),
     Any,
 }
 
+impl PatternExpr {
+    /// The variables the pattern binds.
+    pub fn binds(&self) -> Vec<Ident> {
+        match self {
+            PatternExpr::Or(lhs, rhs) | PatternExpr::ListHead(lhs, rhs) => {
+                lhs.binds().into_iter().chain(rhs.binds()).collect()
+            }
+            PatternExpr::Bind(ident) => vec![ident.clone()],
+            PatternExpr::Constructor(DataConstructor(_, _, args)) => {
+                args.iter().flat_map(term_binds).collect()
+            }
+            PatternExpr::Tuple(_, items) => items.iter().flat_map(PatternExpr::binds).collect(),
+            PatternExpr::Literal(literal) => literal_binds(literal),
+            PatternExpr::Any => Vec::new(),
+        }
+    }
+}
+
+/// The items of tuple and list patterns are parsed as expressions, a reference
+/// binds.
+fn literal_binds(literal: &Literal) -> Vec<Ident> {
+    match literal {
+        Literal::Tuple(_, items) | Literal::List(items) => items
+            .iter()
+            .flat_map(|item| match item {
+                Expr::Reference(ident) => vec![ident.clone()],
+                Expr::Literal(literal) => literal_binds(literal),
+                _ => Vec::new(),
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Constructor patterns are parsed as type expressions, a lower-case ident
+/// binds.
+fn term_binds(term: &DataTerm) -> Vec<Ident> {
+    match term {
+        DataTerm::Constructor(DataConstructor(_, TypeIdent::ForallIdent(ident), args))
+            if args.is_empty() =>
+        {
+            vec![Ident(ident.0.clone())]
+        }
+        DataTerm::Constructor(DataConstructor(_, _, args)) => {
+            args.iter().flat_map(term_binds).collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
 impl From<ast::PatternExpr> for PatternExpr {
     fn from(value: ast::PatternExpr) -> Self {
         match value {
@@ -170,8 +264,8 @@ impl From<ast::PatternExpr> for PatternExpr {
             }
             ast::PatternExpr::Literal(lit) => Self::Literal(lit.into()),
             ast::PatternExpr::Bind(ident) => Self::Bind(ident.into()),
-            ast::PatternExpr::ListHead() => {
-                todo!()
+            ast::PatternExpr::ListHead(head, tail) => {
+                Self::ListHead(Box::new((*head).into()), Box::new((*tail).into()))
             }
             ast::PatternExpr::Tuple(size, exprs) => {
                 Self::Tuple(size, exprs.into_iter().map(PatternExpr::from).collect())
@@ -227,7 +321,9 @@ pub enum Expr {
     Reference(Ident),
     ReferenceInst(InstIdent),
     ReferenceHandler(HandlerIdent),
+    ReferenceData(TypeIdent),
     Pattern(Pattern),
+    Handle(Handle),
 }
 
 // #[derive(Debug, Serialize, Deserialize)]
@@ -282,6 +378,7 @@ pub struct TypeAbstructionEnv {
     trait_constraints: Vec<TraitConstraint>,
     handler_constraints: Vec<HandlerConstraint>,
     abstruction: TypeAbstruction,
+    pub span: Span,
 }
 
 impl TryFrom<ast::AssignDef> for TypeAbstructionEnv {
@@ -306,6 +403,7 @@ impl TryFrom<ast::AssignDef> for TypeAbstructionEnv {
             trait_constraints,
             handler_constraints,
             abstruction,
+            span: value.span,
         })
     }
 }
@@ -332,6 +430,7 @@ impl TryFrom<ast::HandlerDef> for TypeAbstructionEnv {
             trait_constraints,
             handler_constraints,
             abstruction,
+            span: value.span,
         })
     }
 }
@@ -358,26 +457,109 @@ impl TryFrom<ast::InstDef> for TypeAbstructionEnv {
             trait_constraints,
             handler_constraints,
             abstruction,
+            span: value.span,
         })
     }
 }
 
-impl TryFrom<ast::TraitDef> for TypeAbstructionEnv {
+/// `trait (PartialEq a a, Eq a) => Ord a where { cmp :: a -> a -> Ordering }`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TraitDef {
+    pub params: Vec<ForallIdent>,
+    pub superclasses: Vec<TraitConstraint>,
+    pub methods: Vec<(Ident, TypeAbstructionEnv)>,
+    pub span: Span,
+}
+
+impl TryFrom<ast::TraitDef> for TraitDef {
     type Error = anyhow::Error;
 
     fn try_from(value: ast::TraitDef) -> Result<Self, Self::Error> {
-        let trait_constraints = value
-            .trait_constraints
+        let params = value
+            .constructor
+            .args
             .into_iter()
-            .map(|item| item.into())
-            .collect();
-        let handler_constraints = Vec::new();
-        let abstruction = TypeAbstruction::Term(TypeValue::Unknown);
+            .map(|arg| match arg {
+                ast::TypeLiteral::Constructor(ast::DataConstructor {
+                    ident: ast::TypeIdent::ForallIdent(ident),
+                    args,
+                    ..
+                }) if args.is_empty() => Ok(ident.into()),
+                arg => Err(anyhow!(
+                    "parameters of a trait must be type variables: {arg:?}"
+                )),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let methods = value
+            .where_clause
+            .statements
+            .into_iter()
+            .filter_map(|statement| match statement {
+                ast::Statement::AssignDef(def) => {
+                    let ident = Ident::from(def.ident.clone());
+
+                    Some(TypeAbstructionEnv::try_from(def).map(|sig| (ident, sig)))
+                }
+                // TODO: default methods
+                _ => None,
+            })
+            .collect::<Result<Vec<_>, _>>()?;
 
         Ok(Self {
-            trait_constraints,
-            handler_constraints,
-            abstruction,
+            params,
+            superclasses: value
+                .trait_constraints
+                .into_iter()
+                .map(TraitConstraint::from)
+                .collect(),
+            methods,
+            span: value.span,
+        })
+    }
+}
+
+/// `impl Show Bool where { show True = "True" }`, the arguments are the type
+/// constructors the impl is for.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImplDef {
+    pub ident: TraitIdent,
+    pub constraints: Vec<TraitConstraint>,
+    pub args: Vec<TypeIdent>,
+    pub methods: Vec<Function<ir2::IR2>>,
+    pub span: Span,
+}
+
+impl TryFrom<ast::ImplTrait> for ImplDef {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ast::ImplTrait) -> Result<Self, Self::Error> {
+        let ident = match value.ident {
+            ast::TypeIdent::ExistsIdent(ident) => TraitIdent(ident.0),
+            ast::TypeIdent::ForallIdent(ident) => {
+                return Err(anyhow!("{} is not a trait", ident.0))
+            }
+        };
+        let methods = value
+            .where_clause
+            .statements
+            .into_iter()
+            .filter_map(|statement| match statement {
+                ast::Statement::Assign(assign) => Some(Function::try_from(assign)),
+                ast::Statement::LineComment(_) => None,
+                statement => Some(Err(anyhow!("impls only define methods: {statement:?}"))),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            ident,
+            constraints: value
+                .constraints
+                .into_iter()
+                .map(TraitConstraint::from)
+                .collect(),
+            args: value.args.into_iter().map(TypeIdent::from).collect(),
+            methods,
+            span: value.span,
         })
     }
 }
@@ -392,18 +574,150 @@ pub struct Pattern {
 pub struct Apply {
     pub abstruction: Box<Abstruction>,
     pub expr: Box<Expr>,
+    pub span: Span,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ApplyInst {
     pub ident: InstIdent,
     pub expr: Box<Expr>,
+    pub span: Span,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ApplyEff {
     pub ident: HandlerIdent,
     pub expr: Box<Expr>,
+    pub span: Span,
+}
+
+impl Abstruction {
+    /// The variables the abstraction refers to that it does not bind, which a
+    /// closure of it captures. References to top-level functions are among
+    /// them.
+    pub fn captures(&self) -> Vec<Ident> {
+        let mut captures = Vec::new();
+        self.free(&HashSet::new(), &mut captures);
+
+        captures
+    }
+
+    fn free(&self, bound: &HashSet<Ident>, free: &mut Vec<Ident>) {
+        match &self.arg {
+            Some(pattern) => {
+                let mut bound = bound.clone();
+                bound.extend(pattern.binds());
+                self.expr.free(&bound, free);
+            }
+            None => self.expr.free(bound, free),
+        }
+    }
+}
+
+impl Expr {
+    /// Whether `ident` appears as a reference, `resume` in a handler body.
+    pub fn mentions(&self, ident: &Ident) -> bool {
+        match self {
+            Expr::Literal(Literal::Tuple(_, items) | Literal::List(items)) => {
+                items.iter().any(|item| item.mentions(ident))
+            }
+            Expr::Abstruction(abstruction) => abstruction.expr.mentions(ident),
+            Expr::Apply(Apply {
+                abstruction, expr, ..
+            }) => abstruction.expr.mentions(ident) || expr.mentions(ident),
+            Expr::ApplyEmbedded(ApplyInst { expr, .. }) | Expr::ApplyEff(ApplyEff { expr, .. }) => {
+                expr.mentions(ident)
+            }
+            Expr::Reference(reference) => reference == ident,
+            Expr::Handle(Handle { expr, .. }) => expr.mentions(ident),
+            Expr::Literal(_)
+            | Expr::ReferenceInst(_)
+            | Expr::ReferenceHandler(_)
+            | Expr::ReferenceData(_)
+            | Expr::Pattern(_) => false,
+        }
+    }
+
+    fn free(&self, bound: &HashSet<Ident>, free: &mut Vec<Ident>) {
+        match self {
+            Expr::Literal(Literal::Tuple(_, items) | Literal::List(items)) => {
+                items.iter().for_each(|item| item.free(bound, free))
+            }
+            Expr::Abstruction(abstruction) => abstruction.free(bound, free),
+            Expr::Apply(Apply {
+                abstruction, expr, ..
+            }) => {
+                abstruction.free(bound, free);
+                expr.free(bound, free);
+            }
+            Expr::ApplyEmbedded(ApplyInst { expr, .. }) | Expr::ApplyEff(ApplyEff { expr, .. }) => {
+                expr.free(bound, free)
+            }
+            Expr::Reference(ident) if !bound.contains(ident) && !free.contains(ident) => {
+                free.push(ident.clone())
+            }
+            Expr::Handle(Handle { expr, handlers }) => {
+                expr.free(bound, free);
+                let mut bound = bound.clone();
+                bound.insert("resume".into());
+                handlers
+                    .iter()
+                    .for_each(|handler| handler.expr.free(&bound, free));
+            }
+            Expr::Literal(_)
+            | Expr::Reference(_)
+            | Expr::ReferenceInst(_)
+            | Expr::ReferenceHandler(_)
+            | Expr::ReferenceData(_)
+            | Expr::Pattern(_) => {}
+        }
+    }
+
+    /// The `Expr::Reference`s in `expr`, in the order the type checker and the
+    /// interpreter number them.
+    pub fn references(&self) -> Vec<&Expr> {
+        match self {
+            Expr::Literal(Literal::Tuple(_, items) | Literal::List(items)) => {
+                items.iter().flat_map(Expr::references).collect()
+            }
+            Expr::Abstruction(abstruction) => abstruction.expr.references(),
+            Expr::Apply(Apply {
+                abstruction, expr, ..
+            }) => {
+                let mut references = abstruction.expr.references();
+                references.extend(expr.references());
+
+                references
+            }
+            Expr::ApplyEmbedded(ApplyInst { expr, .. }) | Expr::ApplyEff(ApplyEff { expr, .. }) => {
+                expr.references()
+            }
+            Expr::Reference(_) => vec![self],
+            Expr::Handle(Handle { expr, handlers }) => {
+                let mut references = expr.references();
+                references.extend(
+                    handlers
+                        .iter()
+                        .flat_map(|handler| handler.expr.expr.references()),
+                );
+
+                references
+            }
+            Expr::Literal(_)
+            | Expr::ReferenceInst(_)
+            | Expr::ReferenceHandler(_)
+            | Expr::ReferenceData(_)
+            | Expr::Pattern(_) => Vec::new(),
+        }
+    }
+}
+
+/// `handle expr where { ... }`, the handlers intercept the effects `expr`
+/// performs.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Handle {
+    pub expr: Box<Expr>,
+    pub handlers: Vec<HandlerFunction<()>>,
 }
 
 impl From<ast::Expr> for Expr {
@@ -416,20 +730,61 @@ impl From<ast::Expr> for Expr {
             ast::Expr::ApplyEff(value) => Self::ApplyEff(value.into()),
             ast::Expr::InstIdent(value) => Self::ReferenceInst(value.into()),
             ast::Expr::HandlerIdent(value) => Self::ReferenceHandler(value.into()),
-            ast::Expr::Abstruction(value) => {
-                dbg!(&value);
-                // Self::Abstruction(Box::new());
-                todo!()
-            }
+            ast::Expr::TypeIdent(value) => Self::ReferenceData(value.into()),
+            ast::Expr::Handle(value) => Self::Handle(value.into()),
+            ast::Expr::Group(expr) => (*expr).into(),
+            ast::Expr::Abstruction(ast::Lambda { params, expr }) => Self::Abstruction(Box::new(
+                build_abstruction(params.into_iter().rev().collect(), *expr),
+            )),
         }
     }
 }
 
 impl From<ast::Apply> for Apply {
     fn from(value: ast::Apply) -> Self {
+        let ast::Apply {
+            abstruction,
+            expr,
+            span,
+        } = value;
+        let abstruction = match abstruction.arg {
+            // `lhs op rhs` is `(op lhs) rhs`
+            Some(lhs) => Abstruction {
+                arg: None,
+                expr: Expr::Apply(Self {
+                    abstruction: Box::new(Abstruction {
+                        arg: None,
+                        expr: Expr::from(*abstruction.expr),
+                    }),
+                    expr: Box::new(Expr::from(*lhs)),
+                    span,
+                }),
+            },
+            None => abstruction.into(),
+        };
+
+        Self {
+            abstruction: Box::new(abstruction),
+            expr: Box::new((*expr).into()),
+            span,
+        }
+    }
+}
+
+impl From<ast::Handle> for Handle {
+    fn from(value: ast::Handle) -> Self {
         Self {
-            abstruction: Box::new(value.abstruction.into()),
             expr: Box::new((*value.expr).into()),
+            handlers: value
+                .handlers
+                .into_iter()
+                .map(|handler| HandlerFunction {
+                    ident: handler.ident.into(),
+                    expr: build_abstruction(handler.args.into_iter().rev().collect(), handler.expr),
+                    where_clause: (),
+                    span: handler.span,
+                })
+                .collect(),
         }
     }
 }
@@ -439,6 +794,7 @@ impl From<ast::ApplyInst> for ApplyInst {
         Self {
             ident: value.ident.into(),
             expr: Box::new((*value.expr).into()),
+            span: value.span,
         }
     }
 }
@@ -448,6 +804,7 @@ impl From<ast::ApplyEff> for ApplyEff {
         Self {
             ident: value.ident.into(),
             expr: Box::new((*value.expr).into()),
+            span: value.span,
         }
     }
 }
@@ -468,6 +825,9 @@ pub enum Literal {
     Char(char),
     Text(String),
     Int(isize),
+    Float(f64),
+    Integer(BigInt),
+    Rational(BigRational),
     Tuple(usize, Vec<Expr>),
     List(Vec<Expr>),
 }
@@ -478,8 +838,13 @@ impl From<ast::Literal> for Literal {
             ast::Literal::Char(value) => Self::Char(value),
             ast::Literal::Text(value) => Self::Text(value),
             ast::Literal::Int(value) => Self::Int(value),
+            ast::Literal::Float(value) => Self::Float(value),
+            ast::Literal::Integer(value) => Self::Integer(value),
+            ast::Literal::Rational(value) => Self::Rational(value),
             ast::Literal::Unit => Self::Tuple(0, Vec::new()),
-            ast::Literal::Array(_) => todo!(),
+            ast::Literal::Array(items) => {
+                Self::List(items.into_iter().map(|item| item.into()).collect())
+            }
             ast::Literal::Tuple(size, elems) => {
                 Self::Tuple(size, elems.into_iter().map(|item| item.into()).collect())
             }
@@ -493,8 +858,45 @@ impl Display for Literal {
             Literal::Char(value) => write!(f, "{value}"),
             Literal::Text(value) => write!(f, "{value}"),
             Literal::Int(value) => write!(f, "{value}"),
-            Literal::Tuple(_, _) => todo!(),
-            Literal::List(_) => todo!(),
+            Literal::Float(value) => write!(f, "{value:?}"),
+            Literal::Integer(value) => write!(f, "{value}"),
+            Literal::Rational(value) => write!(f, "{value}"),
+            Literal::Tuple(_, items) => write!(f, "({})", join(items, ", ")),
+            Literal::List(items) => write!(f, "[{}]", join(items, " ")),
+        }
+    }
+}
+
+fn join(exprs: &[Expr], sep: &str) -> String {
+    exprs
+        .iter()
+        .map(|expr| expr.to_string())
+        .collect::<Vec<_>>()
+        .join(sep)
+}
+
+/// An expression as `Value` writes what it evaluates to, with the applications
+/// in parentheses and the functions it cannot write in angle brackets.
+impl Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expr::Literal(literal) => write!(f, "{literal}"),
+            Expr::Abstruction(_) => write!(f, "<lambda>"),
+            Expr::Apply(Apply {
+                abstruction, expr, ..
+            }) => match &abstruction.arg {
+                Some(_) => write!(f, "(<lambda> {expr})"),
+                None => write!(f, "({} {expr})", abstruction.expr),
+            },
+            Expr::ApplyEmbedded(ApplyInst { ident, expr, .. }) => write!(f, "({} {expr})", ident.0),
+            Expr::ApplyEff(ApplyEff { ident, expr, .. }) => write!(f, "(eff {} {expr})", ident.0),
+            Expr::Reference(ident) => write!(f, "{ident}"),
+            Expr::ReferenceInst(ident) => write!(f, "{}", ident.0),
+            Expr::ReferenceHandler(ident) => write!(f, "{}", ident.0),
+            Expr::ReferenceData(TypeIdent::ForallIdent(ident)) => write!(f, "{}", ident.0),
+            Expr::ReferenceData(TypeIdent::ExistsIdent(ident)) => write!(f, "{}", ident.0),
+            Expr::Pattern(_) => write!(f, "<pattern>"),
+            Expr::Handle(_) => write!(f, "<handle>"),
         }
     }
 }
@@ -526,7 +928,7 @@ impl From<ast::TraitIdent> for TraitIdent {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct HandlerIdent(pub String);
 
 impl From<ast::TypeIdent> for TypeIdent {
@@ -611,6 +1013,9 @@ impl From<ast::TypeAbstructionExpr> for TypeAbstruction {
 
                 Self::Arrow(Box::new(lhs), Box::new(rhs))
             }
+            ast::TypeAbstructionExpr::Literal(ast::TypeLiteral::Abstruction(expr)) => {
+                TypeAbstruction::from(*expr)
+            }
             ast::TypeAbstructionExpr::Literal(literal) => TypeAbstruction::Term(literal.into()),
         }
     }
@@ -647,6 +1052,9 @@ impl From<ast::TypeLiteral> for TypeValue {
                     .collect(),
             ),
             ast::TypeLiteral::Coroutine(ident) => TypeValue::Coroutine(Box::new((*ident).into())),
+            ast::TypeLiteral::Abstruction(_) => {
+                unreachable!("flattened by From<ast::TypeAbstructionExpr> for TypeAbstruction")
+            }
         }
     }
 }
@@ -661,6 +1069,7 @@ pub enum DataDef {
 pub struct StructualDataDef {
     pub args: Vec<TypeIdent>,
     pub expr: DataExpr,
+    pub span: Span,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -668,6 +1077,53 @@ pub struct NominalDataDef {
     pub ident: TypeIdent,
     pub args: Vec<TypeIdent>,
     pub expr: DataExpr,
+    pub span: Span,
+}
+
+impl DataDef {
+    pub fn args(&self) -> &[TypeIdent] {
+        match self {
+            DataDef::Structual(def) => &def.args,
+            DataDef::Nominal(def) => &def.args,
+        }
+    }
+
+    pub fn expr(&self) -> &DataExpr {
+        match self {
+            DataDef::Structual(def) => &def.expr,
+            DataDef::Nominal(def) => &def.expr,
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            DataDef::Structual(def) => def.span,
+            DataDef::Nominal(def) => def.span,
+        }
+    }
+}
+
+impl TryFrom<ast::DataAssign> for DataDef {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ast::DataAssign) -> Result<Self, Self::Error> {
+        let args = value.args.iter().cloned().map(TypeIdent::from).collect();
+        let span = value.span;
+
+        match value.modifier {
+            Some(ast::DataModifier::Nominal) => Ok(DataDef::Nominal(NominalDataDef {
+                ident: value.ident.clone().into(),
+                args,
+                expr: value.expr.try_into()?,
+                span,
+            })),
+            _ => Ok(DataDef::Structual(StructualDataDef {
+                args,
+                expr: value.expr.try_into()?,
+                span,
+            })),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -701,6 +1157,23 @@ pub enum DataExpr {
     Term(DataTerm),
 }
 
+impl DataExpr {
+    /// The constructors of a union, `Some a` and `Nil` of `Some a | Nil`.
+    pub fn constructors(&self) -> Vec<&DataConstructor> {
+        match self {
+            DataExpr::Or(lhs, rhs) => lhs
+                .constructors()
+                .into_iter()
+                .chain(rhs.constructors())
+                .collect(),
+            DataExpr::Term(DataTerm::Constructor(
+                constructor @ DataConstructor(_, TypeIdent::ExistsIdent(_), _),
+            )) => vec![constructor],
+            DataExpr::Term(_) => Vec::new(),
+        }
+    }
+}
+
 impl TryFrom<ast::DataExpr> for DataExpr {
     type Error = anyhow::Error;
 
@@ -734,6 +1207,7 @@ pub enum DataTerm {
     Parameter(ForallIdent),
     Ident(ExistsIdent),
     Tuple(usize, Vec<DataTerm>),
+    Array(Box<TypeAbstruction>),
     Abstruction(Box<TypeAbstruction>),
     Constructor(DataConstructor),
     Coroutine(Box<CoroutineType>),
@@ -753,9 +1227,7 @@ impl From<ast::TypeLiteral> for DataTerm {
         match value {
             ast::TypeLiteral::Top => DataTerm::Top,
             ast::TypeLiteral::Bottom => DataTerm::Bottom,
-            ast::TypeLiteral::Array(_) => {
-                todo!()
-            }
+            ast::TypeLiteral::Array(expr) => DataTerm::Array(Box::new((*expr).into())),
             ast::TypeLiteral::Constructor(constructor) => DataTerm::Constructor(constructor.into()),
             ast::TypeLiteral::Tuple(size, elems) => DataTerm::Tuple(
                 size,
@@ -764,6 +1236,7 @@ impl From<ast::TypeLiteral> for DataTerm {
             ast::TypeLiteral::Coroutine(coroutine) => {
                 DataTerm::Coroutine(Box::new((*coroutine).into()))
             }
+            ast::TypeLiteral::Abstruction(expr) => DataTerm::Abstruction(Box::new((*expr).into())),
         }
     }
 }
@@ -775,7 +1248,7 @@ impl From<ast::TypeAbstructionExpr> for DataTerm {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-pub struct DataConstructor(DataModifier, TypeIdent, Vec<DataTerm>);
+pub struct DataConstructor(pub DataModifier, pub TypeIdent, pub Vec<DataTerm>);
 
 impl From<ast::DataConstructor> for DataConstructor {
     fn from(value: ast::DataConstructor) -> Self {
@@ -786,3 +1259,25 @@ impl From<ast::DataConstructor> for DataConstructor {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Expr, Literal};
+
+    #[test]
+    fn literal_display_test() {
+        let int = |value| Expr::Literal(Literal::Int(value));
+        let literal = Literal::Tuple(
+            3,
+            vec![
+                int(1),
+                Expr::Literal(Literal::List(vec![int(2), int(3)])),
+                Expr::Reference("x".into()),
+            ],
+        );
+
+        assert_eq!(literal.to_string(), "(1, [2 3], x)");
+        assert_eq!(Literal::Tuple(0, Vec::new()).to_string(), "()");
+        assert_eq!(Literal::List(Vec::new()).to_string(), "[]");
+    }
+}
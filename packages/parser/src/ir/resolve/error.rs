@@ -0,0 +1,43 @@
+use super::Namespace;
+use crate::ir::Span;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ResolveError {
+    #[error("{span}: in {name}: undefined {namespace} {ident}{}", suggest(.suggestion))]
+    Undefined {
+        span: Span,
+        name: String,
+        namespace: Namespace,
+        ident: String,
+        suggestion: Option<String>,
+    },
+    #[error("{span}: {ident} already has a signature")]
+    DuplicateSignature { span: Span, ident: String },
+    #[error("{span}: {ident} has a signature but no equations")]
+    OrphanSignature { span: Span, ident: String },
+    #[error("{span}: {namespace} {ident} is defined twice")]
+    Duplicate {
+        span: Span,
+        namespace: Namespace,
+        ident: String,
+    },
+}
+
+fn suggest(suggestion: &Option<String>) -> String {
+    match suggestion {
+        Some(suggestion) => format!(", did you mean {suggestion}?"),
+        None => String::new(),
+    }
+}
+
+impl ResolveError {
+    pub fn span(&self) -> Span {
+        match self {
+            ResolveError::Undefined { span, .. }
+            | ResolveError::DuplicateSignature { span, .. }
+            | ResolveError::OrphanSignature { span, .. }
+            | ResolveError::Duplicate { span, .. } => *span,
+        }
+    }
+}
@@ -0,0 +1,637 @@
+mod error;
+
+pub use self::error::ResolveError;
+
+use super::{
+    ir1::IR1,
+    type_check::{builtins, intrinsics},
+    Span, TypeIdent,
+};
+use crate::ast::{
+    self, Apply, ApplyEff, ApplyInst, AssignArgs, CoroutineType, DataConstructor, DataValue,
+    EtaEnvs, Expr, Handle, Lambda, Literal, Module, PatternExpr, Statement, TraitConstraint,
+    TypeAbstructionExpr, TypeExpr, TypeLiteral,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+/// The namespaces a name is resolved in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Namespace {
+    Value,
+    Handler,
+    Inst,
+    Constructor,
+    Type,
+    Trait,
+}
+
+impl Display for Namespace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let namespace = match self {
+            Namespace::Value => "reference",
+            Namespace::Handler => "handler",
+            Namespace::Inst => "intrinsic",
+            Namespace::Constructor => "constructor",
+            Namespace::Type => "type",
+            Namespace::Trait => "trait",
+        };
+
+        write!(f, "{namespace}")
+    }
+}
+
+/// Where a name is defined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Definition {
+    /// A statement at the top level or in a where-clause.
+    Statement(Span),
+    /// An argument of an equation, a lambda or a handler clause in the
+    /// statement, or `resume`.
+    Param(Span),
+    /// A statement of an imported module.
+    Imported(Span),
+    /// Provided by the interpreter.
+    Builtin,
+}
+
+/// A name referred to in the statement at `span` and its definition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Binding {
+    pub span: Span,
+    pub namespace: Namespace,
+    pub ident: String,
+    pub definition: Definition,
+}
+
+/// The names in scope, `Text.words` for a name of a module imported as `Text`.
+pub type Scope = HashMap<(Namespace, String), Definition>;
+
+/// The types the type checker provides.
+const BUILTIN_TYPES: [&str; 7] = ["Int", "Integer", "Float", "Rational", "Char", "Text", "IO"];
+
+/// Binds the references in `module` to the definitions `ir1` collects and the
+/// arguments and where-clauses around them. Reports undefined names,
+/// signatures given twice or without equations, and data types, traits and
+/// intrinsics defined twice. Errors are in source order.
+pub fn resolve(ir1: &IR1, module: &Module) -> (Vec<Binding>, Vec<ResolveError>) {
+    resolve_with(ir1, module, Scope::new())
+}
+
+/// Resolves `module` with the names it imports in scope as well.
+pub fn resolve_with(
+    ir1: &IR1,
+    module: &Module,
+    imports: Scope,
+) -> (Vec<Binding>, Vec<ResolveError>) {
+    let mut scope = imports;
+    scope.extend(top_level(ir1));
+    let mut resolver = Resolver::default();
+    resolver.signatures(module);
+    resolver.duplicates(module);
+    module
+        .iter()
+        .for_each(|statement| resolver.statement(statement, &scope));
+
+    let Resolver {
+        bindings,
+        mut errors,
+    } = resolver;
+    errors.sort_by_key(|error| error.span().start);
+    let mut seen = HashSet::new();
+    errors.retain(|error| seen.insert(error.to_string()));
+
+    (bindings, errors)
+}
+
+fn top_level(ir1: &IR1) -> Scope {
+    BUILTIN_TYPES
+        .iter()
+        .map(|ident| (Namespace::Type, ident.to_string()))
+        .chain(
+            intrinsics()
+                .into_iter()
+                .map(|(ident, _)| (Namespace::Inst, ident.0)),
+        )
+        .chain(
+            builtins()
+                .into_iter()
+                .map(|(ident, _)| (Namespace::Value, ident.0)),
+        )
+        .map(|key| (key, Definition::Builtin))
+        .chain(
+            definitions(ir1)
+                .into_iter()
+                .map(|(namespace, ident, span)| ((namespace, ident), Definition::Statement(span))),
+        )
+        .collect()
+}
+
+/// The names `ir1` defines at the top level, where each is defined, in source
+/// order.
+pub fn definitions(ir1: &IR1) -> Vec<(Namespace, String, Span)> {
+    let defined =
+        |namespace: Namespace| move |(ident, span): (String, Span)| (namespace, ident, span);
+    let type_name = |(ident, span): (&TypeIdent, &Span)| match ident {
+        TypeIdent::ExistsIdent(ident) => Some((ident.0.clone(), *span)),
+        TypeIdent::ForallIdent(_) => None,
+    };
+
+    let mut definitions = ir1
+        .ident_symbols
+        .iter()
+        .map(|(ident, span)| (ident.0.clone(), *span))
+        .map(defined(Namespace::Value))
+        .chain(
+            ir1.handler_symbols
+                .iter()
+                .map(|(ident, span)| (ident.0.clone(), *span))
+                .map(defined(Namespace::Handler)),
+        )
+        .chain(
+            ir1.inst_symbols
+                .iter()
+                .map(|(ident, span)| (ident.0.clone(), *span))
+                .map(defined(Namespace::Inst)),
+        )
+        .chain(
+            ir1.constructor_symbols
+                .iter()
+                .map(|(ident, span)| (ident.0.clone(), *span))
+                .map(defined(Namespace::Constructor)),
+        )
+        .chain(
+            ir1.data_symbols
+                .iter()
+                .filter_map(type_name)
+                .map(defined(Namespace::Type)),
+        )
+        .chain(
+            ir1.trait_symbols
+                .iter()
+                .filter_map(type_name)
+                .map(defined(Namespace::Trait)),
+        )
+        .collect::<Vec<_>>();
+    definitions.sort_by(
+        |(lhs_namespace, lhs, lhs_span), (rhs_namespace, rhs, rhs_span)| {
+            (lhs_span.start, lhs_namespace, lhs).cmp(&(rhs_span.start, rhs_namespace, rhs))
+        },
+    );
+
+    definitions
+}
+
+/// The statement a reference is in.
+struct Site {
+    span: Span,
+    name: String,
+}
+
+#[derive(Default)]
+struct Resolver {
+    bindings: Vec<Binding>,
+    errors: Vec<ResolveError>,
+}
+
+impl Resolver {
+    fn statement(&mut self, statement: &Statement, scope: &Scope) {
+        match statement {
+            Statement::Assign(assign) => {
+                let site = Site::new(assign.span, &assign.ident.0);
+                self.equation(
+                    &site,
+                    &assign.args,
+                    &assign.expr,
+                    &assign.where_clause,
+                    scope,
+                )
+            }
+            Statement::HandlerAssign(assign) => {
+                let site = Site::new(assign.span, &assign.ident.0);
+                let mut scope = scope.clone();
+                scope.insert(
+                    (Namespace::Value, "resume".to_string()),
+                    Definition::Param(assign.span),
+                );
+
+                self.equation(
+                    &site,
+                    &assign.args,
+                    &assign.expr,
+                    &assign.where_clause,
+                    &scope,
+                )
+            }
+            Statement::AssignDef(def) => {
+                self.type_expr(&Site::new(def.span, &def.ident.0), &def.expr, scope)
+            }
+            Statement::HandlerDef(def) => {
+                let site = Site::new(def.span, &def.ident.0);
+                self.constraints(&site, &def.expr.trait_constraints, scope);
+                self.eta_envs(&site, &def.expr.eta_envs, scope);
+                self.type_abstruction(&site, &def.expr.expr, scope);
+            }
+            Statement::InstDef(def) => {
+                self.type_expr(&Site::new(def.span, &def.ident.0), &def.expr, scope)
+            }
+            Statement::DataAssign(def) => {
+                let site = Site::new(def.span, &type_name(&def.ident));
+                self.constraints(&site, &def.constraints, scope);
+                def.expr.iter().for_each(|value| match value {
+                    DataValue::Constructor(constructor) => constructor
+                        .args
+                        .iter()
+                        .for_each(|arg| self.type_literal(&site, arg, scope)),
+                    DataValue::Unit => {}
+                });
+            }
+            Statement::TraitDef(def) => {
+                let site = Site::new(def.span, &type_name(&def.constructor.ident));
+                self.constraints(&site, &def.trait_constraints, scope);
+                def.where_clause.iter().for_each(|statement| {
+                    if let Statement::AssignDef(def) = statement {
+                        self.type_expr(&site, &def.expr, scope);
+                    }
+                });
+            }
+            Statement::ImplTrait(def) => {
+                let trait_name = type_name(&def.ident);
+                let site = Site::new(def.span, &format!("impl {trait_name}"));
+                self.constraints(&site, &def.constraints, scope);
+                self.refer(&site, Namespace::Trait, &trait_name, scope);
+                def.args.iter().for_each(|arg| {
+                    if let ast::TypeIdent::ExistsIdent(ident) = arg {
+                        self.refer(&site, Namespace::Type, &ident.0, scope);
+                    }
+                });
+                def.where_clause
+                    .iter()
+                    .for_each(|statement| self.statement(statement, scope));
+            }
+            // the loader resolves what modules import and export
+            Statement::FixityDef(_)
+            | Statement::Import(_)
+            | Statement::Export(_)
+            | Statement::LineComment(_) => {}
+        }
+    }
+
+    /// The arguments of an equation are in scope of its where-clause and its
+    /// body, and the functions of the where-clause in scope of each other and
+    /// the body.
+    fn equation(
+        &mut self,
+        site: &Site,
+        args: &AssignArgs,
+        expr: &Expr,
+        where_clause: &Module,
+        scope: &Scope,
+    ) {
+        let mut scope = scope.clone();
+        args.iter()
+            .for_each(|pattern| self.pattern(site, pattern, &mut scope));
+        where_clause.iter().for_each(|statement| {
+            if let Statement::Assign(assign) = statement {
+                scope.insert(
+                    (Namespace::Value, assign.ident.0.clone()),
+                    Definition::Statement(assign.span),
+                );
+            }
+        });
+
+        self.signatures(where_clause);
+        where_clause
+            .iter()
+            .for_each(|statement| self.statement(statement, &scope));
+        self.expr(site, expr, &scope);
+    }
+
+    fn expr(&mut self, site: &Site, expr: &Expr, scope: &Scope) {
+        match expr {
+            Expr::Apply(Apply {
+                abstruction, expr, ..
+            }) => {
+                if let Some(arg) = &abstruction.arg {
+                    self.expr(site, arg, scope);
+                }
+                self.expr(site, &abstruction.expr, scope);
+                self.expr(site, expr, scope);
+            }
+            Expr::ApplyInst(ApplyInst { ident, expr, .. }) => {
+                self.refer(site, Namespace::Inst, &ident.0, scope);
+                self.expr(site, expr, scope);
+            }
+            Expr::ApplyEff(ApplyEff { ident, expr, .. }) => {
+                self.refer(site, Namespace::Handler, &ident.0, scope);
+                self.expr(site, expr, scope);
+            }
+            Expr::InstIdent(ident) => self.refer(site, Namespace::Inst, &ident.0, scope),
+            Expr::Ident(ident) => self.refer(site, Namespace::Value, &ident.0, scope),
+            Expr::HandlerIdent(ident) => self.refer(site, Namespace::Handler, &ident.0, scope),
+            Expr::TypeIdent(ast::TypeIdent::ExistsIdent(ident)) => {
+                self.refer(site, Namespace::Constructor, &ident.0, scope)
+            }
+            Expr::TypeIdent(ast::TypeIdent::ForallIdent(_)) => {}
+            Expr::Literal(Literal::Tuple(_, items) | Literal::Array(items)) => {
+                items.iter().for_each(|item| self.expr(site, item, scope))
+            }
+            Expr::Literal(_) => {}
+            Expr::Abstruction(Lambda { params, expr }) => {
+                let mut scope = scope.clone();
+                params
+                    .iter()
+                    .for_each(|param| self.pattern(site, param, &mut scope));
+
+                self.expr(site, expr, &scope)
+            }
+            Expr::Handle(Handle { expr, handlers }) => {
+                self.expr(site, expr, scope);
+
+                let mut scope = scope.clone();
+                scope.insert(
+                    (Namespace::Value, "resume".to_string()),
+                    Definition::Param(site.span),
+                );
+                handlers.iter().for_each(|handler| {
+                    self.refer(site, Namespace::Handler, &handler.ident.0, &scope);
+                    self.equation(
+                        site,
+                        &handler.args,
+                        &handler.expr,
+                        &handler.where_clause,
+                        &scope,
+                    );
+                });
+            }
+            Expr::Group(expr) => self.expr(site, expr, scope),
+        }
+    }
+
+    /// Binds the variables of `pattern` in `scope`.
+    fn pattern(&mut self, site: &Site, pattern: &PatternExpr, scope: &mut Scope) {
+        match pattern {
+            PatternExpr::Or(lhs, rhs) | PatternExpr::ListHead(lhs, rhs) => {
+                self.pattern(site, lhs, scope);
+                self.pattern(site, rhs, scope);
+            }
+            PatternExpr::Literal(literal) => self.literal_pattern(site, literal, scope),
+            PatternExpr::Bind(ident) => bind(site, &ident.0, scope),
+            PatternExpr::Constructor(constructor) => {
+                self.constructor_pattern(site, constructor, scope)
+            }
+            PatternExpr::Tuple(_, items) => items
+                .iter()
+                .for_each(|item| self.pattern(site, item, scope)),
+            PatternExpr::Any => {}
+        }
+    }
+
+    /// The items of tuple and list patterns are parsed as expressions, an
+    /// ident binds.
+    fn literal_pattern(&mut self, site: &Site, literal: &Literal, scope: &mut Scope) {
+        let items = match literal {
+            Literal::Tuple(_, items) | Literal::Array(items) => items,
+            _ => return,
+        };
+
+        items.iter().for_each(|item| match item {
+            Expr::Ident(ident) => bind(site, &ident.0, scope),
+            Expr::Literal(literal) => self.literal_pattern(site, literal, scope),
+            item => self.expr(site, item, scope),
+        });
+    }
+
+    /// Constructor patterns are parsed as type expressions, a lower-case ident
+    /// binds.
+    fn constructor_pattern(
+        &mut self,
+        site: &Site,
+        constructor: &DataConstructor,
+        scope: &mut Scope,
+    ) {
+        match &constructor.ident {
+            ast::TypeIdent::ForallIdent(ident) if constructor.args.is_empty() => {
+                return bind(site, &ident.0, scope)
+            }
+            ast::TypeIdent::ForallIdent(_) => {}
+            ast::TypeIdent::ExistsIdent(ident) => {
+                self.refer(site, Namespace::Constructor, &ident.0, scope)
+            }
+        }
+
+        constructor.args.iter().for_each(|arg| {
+            if let TypeLiteral::Constructor(constructor) = arg {
+                self.constructor_pattern(site, constructor, scope);
+            }
+        });
+    }
+
+    fn type_expr(&mut self, site: &Site, expr: &TypeExpr, scope: &Scope) {
+        self.constraints(site, &expr.trait_constraints, scope);
+        self.eta_envs(site, &expr.eta_envs, scope);
+        self.type_abstruction(site, &expr.expr, scope);
+    }
+
+    fn constraints(&mut self, site: &Site, constraints: &[TraitConstraint], scope: &Scope) {
+        constraints
+            .iter()
+            .for_each(|constraint| self.refer(site, Namespace::Trait, &constraint.ident.0, scope));
+    }
+
+    fn eta_envs(&mut self, site: &Site, eta_envs: &EtaEnvs, scope: &Scope) {
+        eta_envs.iter().for_each(|eta_env| {
+            self.refer(site, Namespace::Handler, &eta_env.ident.0, scope);
+            self.coroutine(site, &eta_env.expr, scope);
+        });
+    }
+
+    fn coroutine(&mut self, site: &Site, coroutine: &CoroutineType, scope: &Scope) {
+        self.type_abstruction(site, &coroutine.resume, scope);
+        self.type_abstruction(site, &coroutine.ret, scope);
+    }
+
+    fn type_abstruction(&mut self, site: &Site, expr: &TypeAbstructionExpr, scope: &Scope) {
+        match expr {
+            TypeAbstructionExpr::Arrow(lhs, rhs) => {
+                self.type_abstruction(site, lhs, scope);
+                self.type_abstruction(site, rhs, scope);
+            }
+            TypeAbstructionExpr::Literal(literal) => self.type_literal(site, literal, scope),
+        }
+    }
+
+    fn type_literal(&mut self, site: &Site, literal: &TypeLiteral, scope: &Scope) {
+        match literal {
+            TypeLiteral::Array(expr) | TypeLiteral::Abstruction(expr) => {
+                self.type_abstruction(site, expr, scope)
+            }
+            TypeLiteral::Constructor(constructor) => {
+                if let ast::TypeIdent::ExistsIdent(ident) = &constructor.ident {
+                    self.refer(site, Namespace::Type, &ident.0, scope);
+                }
+                constructor
+                    .args
+                    .iter()
+                    .for_each(|arg| self.type_literal(site, arg, scope));
+            }
+            TypeLiteral::Tuple(_, items) => items
+                .iter()
+                .for_each(|item| self.type_abstruction(site, item, scope)),
+            TypeLiteral::Coroutine(coroutine) => self.coroutine(site, coroutine, scope),
+            TypeLiteral::Top | TypeLiteral::Bottom => {}
+        }
+    }
+
+    /// A handler the program does not define is the intrinsic of the same
+    /// name, `*write` is `@write`.
+    fn refer(&mut self, site: &Site, namespace: Namespace, ident: &str, scope: &Scope) {
+        let definition = scope
+            .get(&(namespace, ident.to_string()))
+            .or_else(|| match namespace {
+                Namespace::Handler => scope.get(&(Namespace::Inst, ident.replacen('*', "@", 1))),
+                _ => None,
+            });
+
+        match definition {
+            Some(definition) => self.bindings.push(Binding {
+                span: site.span,
+                namespace,
+                ident: ident.to_string(),
+                definition: *definition,
+            }),
+            None => self.errors.push(ResolveError::Undefined {
+                span: site.span,
+                name: site.name.clone(),
+                namespace,
+                ident: ident.to_string(),
+                suggestion: suggest(
+                    ident,
+                    scope
+                        .keys()
+                        .filter(|(other, _)| *other == namespace)
+                        .map(|(_, name)| name.as_str()),
+                ),
+            }),
+        }
+    }
+
+    /// A function has at most one signature, and equations in the same
+    /// module. A handler may be declared without equations, an intrinsic then
+    /// handles it.
+    fn signatures(&mut self, module: &Module) {
+        let equations = module
+            .iter()
+            .filter_map(|statement| match statement {
+                Statement::Assign(assign) => Some(assign.ident.0.as_str()),
+                _ => None,
+            })
+            .collect::<HashSet<_>>();
+        let mut signed = HashSet::new();
+
+        module.iter().for_each(|statement| {
+            let (namespace, ident, span) = match statement {
+                Statement::AssignDef(def) => (Namespace::Value, &def.ident.0, def.span),
+                Statement::HandlerDef(def) => (Namespace::Handler, &def.ident.0, def.span),
+                _ => return,
+            };
+
+            if !signed.insert((namespace, ident)) {
+                self.errors.push(ResolveError::DuplicateSignature {
+                    span,
+                    ident: ident.clone(),
+                });
+            } else if namespace == Namespace::Value && !equations.contains(ident.as_str()) {
+                self.errors.push(ResolveError::OrphanSignature {
+                    span,
+                    ident: ident.clone(),
+                });
+            }
+        });
+    }
+
+    fn duplicates(&mut self, module: &Module) {
+        let mut defined = HashSet::new();
+
+        module
+            .iter()
+            .filter_map(|statement| match statement {
+                Statement::DataAssign(def) => {
+                    Some((Namespace::Type, type_name(&def.ident), def.span))
+                }
+                Statement::TraitDef(def) => Some((
+                    Namespace::Trait,
+                    type_name(&def.constructor.ident),
+                    def.span,
+                )),
+                Statement::InstDef(def) => Some((Namespace::Inst, def.ident.0.clone(), def.span)),
+                _ => None,
+            })
+            .for_each(|(namespace, ident, span)| {
+                if !defined.insert((namespace, ident.clone())) {
+                    self.errors.push(ResolveError::Duplicate {
+                        span,
+                        namespace,
+                        ident,
+                    });
+                }
+            });
+    }
+}
+
+impl Site {
+    fn new(span: Span, name: &str) -> Self {
+        Self {
+            span,
+            name: name.to_string(),
+        }
+    }
+}
+
+fn bind(site: &Site, ident: &str, scope: &mut Scope) {
+    scope.insert(
+        (Namespace::Value, ident.to_string()),
+        Definition::Param(site.span),
+    );
+}
+
+fn type_name(ident: &ast::TypeIdent) -> String {
+    match ident {
+        ast::TypeIdent::ForallIdent(ident) => ident.0.clone(),
+        ast::TypeIdent::ExistsIdent(ident) => ident.0.clone(),
+    }
+}
+
+/// The closest name at most a third of the length of `ident` edits away, and
+/// at least one, that keeps some of `ident`.
+fn suggest<'a>(ident: &str, candidates: impl Iterator<Item = &'a str>) -> Option<String> {
+    let limit = (ident.chars().count() / 3).max(1);
+
+    candidates
+        .filter(|candidate| *candidate != ident)
+        .map(|candidate| (distance(ident, candidate), candidate))
+        .filter(|(distance, _)| *distance <= limit && *distance < ident.chars().count())
+        .min()
+        .map(|(_, candidate)| candidate.to_string())
+}
+
+/// The Levenshtein distance.
+fn distance(lhs: &str, rhs: &str) -> usize {
+    let rhs = rhs.chars().collect::<Vec<_>>();
+    let first = (0..=rhs.len()).collect::<Vec<_>>();
+
+    lhs.chars()
+        .enumerate()
+        .fold(first, |prev, (i, l)| {
+            rhs.iter().enumerate().fold(vec![i + 1], |mut row, (j, r)| {
+                let substitute = prev[j] + usize::from(l != *r);
+                row.push(substitute.min(prev[j + 1] + 1).min(row[j] + 1));
+                row
+            })
+        })
+        .last()
+        .copied()
+        .unwrap_or_default()
+}
@@ -0,0 +1,188 @@
+use super::{error::TypeError, traits::Owner, Definition};
+use crate::ir::{
+    graph::Graph, ir2::IR2, Abstruction, Apply, ApplyEff, ApplyInst, DataConstructor, DataDef,
+    DataExpr, DataModifier, DataTerm, ExistsIdent, Expr, Handle, HandlerIdent, Literal, TypeIdent,
+};
+use std::collections::{HashMap, HashSet};
+
+/// Groups mutually recursive definitions, each group after the ones it refers
+/// to. References to definitions outside of `defs` are not followed.
+pub(super) fn groups(defs: Vec<Definition>) -> Vec<Vec<Definition>> {
+    let graph = defs.iter().fold(Graph::default(), |mut graph, def| {
+        graph.node(def.owner.clone());
+        def.equations
+            .iter()
+            .flat_map(|(abstruction, where_clause, _)| refers(abstruction, where_clause))
+            .for_each(|owner| graph.edge(def.owner.clone(), owner));
+
+        graph
+    });
+    let mut defs = defs
+        .into_iter()
+        .map(|def| (def.owner.clone(), def))
+        .collect::<HashMap<_, _>>();
+
+    graph
+        .components()
+        .into_iter()
+        .map(|component| {
+            component
+                .iter()
+                .filter_map(|owner| defs.remove(owner))
+                .collect()
+        })
+        .collect()
+}
+
+/// The top-level definitions an equation refers to, the functions of its
+/// where-clause shadow them.
+fn refers(abstruction: &Abstruction, where_clause: &IR2) -> Vec<Owner> {
+    let functions = where_clause.functions();
+    let locals = functions
+        .iter()
+        .map(|(ident, _)| *ident)
+        .collect::<HashSet<_>>();
+    let mut handlers = Vec::new();
+    performs(&abstruction.expr, &mut handlers);
+
+    abstruction
+        .captures()
+        .into_iter()
+        .map(Owner::Function)
+        .chain(handlers.into_iter().map(Owner::Handler))
+        .chain(
+            functions
+                .iter()
+                .flat_map(|(_, functions)| functions.iter())
+                .flat_map(|f| refers(&f.expr, &f.where_clause)),
+        )
+        .filter(|owner| !matches!(owner, Owner::Function(ident) if locals.contains(ident)))
+        .collect()
+}
+
+/// The handlers an expression performs or handles.
+fn performs(expr: &Expr, handlers: &mut Vec<HandlerIdent>) {
+    match expr {
+        Expr::Literal(Literal::Tuple(_, items) | Literal::List(items)) => {
+            items.iter().for_each(|item| performs(item, handlers))
+        }
+        Expr::Abstruction(abstruction) => performs(&abstruction.expr, handlers),
+        Expr::Apply(Apply {
+            abstruction, expr, ..
+        }) => {
+            performs(&abstruction.expr, handlers);
+            performs(expr, handlers);
+        }
+        Expr::ApplyEmbedded(ApplyInst { expr, .. }) => performs(expr, handlers),
+        Expr::ApplyEff(ApplyEff { ident, expr, .. }) => {
+            handlers.push(ident.clone());
+            performs(expr, handlers);
+        }
+        Expr::ReferenceHandler(ident) => handlers.push(ident.clone()),
+        Expr::Handle(Handle {
+            expr,
+            handlers: clauses,
+        }) => {
+            performs(expr, handlers);
+            clauses.iter().for_each(|clause| {
+                handlers.push(clause.ident.clone());
+                performs(&clause.expr.expr, handlers);
+            });
+        }
+        Expr::Literal(_)
+        | Expr::Reference(_)
+        | Expr::ReferenceInst(_)
+        | Expr::ReferenceData(_)
+        | Expr::Pattern(_) => {}
+    }
+}
+
+/// A data type may be a union of other data types and a trait a subclass of
+/// other traits, as long as neither ends up in terms of itself. A nominal type
+/// or a constructor ends the chain, `data Nat = nominal Succ Nat | nominal
+/// Zero` is fine.
+pub(super) fn cycles(ir: &IR2) -> Vec<TypeError> {
+    data_cycles(ir)
+        .into_iter()
+        .chain(superclass_cycles(ir))
+        .collect()
+}
+
+fn data_cycles(ir: &IR2) -> Vec<TypeError> {
+    let mut structual = ir
+        .data_symbols
+        .iter()
+        .filter_map(|(ident, def)| match (ident, def) {
+            (TypeIdent::ExistsIdent(ident), DataDef::Structual(def)) => Some((ident, def)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    structual.sort_by_key(|(_, def)| def.span.start);
+
+    let graph = structual
+        .iter()
+        .fold(Graph::default(), |mut graph, (ident, def)| {
+            graph.node(*ident);
+            members(&def.expr)
+                .into_iter()
+                .for_each(|member| graph.edge(*ident, member));
+
+            graph
+        });
+    let spans = structual
+        .iter()
+        .map(|(ident, def)| (*ident, def.span))
+        .collect::<HashMap<_, _>>();
+
+    graph
+        .cycles()
+        .into_iter()
+        .map(|cycle| TypeError::CyclicData {
+            span: spans[cycle[0]],
+            cycle: cycle.iter().map(|ident| ident.0.clone()).collect(),
+        })
+        .collect()
+}
+
+/// The data types a union is made of, `True` and `False` of `True | False`.
+fn members(expr: &DataExpr) -> Vec<&ExistsIdent> {
+    match expr {
+        DataExpr::Or(lhs, rhs) => members(lhs).into_iter().chain(members(rhs)).collect(),
+        DataExpr::Term(DataTerm::Constructor(DataConstructor(
+            DataModifier::Structual,
+            TypeIdent::ExistsIdent(ident),
+            _,
+        )))
+        | DataExpr::Term(DataTerm::Ident(ident)) => vec![ident],
+        DataExpr::Term(_) => Vec::new(),
+    }
+}
+
+fn superclass_cycles(ir: &IR2) -> Vec<TypeError> {
+    let mut traits = ir.trait_symbols.iter().collect::<Vec<_>>();
+    traits.sort_by_key(|(_, def)| def.span.start);
+
+    let graph = traits
+        .iter()
+        .fold(Graph::default(), |mut graph, (ident, def)| {
+            graph.node(*ident);
+            def.superclasses
+                .iter()
+                .for_each(|superclass| graph.edge(*ident, &superclass.ident));
+
+            graph
+        });
+    let spans = traits
+        .iter()
+        .map(|(ident, def)| (*ident, def.span))
+        .collect::<HashMap<_, _>>();
+
+    graph
+        .cycles()
+        .into_iter()
+        .map(|cycle| TypeError::CyclicSuperclass {
+            span: spans[cycle[0]],
+            cycle: cycle.iter().map(|ident| ident.0.clone()).collect(),
+        })
+        .collect()
+}
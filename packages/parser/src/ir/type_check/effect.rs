@@ -0,0 +1,341 @@
+use super::{error::TypeError, infer::Infer, intrinsics, types::Type, TypeEnv};
+use crate::ir::{
+    ir2::IR2, Abstruction, Apply, ApplyEff, ApplyInst, Expr, Handle, HandlerIdent, Ident,
+    InstIdent, Literal, Span, TypeAbstructionEnv,
+};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// Handlers a definition may perform, `<write: <a, b>>` in a signature. A
+/// signature without an eta-environment performs nothing.
+pub type Row = BTreeSet<HandlerIdent>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(super) enum Node {
+    Function(Ident),
+    Handler(HandlerIdent),
+}
+
+/// The handlers an equation performs itself and the definitions it refers to,
+/// each with the handlers a `handle` around the reference handles.
+#[derive(Debug, Default)]
+struct Uses {
+    handlers: Row,
+    nodes: Vec<(Node, Row)>,
+}
+
+struct Definition<'a> {
+    node: Node,
+    sig: Option<&'a TypeAbstructionEnv>,
+    equations: Vec<(Uses, Span)>,
+}
+
+impl TypeEnv {
+    /// Infers the rows of the definitions without a signature and checks the
+    /// others against their eta-environment. Every effect `main` may perform
+    /// must be handled by a handler or an intrinsic.
+    pub(super) fn check_effects(&mut self, ir: &IR2) -> Vec<TypeError> {
+        let defs = definitions(ir);
+        self.effects = rows(&defs);
+
+        let mut errors = defs
+            .iter()
+            .filter_map(|def| def.sig.map(|sig| (def, sig)))
+            .flat_map(|(def, sig)| {
+                let declared = &self.effects[&def.node];
+                let undeclared = def.equations.iter().flat_map(|(uses, span)| {
+                    self.equation_row(uses)
+                        .into_iter()
+                        .filter(|ident| !declared.contains(ident))
+                        .map(|ident| TypeError::UndeclaredEffect {
+                            span: *span,
+                            name: def.node.to_string(),
+                            ident,
+                        })
+                });
+
+                undeclared
+                    .chain(self.check_eta_env(&def.node, sig))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        errors.extend(self.check_main(ir, &defs));
+
+        errors
+    }
+
+    fn equation_row(&self, uses: &Uses) -> Row {
+        uses.nodes
+            .iter()
+            .filter_map(|(node, handled)| self.effects.get(node).map(|row| (row, handled)))
+            .fold(uses.handlers.clone(), |mut acc, (row, handled)| {
+                acc.extend(row.difference(handled).cloned());
+                acc
+            })
+    }
+
+    /// The types in the eta-environment are those of the handlers.
+    fn check_eta_env(&self, node: &Node, sig: &TypeAbstructionEnv) -> Vec<TypeError> {
+        sig.handler_constraints
+            .iter()
+            .filter_map(|constraint| {
+                let handler = match self.handlers.get(&constraint.ident) {
+                    Some(scheme) => scheme,
+                    None => self.insts.get(&fallback(&constraint.ident))?,
+                };
+                let mut infer = Infer::default();
+                let (found, _) = infer.instantiate(handler);
+                let expected = Type::from(&constraint.expr.resume);
+
+                infer
+                    .unify(&expected, &found)
+                    .is_err()
+                    .then(|| TypeError::Mismatch {
+                        span: sig.span,
+                        name: node.to_string(),
+                        expected,
+                        found: handler.ty.clone(),
+                    })
+            })
+            .collect()
+    }
+
+    fn check_main(&self, ir: &IR2, defs: &[Definition]) -> Vec<TypeError> {
+        let main = Node::Function("main".into());
+        let span = match defs.iter().find(|def| def.node == main) {
+            Some(Definition { sig: Some(sig), .. }) => sig.span,
+            Some(Definition { equations, .. }) if !equations.is_empty() => equations[0].1,
+            _ => return Vec::new(),
+        };
+        let intrinsics = intrinsics()
+            .into_iter()
+            .map(|(ident, _)| ident)
+            .collect::<HashSet<_>>();
+
+        self.effects[&main]
+            .iter()
+            .filter(|ident| {
+                let handled = ir
+                    .handler_symbols
+                    .find(ident)
+                    .is_some_and(|(functions, _)| !functions.is_empty());
+
+                !handled && !intrinsics.contains(&fallback(ident))
+            })
+            .map(|ident| TypeError::UnhandledEffect {
+                span,
+                ident: ident.clone(),
+            })
+            .collect()
+    }
+}
+
+/// `*write` falls back to `@write`.
+fn fallback(ident: &HandlerIdent) -> InstIdent {
+    InstIdent(ident.0.replacen('*', "@", 1))
+}
+
+fn definitions(ir: &IR2) -> Vec<Definition<'_>> {
+    let functions = ir
+        .ident_symbols
+        .iter()
+        .map(|(ident, (functions, sig))| Definition {
+            node: Node::Function(ident.clone()),
+            sig: sig.as_ref(),
+            equations: functions
+                .iter()
+                .map(|f| (uses(&f.expr, &f.where_clause), f.span))
+                .collect(),
+        });
+    let handlers = ir
+        .handler_symbols
+        .iter()
+        .map(|(ident, (functions, sig))| Definition {
+            node: Node::Handler(ident.clone()),
+            sig: sig.as_ref(),
+            equations: functions
+                .iter()
+                .map(|f| (uses(&f.expr, &f.where_clause), f.span))
+                .collect(),
+        });
+
+    functions.chain(handlers).collect()
+}
+
+/// Rows of the signatures as written, the others grow with the rows of the
+/// definitions they refer to until nothing changes.
+fn rows(defs: &[Definition]) -> HashMap<Node, Row> {
+    let mut rows = defs
+        .iter()
+        .map(|def| {
+            let row = match def.sig {
+                Some(sig) => sig
+                    .handler_constraints
+                    .iter()
+                    .map(|constraint| constraint.ident.clone())
+                    .collect(),
+                None => def
+                    .equations
+                    .iter()
+                    .flat_map(|(uses, _)| uses.handlers.iter().cloned())
+                    .collect(),
+            };
+
+            (def.node.clone(), row)
+        })
+        .collect::<HashMap<_, Row>>();
+
+    loop {
+        let changed = defs
+            .iter()
+            .filter(|def| def.sig.is_none())
+            .fold(false, |changed, def| {
+                let row = def
+                    .equations
+                    .iter()
+                    .flat_map(|(uses, _)| &uses.nodes)
+                    .filter_map(|(node, handled)| rows.get(node).map(|row| (row, handled)))
+                    .fold(rows[&def.node].clone(), |mut acc, (row, handled)| {
+                        acc.extend(row.difference(handled).cloned());
+                        acc
+                    });
+
+                match row.len() == rows[&def.node].len() {
+                    true => changed,
+                    false => {
+                        rows.insert(def.node.clone(), row);
+                        true
+                    }
+                }
+            });
+
+        if !changed {
+            return rows;
+        }
+    }
+}
+
+fn uses(abstruction: &Abstruction, where_clause: &IR2) -> Uses {
+    let mut uses = Uses::default();
+    walk_equation(abstruction, where_clause, &HashSet::new(), &mut uses);
+
+    uses
+}
+
+/// What the functions of a where-clause perform, the equation performs. They
+/// see its arguments.
+fn walk_equation(
+    abstruction: &Abstruction,
+    where_clause: &IR2,
+    bound: &HashSet<Ident>,
+    uses: &mut Uses,
+) {
+    let functions = where_clause.functions();
+    let mut bound = bound.clone();
+    bound.extend(params(abstruction));
+    bound.extend(functions.iter().map(|(ident, _)| (*ident).clone()));
+
+    walk_abstruction(abstruction, &bound, uses);
+    functions
+        .into_iter()
+        .flat_map(|(_, functions)| functions)
+        .for_each(|f| walk_equation(&f.expr, &f.where_clause, &bound, uses));
+}
+
+/// The variables the arguments of an equation bind.
+fn params(abstruction: &Abstruction) -> Vec<Ident> {
+    match abstruction {
+        Abstruction {
+            arg: Some(pattern),
+            expr,
+        } => {
+            let mut binds = pattern.binds();
+            if let Expr::Abstruction(inner) = expr {
+                binds.extend(params(inner));
+            }
+
+            binds
+        }
+        Abstruction { arg: None, .. } => Vec::new(),
+    }
+}
+
+fn walk_abstruction(abstruction: &Abstruction, bound: &HashSet<Ident>, uses: &mut Uses) {
+    match &abstruction.arg {
+        Some(pattern) => {
+            let mut bound = bound.clone();
+            bound.extend(pattern.binds());
+            walk(&abstruction.expr, &bound, uses);
+        }
+        None => walk(&abstruction.expr, bound, uses),
+    }
+}
+
+fn walk(expr: &Expr, bound: &HashSet<Ident>, uses: &mut Uses) {
+    match expr {
+        Expr::Literal(Literal::Tuple(_, items) | Literal::List(items)) => {
+            items.iter().for_each(|item| walk(item, bound, uses))
+        }
+        Expr::Literal(_) => {}
+        Expr::Abstruction(abstruction) => walk_abstruction(abstruction, bound, uses),
+        Expr::Apply(Apply {
+            abstruction, expr, ..
+        }) => {
+            walk_abstruction(abstruction, bound, uses);
+            walk(expr, bound, uses);
+        }
+        Expr::ApplyEmbedded(ApplyInst { expr, .. }) => walk(expr, bound, uses),
+        Expr::ApplyEff(ApplyEff { ident, expr, .. }) => {
+            perform(ident, uses);
+            walk(expr, bound, uses);
+        }
+        Expr::ReferenceHandler(ident) => perform(ident, uses),
+        Expr::Reference(ident) if !bound.contains(ident) => {
+            uses.nodes.push((Node::Function(ident.clone()), Row::new()))
+        }
+        Expr::Reference(_) | Expr::ReferenceInst(_) | Expr::ReferenceData(_) => {}
+        Expr::Pattern(_) => {}
+        Expr::Handle(handle) => walk_handle(handle, bound, uses),
+    }
+}
+
+/// The handlers run outside of the `handle`, what they perform is not handled
+/// by it.
+fn walk_handle(Handle { expr, handlers }: &Handle, bound: &HashSet<Ident>, uses: &mut Uses) {
+    let handled = handlers
+        .iter()
+        .map(|handler| handler.ident.clone())
+        .collect::<Row>();
+    let mut inner = Uses::default();
+    walk(expr, bound, &mut inner);
+
+    uses.handlers
+        .extend(inner.handlers.difference(&handled).cloned());
+    uses.nodes.extend(
+        inner
+            .nodes
+            .into_iter()
+            .filter(|(node, _)| !matches!(node, Node::Handler(ident) if handled.contains(ident)))
+            .map(|(node, mask)| (node, mask.union(&handled).cloned().collect())),
+    );
+
+    let mut bound = bound.clone();
+    bound.insert("resume".into());
+    handlers
+        .iter()
+        .for_each(|handler| walk_abstruction(&handler.expr, &bound, uses));
+}
+
+/// Performing an effect also performs what its handler does.
+fn perform(ident: &HandlerIdent, uses: &mut Uses) {
+    uses.handlers.insert(ident.clone());
+    uses.nodes.push((Node::Handler(ident.clone()), Row::new()));
+}
+
+impl std::fmt::Display for Node {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Node::Function(ident) => write!(f, "{}", ident.0),
+            Node::Handler(ident) => write!(f, "{}", ident.0),
+        }
+    }
+}
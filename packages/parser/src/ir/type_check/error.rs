@@ -0,0 +1,93 @@
+use super::{
+    traits::ImplKey,
+    types::{Constraint, Type},
+};
+use crate::ir::{HandlerIdent, Ident, Span};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum TypeError {
+    #[error("{span}: in {name}: expected {expected}, found {found}")]
+    Mismatch {
+        span: Span,
+        name: String,
+        expected: Type,
+        found: Type,
+    },
+    #[error("{span}: in {name}: {ty} is not a function")]
+    NotAFunction { span: Span, name: String, ty: Type },
+    #[error("{span}: in {name}: undefined reference {ident}")]
+    Undefined {
+        span: Span,
+        name: String,
+        ident: String,
+    },
+    #[error("{span}: in {name}: {constraint} is not in the signature")]
+    MissingConstraint {
+        span: Span,
+        name: String,
+        constraint: Constraint,
+    },
+    #[error("{span}: in {name}: {} is not in the eta-environment", .ident.0)]
+    UndeclaredEffect {
+        span: Span,
+        name: String,
+        ident: HandlerIdent,
+    },
+    #[error("{span}: in main: unhandled effect {}", .ident.0)]
+    UnhandledEffect { span: Span, ident: HandlerIdent },
+    #[error("{span}: in {name}: no impl for {constraint}")]
+    NoImpl {
+        span: Span,
+        name: String,
+        constraint: Constraint,
+    },
+    #[error("{span}: impl {key} overlaps an earlier impl")]
+    OverlappingImpl { span: Span, key: ImplKey },
+    #[error("{span}: in impl {key}: {method} is not defined")]
+    MissingMethod {
+        span: Span,
+        key: ImplKey,
+        method: Ident,
+    },
+    #[error("{span}: {member} is a member of both {first} and {second}")]
+    AmbiguousMember {
+        span: Span,
+        member: String,
+        first: String,
+        second: String,
+    },
+    #[error("{span}: data {} is a union of itself", path(.cycle))]
+    CyclicData { span: Span, cycle: Vec<String> },
+    #[error("{span}: trait {} is a superclass of itself", path(.cycle))]
+    CyclicSuperclass { span: Span, cycle: Vec<String> },
+}
+
+/// `A -> B -> A`
+fn path(cycle: &[String]) -> String {
+    cycle
+        .iter()
+        .chain(cycle.first())
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
+
+impl TypeError {
+    pub fn span(&self) -> Span {
+        match self {
+            TypeError::Mismatch { span, .. }
+            | TypeError::NotAFunction { span, .. }
+            | TypeError::Undefined { span, .. }
+            | TypeError::MissingConstraint { span, .. }
+            | TypeError::UndeclaredEffect { span, .. }
+            | TypeError::UnhandledEffect { span, .. }
+            | TypeError::NoImpl { span, .. }
+            | TypeError::OverlappingImpl { span, .. }
+            | TypeError::MissingMethod { span, .. }
+            | TypeError::AmbiguousMember { span, .. }
+            | TypeError::CyclicData { span, .. }
+            | TypeError::CyclicSuperclass { span, .. } => *span,
+        }
+    }
+}
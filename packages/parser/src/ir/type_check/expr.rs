@@ -0,0 +1,465 @@
+use super::{
+    builtins,
+    error::TypeError,
+    infer::Infer,
+    types::{Constraint, Scheme, Type},
+    Group, TypeEnv,
+};
+use crate::ir::{
+    ir2::IR2, Abstruction, Apply, ApplyEff, ApplyInst, DataConstructor, DataTerm, ExistsIdent,
+    Expr, Handle, HandlerIdent, Ident, InstIdent, Literal, PatternExpr, Span, TypeIdent,
+};
+use std::collections::HashMap;
+
+type Locals = HashMap<Ident, Type>;
+
+/// A reference to a top-level function, by its position in
+/// `Expr::references`, and the type it is used at.
+#[derive(Debug)]
+pub(super) struct Reference {
+    pub(super) index: usize,
+    pub(super) callee: Ident,
+    pub(super) ty: Type,
+}
+
+/// Infers the types of one definition.
+pub(super) struct Context<'a> {
+    pub(super) env: &'a TypeEnv,
+    pub(super) infer: &'a mut Infer,
+    pub(super) group: &'a Group,
+    pub(super) wanted: Vec<Constraint>,
+    pub(super) errors: Vec<TypeError>,
+    /// The positions of the references of the equation.
+    pub(super) references: HashMap<*const Expr, usize>,
+    pub(super) sites: Vec<Reference>,
+    pub(super) name: String,
+    pub(super) span: Span,
+}
+
+impl Context<'_> {
+    pub(super) fn abstruction(&mut self, abstruction: &Abstruction, where_clause: &IR2) -> Type {
+        self.infer_abstruction(abstruction, Some(where_clause), &Locals::new())
+    }
+
+    /// Checks an equation of a handler against the type of its effect. A
+    /// handler that calls `resume`, and any clause of a `handle`, returns
+    /// `answer`, the value of the `handle`, and its effect returns what `resume`
+    /// is called with. A handler at the top level that does not returns the
+    /// value of its effect.
+    pub(super) fn clause(
+        &mut self,
+        abstruction: &Abstruction,
+        where_clause: &IR2,
+        resumes: bool,
+        op: &Type,
+        answer: &Type,
+    ) {
+        self.infer_clause(
+            abstruction,
+            Some(where_clause),
+            resumes,
+            op,
+            answer,
+            &Locals::new(),
+        )
+    }
+
+    fn infer_clause(
+        &mut self,
+        abstruction: &Abstruction,
+        where_clause: Option<&IR2>,
+        resumes: bool,
+        op: &Type,
+        answer: &Type,
+        locals: &Locals,
+    ) {
+        if !resumes {
+            let ty = self.infer_abstruction(abstruction, where_clause, locals);
+            self.expect(op, &ty);
+
+            return;
+        }
+
+        let args = (0..arity(abstruction))
+            .map(|_| self.infer.fresh())
+            .collect::<Vec<_>>();
+        let curried = |ret| {
+            args.iter()
+                .rev()
+                .fold(ret, |acc, arg| Type::arrow(arg.clone(), acc))
+        };
+        let resumed = self.infer.fresh();
+        self.expect(op, &curried(resumed.clone()));
+
+        let mut locals = locals.clone();
+        locals.insert("resume".into(), Type::arrow(resumed, answer.clone()));
+        let ty = self.infer_abstruction(abstruction, where_clause, &locals);
+        self.expect(&curried(answer.clone()), &ty);
+    }
+
+    /// Unifies, reporting a mismatch of the whole types.
+    pub(super) fn expect(&mut self, expected: &Type, found: &Type) {
+        if self.infer.unify(expected, found).is_err() {
+            self.errors.push(TypeError::Mismatch {
+                span: self.span,
+                name: self.name.clone(),
+                expected: self.infer.resolve(expected),
+                found: self.infer.resolve(found),
+            });
+        }
+    }
+
+    /// The functions of the where-clause of an equation are in scope of its
+    /// body, after its arguments.
+    fn infer_abstruction(
+        &mut self,
+        abstruction: &Abstruction,
+        where_clause: Option<&IR2>,
+        locals: &Locals,
+    ) -> Type {
+        match &abstruction.arg {
+            Some(pattern) => {
+                let mut locals = locals.clone();
+                let arg = self.pattern(pattern, &mut locals);
+                let ret = match &abstruction.expr {
+                    Expr::Abstruction(inner) => {
+                        self.infer_abstruction(inner, where_clause, &locals)
+                    }
+                    expr => {
+                        let locals = self.where_clause(where_clause, &locals);
+                        self.expr(expr, &locals)
+                    }
+                };
+
+                Type::arrow(arg, ret)
+            }
+            None => {
+                let locals = self.where_clause(where_clause, locals);
+                self.expr(&abstruction.expr, &locals)
+            }
+        }
+    }
+
+    /// The functions of a where-clause see each other, they are inferred
+    /// together and are not generalized. A signature gives the type of one.
+    fn where_clause(&mut self, where_clause: Option<&IR2>, locals: &Locals) -> Locals {
+        let mut locals = locals.clone();
+        let where_clause = match where_clause {
+            Some(where_clause) => where_clause,
+            None => return locals,
+        };
+
+        let functions = where_clause.functions();
+        functions.iter().for_each(|(ident, _)| {
+            let ty = match where_clause.ident_symbols.find(ident) {
+                Some((_, Some(sig))) => self.infer.instantiate(&sig.into()).0,
+                _ => self.infer.fresh(),
+            };
+            locals.insert((*ident).clone(), ty);
+        });
+        functions.iter().for_each(|(ident, functions)| {
+            functions.iter().for_each(|f| {
+                self.at(f.span, |this| {
+                    let ty = this.infer_abstruction(&f.expr, Some(&f.where_clause), &locals);
+                    this.expect(&locals[*ident], &ty);
+
+                    ty
+                });
+            })
+        });
+
+        locals
+    }
+
+    fn expr(&mut self, expr: &Expr, locals: &Locals) -> Type {
+        match expr {
+            Expr::Literal(literal) => self.literal(literal, locals),
+            Expr::Abstruction(abstruction) => self.infer_abstruction(abstruction, None, locals),
+            Expr::Apply(Apply {
+                abstruction,
+                expr,
+                span,
+            }) => self.at(*span, |this| match abstruction.as_ref() {
+                Abstruction { arg: None, expr: f } => {
+                    let f = this.expr(f, locals);
+                    let arg = this.expr(expr, locals);
+
+                    this.apply(f, arg)
+                }
+                Abstruction {
+                    arg: Some(pattern),
+                    expr: body,
+                } => {
+                    let arg = this.expr(expr, locals);
+                    let mut locals = locals.clone();
+                    let pattern = this.pattern(pattern, &mut locals);
+                    this.expect(&pattern, &arg);
+
+                    this.expr(body, &locals)
+                }
+            }),
+            Expr::ApplyEmbedded(ApplyInst { ident, expr, span }) => self.at(*span, |this| {
+                let f = this.inst(ident);
+                let arg = this.expr(expr, locals);
+
+                this.apply(f, arg)
+            }),
+            Expr::ApplyEff(ApplyEff { ident, expr, span }) => self.at(*span, |this| {
+                let f = this.handler(ident);
+                let arg = this.expr(expr, locals);
+
+                this.apply(f, arg)
+            }),
+            Expr::Reference(ident) => match locals.get(ident) {
+                Some(ty) => ty.clone(),
+                None => {
+                    let ty = self.function(ident);
+                    if let Some(index) = self.references.get(&(expr as *const Expr)) {
+                        self.sites.push(Reference {
+                            index: *index,
+                            callee: ident.clone(),
+                            ty: ty.clone(),
+                        });
+                    }
+
+                    ty
+                }
+            },
+            Expr::ReferenceInst(ident) => self.inst(ident),
+            Expr::ReferenceHandler(ident) => self.handler(ident),
+            Expr::ReferenceData(TypeIdent::ExistsIdent(ident)) => self.constructor(ident),
+            Expr::ReferenceData(TypeIdent::ForallIdent(ident)) => self.undefined(&ident.0),
+            Expr::Pattern(_) => self.infer.fresh(),
+            Expr::Handle(Handle { expr, handlers }) => {
+                let answer = self.expr(expr, locals);
+                handlers.iter().for_each(|handler| {
+                    let op = self.handler(&handler.ident);
+                    self.infer_clause(&handler.expr, None, true, &op, &answer, locals);
+                });
+
+                answer
+            }
+        }
+    }
+
+    /// Reports the errors of `infer` at `span`, the innermost application
+    /// around them.
+    fn at(&mut self, span: Span, infer: impl FnOnce(&mut Self) -> Type) -> Type {
+        let outer = std::mem::replace(&mut self.span, span);
+        let ty = infer(self);
+        self.span = outer;
+
+        ty
+    }
+
+    fn literal(&mut self, literal: &Literal, locals: &Locals) -> Type {
+        match literal {
+            Literal::Char(_) => Type::con("Char"),
+            Literal::Text(_) => Type::text(),
+            Literal::Int(_) => Type::con("Int"),
+            Literal::Float(_) => Type::con("Float"),
+            Literal::Integer(_) => Type::con("Integer"),
+            Literal::Rational(_) => Type::con("Rational"),
+            Literal::Tuple(_, items) => {
+                Type::Tuple(items.iter().map(|item| self.expr(item, locals)).collect())
+            }
+            Literal::List(items) => {
+                let elem = items.iter().fold(self.infer.fresh(), |elem, item| {
+                    let item = self.expr(item, locals);
+                    self.join(&elem, &item)
+                });
+
+                Type::List(Box::new(elem))
+            }
+        }
+    }
+
+    fn apply(&mut self, f: Type, arg: Type) -> Type {
+        match self.infer.resolve(&f) {
+            Type::Arrow(param, ret) => {
+                self.subsume(&param, &arg);
+
+                *ret
+            }
+            Type::Var(_) | Type::Bottom => {
+                let ret = self.infer.fresh();
+                self.expect(&f, &Type::arrow(arg, ret.clone()));
+
+                ret
+            }
+            ty => {
+                self.errors.push(TypeError::NotAFunction {
+                    span: self.span,
+                    name: self.name.clone(),
+                    ty,
+                });
+
+                self.infer.fresh()
+            }
+        }
+    }
+
+    fn pattern(&mut self, pattern: &PatternExpr, locals: &mut Locals) -> Type {
+        match pattern {
+            PatternExpr::Or(lhs, rhs) => {
+                let lhs = self.pattern(lhs, locals);
+                let rhs = self.pattern(rhs, locals);
+
+                self.join(&lhs, &rhs)
+            }
+            PatternExpr::Literal(literal) => self.literal_pattern(literal, locals),
+            PatternExpr::Bind(ident) => {
+                let ty = self.infer.fresh();
+                locals.insert(ident.clone(), ty.clone());
+
+                ty
+            }
+            PatternExpr::Constructor(DataConstructor(_, TypeIdent::ExistsIdent(ident), args)) => {
+                self.constructor_pattern(ident, args, locals)
+            }
+            PatternExpr::Tuple(_, items) => Type::Tuple(
+                items
+                    .iter()
+                    .map(|item| self.pattern(item, locals))
+                    .collect(),
+            ),
+            PatternExpr::ListHead(head, tail) => {
+                let list = Type::List(Box::new(self.pattern(head, locals)));
+                let tail = self.pattern(tail, locals);
+                self.expect(&list, &tail);
+
+                list
+            }
+            PatternExpr::Constructor(_) | PatternExpr::Any => self.infer.fresh(),
+        }
+    }
+
+    /// The items of tuple and list patterns are parsed as expressions, a
+    /// reference binds.
+    fn literal_pattern(&mut self, literal: &Literal, locals: &mut Locals) -> Type {
+        let mut item = |this: &mut Self, item: &Expr| match item {
+            Expr::Reference(ident) => {
+                let ty = this.infer.fresh();
+                locals.insert(ident.clone(), ty.clone());
+
+                ty
+            }
+            Expr::Literal(literal) => this.literal_pattern(literal, locals),
+            item => this.expr(item, locals),
+        };
+
+        match literal {
+            Literal::Tuple(_, items) => {
+                Type::Tuple(items.iter().map(|it| item(self, it)).collect())
+            }
+            Literal::List(items) => {
+                let elem = self.infer.fresh();
+                items.iter().for_each(|it| {
+                    let ty = item(self, it);
+                    self.expect(&elem, &ty);
+                });
+
+                Type::List(Box::new(elem))
+            }
+            literal => self.literal(literal, locals),
+        }
+    }
+
+    /// Constructor patterns are parsed as type expressions, a lower-case ident
+    /// binds and an upper-case one matches.
+    fn constructor_pattern(
+        &mut self,
+        ident: &ExistsIdent,
+        args: &[DataTerm],
+        locals: &mut Locals,
+    ) -> Type {
+        let constructor = self.constructor(ident);
+
+        args.iter().fold(constructor, |acc, arg| {
+            let arg = match arg {
+                DataTerm::Constructor(DataConstructor(_, TypeIdent::ForallIdent(ident), args))
+                    if args.is_empty() =>
+                {
+                    let ty = self.infer.fresh();
+                    locals.insert(Ident(ident.0.clone()), ty.clone());
+
+                    ty
+                }
+                DataTerm::Constructor(DataConstructor(_, TypeIdent::ExistsIdent(ident), args)) => {
+                    self.constructor_pattern(ident, args, locals)
+                }
+                DataTerm::Unit => Type::unit(),
+                _ => self.infer.fresh(),
+            };
+
+            self.apply(acc, arg)
+        })
+    }
+
+    fn function(&mut self, ident: &Ident) -> Type {
+        match (
+            self.group.functions.get(ident),
+            self.env.functions.get(ident),
+        ) {
+            (Some(ty), _) => ty.clone(),
+            (None, Some(scheme)) => self.instantiate(scheme),
+            (None, None) => match builtins().into_iter().find(|(other, _)| other == ident) {
+                Some((_, scheme)) => self.instantiate(&scheme),
+                None => self.undefined(&ident.0),
+            },
+        }
+    }
+
+    /// A handler the program does not define is the intrinsic of the same
+    /// name, `*write` is `@write`.
+    fn handler(&mut self, ident: &HandlerIdent) -> Type {
+        match (self.group.handlers.get(ident), self.env.handlers.get(ident)) {
+            (Some(ty), _) => ty.clone(),
+            (None, Some(scheme)) => self.instantiate(scheme),
+            (None, None) => self.inst(&InstIdent(ident.0.replacen('*', "@", 1))),
+        }
+    }
+
+    fn inst(&mut self, ident: &InstIdent) -> Type {
+        match self.env.insts.get(ident) {
+            Some(scheme) => self.instantiate(scheme),
+            None => self.undefined(&ident.0),
+        }
+    }
+
+    fn constructor(&mut self, ident: &ExistsIdent) -> Type {
+        match self.env.constructors.get(ident) {
+            Some(scheme) => self.instantiate(scheme),
+            None => self.undefined(&ident.0),
+        }
+    }
+
+    fn instantiate(&mut self, scheme: &Scheme) -> Type {
+        let (ty, constraints) = self.infer.instantiate(scheme);
+        self.wanted.extend(constraints);
+
+        ty
+    }
+
+    fn undefined(&mut self, ident: &str) -> Type {
+        self.errors.push(TypeError::Undefined {
+            span: self.span,
+            name: self.name.clone(),
+            ident: ident.to_string(),
+        });
+
+        self.infer.fresh()
+    }
+}
+
+/// The number of arguments of curried abstractions.
+fn arity(abstruction: &Abstruction) -> usize {
+    match abstruction {
+        Abstruction {
+            arg: Some(_),
+            expr: Expr::Abstruction(inner),
+        } => 1 + arity(inner),
+        Abstruction { arg: Some(_), .. } => 1,
+        Abstruction { arg: None, .. } => 0,
+    }
+}
@@ -0,0 +1,146 @@
+use super::types::{Constraint, Scheme, Type};
+use std::collections::HashMap;
+
+#[derive(Debug)]
+pub(super) struct Mismatch;
+
+/// Unification variables and their substitution.
+#[derive(Debug, Default)]
+pub(super) struct Infer {
+    next: usize,
+    subst: HashMap<usize, Type>,
+}
+
+impl Infer {
+    pub(super) fn fresh(&mut self) -> Type {
+        self.next += 1;
+
+        Type::Var(self.next - 1)
+    }
+
+    /// Widens a variable that is bound already.
+    pub(super) fn bind(&mut self, var: usize, ty: Type) {
+        self.subst.insert(var, ty);
+    }
+
+    /// Applies the substitution.
+    pub(super) fn resolve(&self, ty: &Type) -> Type {
+        ty.map(&|ty| match ty {
+            Type::Var(var) => self.subst.get(var).map(|ty| self.resolve(ty)),
+            _ => None,
+        })
+    }
+
+    pub(super) fn resolve_constraint(&self, constraint: &Constraint) -> Constraint {
+        constraint.map(&|ty| match ty {
+            Type::Var(var) => self.subst.get(var).map(|ty| self.resolve(ty)),
+            _ => None,
+        })
+    }
+
+    pub(super) fn unify(&mut self, lhs: &Type, rhs: &Type) -> Result<(), Mismatch> {
+        match (self.resolve(lhs), self.resolve(rhs)) {
+            (Type::Var(lhs), Type::Var(rhs)) if lhs == rhs => Ok(()),
+            (Type::Var(var), ty) | (ty, Type::Var(var)) => match ty.vars().contains(&var) {
+                true => Err(Mismatch),
+                false => {
+                    self.subst.insert(var, ty);
+                    Ok(())
+                }
+            },
+            (Type::Bottom, _) | (_, Type::Bottom) | (Type::Top, Type::Top) => Ok(()),
+            (Type::Param(lhs), Type::Param(rhs)) if lhs == rhs => Ok(()),
+            (Type::Con(lhs, lhs_args), Type::Con(rhs, rhs_args))
+                if lhs == rhs && lhs_args.len() == rhs_args.len() =>
+            {
+                self.unify_all(&lhs_args, &rhs_args)
+            }
+            (Type::Tuple(lhs), Type::Tuple(rhs)) if lhs.len() == rhs.len() => {
+                self.unify_all(&lhs, &rhs)
+            }
+            (Type::Arrow(lhs_arg, lhs_ret), Type::Arrow(rhs_arg, rhs_ret))
+            | (Type::Coroutine(lhs_arg, lhs_ret), Type::Coroutine(rhs_arg, rhs_ret)) => {
+                self.unify(&lhs_arg, &rhs_arg)?;
+                self.unify(&lhs_ret, &rhs_ret)
+            }
+            (Type::List(lhs), Type::List(rhs)) => self.unify(&lhs, &rhs),
+            _ => Err(Mismatch),
+        }
+    }
+
+    fn unify_all(&mut self, lhs: &[Type], rhs: &[Type]) -> Result<(), Mismatch> {
+        lhs.iter()
+            .zip(rhs)
+            .try_for_each(|(lhs, rhs)| self.unify(lhs, rhs))
+    }
+
+    /// Replaces the parameters of the scheme with fresh variables.
+    pub(super) fn instantiate(&mut self, scheme: &Scheme) -> (Type, Vec<Constraint>) {
+        let params = scheme
+            .params
+            .iter()
+            .map(|param| (param.clone(), self.fresh()))
+            .collect::<HashMap<_, _>>();
+        let instantiate = |ty: &Type| match ty {
+            Type::Param(param) => params.get(param).cloned(),
+            _ => None,
+        };
+
+        let constraints = scheme
+            .constraints
+            .iter()
+            .map(|constraint| constraint.map(&instantiate))
+            .collect();
+
+        (scheme.ty.map(&instantiate), constraints)
+    }
+
+    /// Quantifies over the variables left in `ty`. Constraints on those
+    /// variables become the constraints of the scheme.
+    pub(super) fn generalize(&self, ty: &Type, wanted: &[Constraint]) -> Scheme {
+        let ty = self.resolve(ty);
+        let vars = ty.vars();
+        let params = vars
+            .iter()
+            .enumerate()
+            .map(|(idx, var)| (*var, param_name(idx)))
+            .collect::<HashMap<_, _>>();
+        let generalize = |ty: &Type| match ty {
+            Type::Var(var) => params.get(var).cloned().map(Type::Param),
+            _ => None,
+        };
+
+        let constraints = wanted
+            .iter()
+            .map(|constraint| self.resolve_constraint(constraint))
+            .filter(|constraint| {
+                let constraint_vars = constraint.vars();
+
+                !constraint_vars.is_empty() && constraint_vars.iter().all(|var| vars.contains(var))
+            })
+            .fold(Vec::new(), |mut acc, constraint| {
+                let constraint = constraint.map(&generalize);
+                if !acc.contains(&constraint) {
+                    acc.push(constraint);
+                }
+
+                acc
+            });
+
+        Scheme {
+            params: vars.iter().map(|var| params[var].clone()).collect(),
+            constraints,
+            ty: ty.map(&generalize),
+        }
+    }
+}
+
+/// `a`, `b`, ..., `z`, `a1`, ...
+fn param_name(idx: usize) -> String {
+    let name = (b'a' + (idx % 26) as u8) as char;
+
+    match idx / 26 {
+        0 => name.to_string(),
+        n => format!("{name}{n}"),
+    }
+}
@@ -0,0 +1,672 @@
+mod dependency;
+mod effect;
+mod error;
+mod expr;
+mod infer;
+mod signature;
+mod subtype;
+mod traits;
+mod types;
+
+pub use self::effect::Row;
+pub use self::error::TypeError;
+pub(crate) use self::traits::is_show;
+pub use self::traits::{Dict, Impl, ImplKey, Owner, Site};
+pub use self::types::{Constraint, Scheme, Type};
+
+use self::{
+    effect::Node,
+    expr::{Context, Reference},
+    infer::Infer,
+    signature::handler_scheme,
+    traits::{constraints_at, Trait},
+};
+use super::{
+    ir2::IR2, lattice::DataLattice, Abstruction, DataExpr, DataTerm, ExistsIdent, Expr,
+    HandlerIdent, Ident, InstIdent, Span, TraitIdent,
+};
+use std::{collections::HashMap, fmt::Display};
+
+/// Types of the top-level definitions of a program.
+#[derive(Debug, Default)]
+pub struct TypeEnv {
+    functions: HashMap<Ident, Scheme>,
+    handlers: HashMap<HandlerIdent, Scheme>,
+    insts: HashMap<InstIdent, Scheme>,
+    constructors: HashMap<ExistsIdent, Scheme>,
+    effects: HashMap<Node, Row>,
+    traits: HashMap<TraitIdent, Trait>,
+    impls: HashMap<ImplKey, Impl>,
+    methods: HashMap<Ident, TraitIdent>,
+    dictionaries: HashMap<Site, Vec<Dict>>,
+    lattice: DataLattice,
+}
+
+/// Definitions inferred together, before they are generalized.
+#[derive(Debug, Default)]
+struct Group {
+    functions: HashMap<Ident, Type>,
+    handlers: HashMap<HandlerIdent, Type>,
+}
+
+/// An equation and the where-clause local to it.
+type Equation<'a> = (&'a Abstruction, &'a IR2, Span);
+
+/// A definition to infer, `name` equation by equation.
+struct Definition<'a> {
+    name: String,
+    owner: Owner,
+    equations: Vec<Equation<'a>>,
+    /// A handler that mentions `resume`.
+    resumes: bool,
+}
+
+/// What inferring a definition leaves to resolve.
+#[derive(Debug, Default)]
+struct Inferred {
+    wanted: Vec<Constraint>,
+    errors: Vec<TypeError>,
+    /// The references to top-level functions, by equation.
+    sites: Vec<(usize, Reference)>,
+}
+
+pub fn type_check(ir: &IR2) -> (TypeEnv, Vec<TypeError>) {
+    let mut env = TypeEnv::default();
+    let errors = env.check(ir);
+
+    (env, errors)
+}
+
+impl TypeEnv {
+    pub fn function(&self, ident: &Ident) -> Option<&Scheme> {
+        self.functions.get(ident)
+    }
+
+    pub fn handler(&self, ident: &HandlerIdent) -> Option<&Scheme> {
+        self.handlers.get(ident)
+    }
+
+    /// The scheme of the function `ident` after the effects it performs,
+    /// `<*write> IO ()`.
+    pub fn describe(&self, ident: &Ident) -> Option<String> {
+        let node = Node::Function(ident.clone());
+
+        Some(self.typed(&node, self.functions.get(ident)?))
+    }
+
+    fn typed(&self, node: &Node, scheme: &Scheme) -> String {
+        match self.effects.get(node) {
+            Some(row) if !row.is_empty() => format!(
+                "<{}> {scheme}",
+                row.iter()
+                    .map(|ident| ident.0.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            _ => scheme.to_string(),
+        }
+    }
+
+    pub fn effects(&self, ident: &Ident) -> Option<&Row> {
+        self.effects.get(&Node::Function(ident.clone()))
+    }
+
+    /// Infers the functions and handlers without a signature, then checks the
+    /// ones with a signature and the methods of the impls against it. Errors
+    /// are in source order.
+    pub fn check(&mut self, ir: &IR2) -> Vec<TypeError> {
+        self.declare(ir);
+
+        let mut errors = dependency::cycles(ir);
+        errors.extend(self.declare_lattice(ir));
+        errors.extend(self.declare_impls(ir));
+        errors.extend(self.infer_unannotated(ir));
+        errors.extend(self.check_annotated(ir));
+        errors.extend(self.check_impls(ir));
+        errors.extend(self.check_effects(ir));
+        errors.sort_by_key(|error| error.span().start);
+
+        errors
+    }
+
+    fn declare(&mut self, ir: &IR2) {
+        self.insts.extend(intrinsics());
+        self.insts.extend(
+            ir.inst_symbols
+                .iter()
+                .map(|(ident, sig)| (ident.clone(), sig.into())),
+        );
+
+        // constructors of a union win over a `nominal data True = ()` of the
+        // same name
+        let (values, unions): (Vec<_>, Vec<_>) = ir
+            .data_symbols
+            .iter()
+            .partition(|(_, def)| matches!(def.expr(), DataExpr::Term(DataTerm::Unit)));
+        values.into_iter().chain(unions).for_each(|(ident, def)| {
+            self.constructors
+                .extend(signature::constructor_schemes(ident, def))
+        });
+
+        ir.ident_symbols.iter().for_each(|(ident, (_, sig))| {
+            if let Some(sig) = sig {
+                self.functions.insert(ident.clone(), sig.into());
+            }
+        });
+        ir.handler_symbols.iter().for_each(|(ident, (_, sig))| {
+            if let Some(sig) = sig {
+                self.handlers.insert(ident.clone(), handler_scheme(sig));
+            }
+        });
+        self.declare_traits(ir);
+    }
+
+    /// Functions and handlers without a signature are inferred in groups of
+    /// mutually recursive definitions. A group is monomorphic, and generalized
+    /// before the groups that refer to it.
+    fn infer_unannotated(&mut self, ir: &IR2) -> Vec<TypeError> {
+        let functions = ir
+            .ident_symbols
+            .iter()
+            .filter(|(_, (functions, sig))| sig.is_none() && !functions.is_empty())
+            .map(|(ident, (functions, _))| {
+                let equations = functions
+                    .iter()
+                    .map(|f| (&f.expr, &f.where_clause, f.span))
+                    .collect();
+
+                (ident, Definition::function(ident, equations))
+            });
+        let handlers = ir
+            .handler_symbols
+            .iter()
+            .filter(|(_, (functions, sig))| sig.is_none() && !functions.is_empty())
+            .map(|(ident, (functions, _))| {
+                let equations = functions
+                    .iter()
+                    .map(|f| (&f.expr, &f.where_clause, f.span))
+                    .collect();
+
+                (ident, Definition::handler(ident, equations))
+            });
+        let defs = sorted(functions)
+            .into_iter()
+            .map(|(_, def)| def)
+            .chain(sorted(handlers).into_iter().map(|(_, def)| def))
+            .collect::<Vec<_>>();
+
+        dependency::groups(defs)
+            .into_iter()
+            .flat_map(|defs| self.infer_group(defs))
+            .collect()
+    }
+
+    fn infer_group(&mut self, defs: Vec<Definition>) -> Vec<TypeError> {
+        let mut infer = Infer::default();
+        let types = defs.iter().map(|_| infer.fresh()).collect::<Vec<_>>();
+        let group = defs
+            .iter()
+            .zip(&types)
+            .fold(Group::default(), |mut group, (def, ty)| {
+                match &def.owner {
+                    Owner::Function(ident) => {
+                        group.functions.insert(ident.clone(), ty.clone());
+                    }
+                    Owner::Handler(ident) => {
+                        group.handlers.insert(ident.clone(), ty.clone());
+                    }
+                    Owner::Method(_, _) => unreachable!(),
+                }
+
+                group
+            });
+
+        let defs = defs
+            .iter()
+            .zip(&types)
+            .map(|(def, ty)| (ty, def, self.infer_definition(&mut infer, &group, def, ty)))
+            .collect::<Vec<_>>();
+        let wanted = defs
+            .iter()
+            .flat_map(|(_, _, inferred)| inferred.wanted.iter().cloned())
+            .collect::<Vec<_>>();
+
+        group.functions.iter().for_each(|(ident, ty)| {
+            self.functions
+                .insert(ident.clone(), infer.generalize(ty, &wanted));
+        });
+        group.handlers.iter().for_each(|(ident, ty)| {
+            self.handlers
+                .insert(ident.clone(), infer.generalize(ty, &wanted));
+        });
+
+        // the dictionaries a function takes are the constraints of its scheme
+        // where the group is not generalized
+        defs.into_iter()
+            .flat_map(|(ty, def, inferred)| {
+                let scheme = match &def.owner {
+                    Owner::Function(ident) => &self.functions[ident],
+                    Owner::Handler(ident) => &self.handlers[ident],
+                    Owner::Method(_, _) => unreachable!(),
+                };
+                let given = constraints_at(scheme, &infer.resolve(ty)).unwrap_or_default();
+
+                self.resolve_definition(&infer, def, inferred, &given)
+            })
+            .collect()
+    }
+
+    /// Checks the equations against the signature. The parameters of the
+    /// signature are rigid, and trait constraints on them must be given by the
+    /// signature.
+    fn check_annotated(&mut self, ir: &IR2) -> Vec<TypeError> {
+        let functions = ir
+            .ident_symbols
+            .iter()
+            .filter(|(_, (functions, sig))| sig.is_some() && !functions.is_empty())
+            .map(|(ident, (functions, _))| {
+                let equations = functions
+                    .iter()
+                    .map(|f| (&f.expr, &f.where_clause, f.span))
+                    .collect();
+
+                (
+                    self.functions[ident].clone(),
+                    Definition::function(ident, equations),
+                )
+            });
+        let handlers = ir
+            .handler_symbols
+            .iter()
+            .filter(|(_, (functions, sig))| sig.is_some() && !functions.is_empty())
+            .map(|(ident, (functions, _))| {
+                let equations = functions
+                    .iter()
+                    .map(|f| (&f.expr, &f.where_clause, f.span))
+                    .collect();
+
+                (
+                    self.handlers[ident].clone(),
+                    Definition::handler(ident, equations),
+                )
+            });
+
+        functions
+            .chain(handlers)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .flat_map(|(scheme, def)| {
+                let mut infer = Infer::default();
+                let inferred =
+                    self.infer_definition(&mut infer, &Group::default(), &def, &scheme.ty);
+
+                self.resolve_definition(&infer, &def, inferred, &scheme.constraints)
+            })
+            .collect()
+    }
+
+    /// Checks the methods of each impl against the trait, with the parameters
+    /// of the trait replaced by the type of the impl. An impl that overlaps an
+    /// earlier one is not checked.
+    fn check_impls(&mut self, ir: &IR2) -> Vec<TypeError> {
+        let mut checked = Vec::new();
+        let impls = ir
+            .impls
+            .iter()
+            .filter_map(|def| Some((def, ImplKey::of(def)?)))
+            .filter(|(_, key)| {
+                let first = self.impls.contains_key(key) && !checked.contains(key);
+                checked.push(key.clone());
+
+                first
+            })
+            .collect::<Vec<_>>();
+
+        impls
+            .into_iter()
+            .flat_map(|(def, key)| {
+                let mut errors = self.check_impl_methods(def, &key);
+                let methods =
+                    def.methods
+                        .iter()
+                        .fold(Vec::<(&Ident, Vec<_>)>::new(), |mut methods, f| {
+                            match methods.iter_mut().find(|(ident, _)| *ident == &f.ident) {
+                                Some((_, equations)) => {
+                                    equations.push((&f.expr, &f.where_clause, f.span))
+                                }
+                                None => methods
+                                    .push((&f.ident, vec![(&f.expr, &f.where_clause, f.span)])),
+                            }
+
+                            methods
+                        });
+
+                errors.extend(methods.into_iter().flat_map(|(ident, equations)| {
+                    let (ty, given) = match self.impl_method(&key, ident) {
+                        Some(method) => method,
+                        None => return Vec::new(),
+                    };
+                    let def = Definition::method(&key, ident, equations);
+                    let mut infer = Infer::default();
+                    let inferred = self.infer_definition(&mut infer, &Group::default(), &def, &ty);
+
+                    self.resolve_definition(&infer, &def, inferred, &given)
+                }));
+
+                errors
+            })
+            .collect()
+    }
+
+    /// Resolves the constraints a definition wants against the ones it is
+    /// `given`, and records the dictionaries each reference to a constrained
+    /// function is applied to. The clauses of a handler take no dictionaries.
+    fn resolve_definition(
+        &mut self,
+        infer: &Infer,
+        def: &Definition,
+        inferred: Inferred,
+        given: &[Constraint],
+    ) -> Vec<TypeError> {
+        let Inferred {
+            wanted,
+            mut errors,
+            sites,
+        } = inferred;
+        let span = def.equations[0].2;
+
+        let unresolved = wanted
+            .iter()
+            .map(|constraint| infer.resolve_constraint(constraint))
+            .filter(|constraint| {
+                constraint.vars().is_empty() && self.resolve(constraint, given).is_none()
+            })
+            .fold(Vec::new(), |mut acc, constraint| {
+                if !acc.contains(&constraint) {
+                    acc.push(constraint);
+                }
+
+                acc
+            });
+        errors.extend(unresolved.iter().map(|constraint| {
+            let name = def.name.clone();
+            let constraint = constraint.clone();
+            match constraint.has_params() {
+                true => TypeError::MissingConstraint {
+                    span,
+                    name,
+                    constraint,
+                },
+                false => TypeError::NoImpl {
+                    span,
+                    name,
+                    constraint,
+                },
+            }
+        }));
+
+        let given = match def.owner {
+            Owner::Handler(_) => &[],
+            _ => given,
+        };
+        sites.into_iter().for_each(|(equation, reference)| {
+            let wanted = match self
+                .functions
+                .get(&reference.callee)
+                .and_then(|scheme| constraints_at(scheme, &infer.resolve(&reference.ty)))
+            {
+                Some(wanted) if !wanted.is_empty() => wanted,
+                _ => return,
+            };
+
+            let dicts = wanted
+                .iter()
+                .map(|constraint| self.resolve(constraint, given).ok_or(constraint))
+                .collect::<Result<Vec<_>, _>>();
+            match dicts {
+                Ok(dicts) => {
+                    let site = Site {
+                        owner: def.owner.clone(),
+                        equation,
+                        reference: reference.index,
+                    };
+                    self.dictionaries.insert(site, dicts);
+                }
+                // reported above, unless it is ambiguous or in a handler
+                Err(constraint) if unresolved.contains(constraint) => {}
+                Err(constraint) => errors.push(TypeError::NoImpl {
+                    span,
+                    name: def.name.clone(),
+                    constraint: constraint.clone(),
+                }),
+            }
+        });
+
+        errors
+    }
+
+    fn infer_definition(
+        &self,
+        infer: &mut Infer,
+        group: &Group,
+        def: &Definition,
+        expected: &Type,
+    ) -> Inferred {
+        // the value of the `handle` around `main` the handlers resume in
+        let answer = def.resumes.then(|| infer.fresh());
+
+        def.equations.iter().enumerate().fold(
+            Inferred::default(),
+            |mut inferred, (equation, (abstruction, where_clause, span))| {
+                let mut cx = Context {
+                    env: self,
+                    infer: &mut *infer,
+                    group,
+                    wanted: Vec::new(),
+                    errors: Vec::new(),
+                    references: abstruction
+                        .expr
+                        .references()
+                        .into_iter()
+                        .chain(where_clause.references())
+                        .enumerate()
+                        .map(|(index, expr)| (expr as *const Expr, index))
+                        .collect(),
+                    sites: Vec::new(),
+                    name: def.name.clone(),
+                    span: *span,
+                };
+                match &answer {
+                    Some(answer) => cx.clause(abstruction, where_clause, true, expected, answer),
+                    None => {
+                        let ty = cx.abstruction(abstruction, where_clause);
+                        cx.equation(expected, &ty);
+                    }
+                }
+
+                inferred.wanted.extend(cx.wanted);
+                inferred.errors.extend(cx.errors);
+                inferred
+                    .sites
+                    .extend(cx.sites.into_iter().map(|site| (equation, site)));
+
+                inferred
+            },
+        )
+    }
+}
+
+impl<'a> Definition<'a> {
+    fn function(ident: &Ident, equations: Vec<Equation<'a>>) -> Self {
+        Self {
+            name: ident.0.clone(),
+            owner: Owner::Function(ident.clone()),
+            equations,
+            resumes: false,
+        }
+    }
+
+    fn handler(ident: &HandlerIdent, equations: Vec<Equation<'a>>) -> Self {
+        let resume = "resume".into();
+        let resumes = equations.iter().any(|(abstruction, where_clause, _)| {
+            abstruction.expr.mentions(&resume) || where_clause.mentions(&resume)
+        });
+
+        Self {
+            name: ident.0.clone(),
+            owner: Owner::Handler(ident.clone()),
+            equations,
+            resumes,
+        }
+    }
+
+    fn method(key: &ImplKey, ident: &Ident, equations: Vec<Equation<'a>>) -> Self {
+        Self {
+            name: format!("{ident} of impl {key}"),
+            owner: Owner::Method(key.clone(), ident.clone()),
+            equations,
+            resumes: false,
+        }
+    }
+}
+
+/// Orders definitions as they appear in the source, so that inference does
+/// not depend on the hash order.
+fn sorted<'a, T>(defs: impl Iterator<Item = (T, Definition<'a>)>) -> Vec<(T, Definition<'a>)> {
+    let mut defs = defs.collect::<Vec<_>>();
+    defs.sort_by_key(|(_, def)| def.equations.first().map(|(_, _, span)| span.start));
+
+    defs
+}
+
+/// The names of the prelude that the intrinsics refer to, `Ordering` of
+/// `@compare`. The prelude keeps them when another module defines them as
+/// well.
+pub(crate) const PRELUDE_NAMES: [&str; 4] = ["Ordering", "LT", "EQ", "GT"];
+
+/// Types of the functions the interpreter provides, unless the program defines
+/// them. Outside of a handler, `resume` resumes nothing and returns what it is
+/// given, `resume (eff *write) "hi"` is `eff *write "hi"`. `await` returns the
+/// value of a computation, which has run as arguments are evaluated first.
+pub(crate) fn builtins() -> Vec<(Ident, Scheme)> {
+    let id = || Scheme::poly(&["a"], Type::arrow(param("a"), param("a")));
+
+    vec![("resume".into(), id()), ("await".into(), id())]
+}
+
+/// Types of the intrinsics, unless the program declares them.
+pub(crate) fn intrinsics() -> Vec<(InstIdent, Scheme)> {
+    let io = |ty| Type::Con("IO".to_string(), vec![ty]);
+
+    vec![
+        (InstIdent("@atomic".to_string()), Scheme::mono(Type::unit())),
+        (
+            InstIdent("@write".to_string()),
+            Scheme {
+                params: vec!["a".to_string()],
+                constraints: vec![Constraint {
+                    ident: TraitIdent("Show".to_string()),
+                    args: vec![Type::Param("a".to_string())],
+                }],
+                ty: Type::arrow(Type::Param("a".to_string()), io(Type::unit())),
+            },
+        ),
+        (
+            InstIdent("@show".to_string()),
+            Scheme {
+                params: vec!["a".to_string()],
+                constraints: vec![Constraint {
+                    ident: TraitIdent("Show".to_string()),
+                    args: vec![Type::Param("a".to_string())],
+                }],
+                ty: Type::arrow(Type::Param("a".to_string()), Type::text()),
+            },
+        ),
+        (
+            InstIdent("@read".to_string()),
+            Scheme::mono(Type::arrow(Type::unit(), io(Type::con("Char")))),
+        ),
+    ]
+    .into_iter()
+    // of `Int`, `Integer`, `Float` and `Rational`, the impls of `Num` pick them
+    .chain(["@add", "@sub", "@mul", "@div", "@mod"].map(|ident| {
+        (
+            InstIdent(ident.to_string()),
+            Scheme::poly(&["a"], binary(param("a"), param("a"))),
+        )
+    }))
+    .chain(
+        [
+            ("@toInt", "Int"),
+            ("@toInteger", "Integer"),
+            ("@toFloat", "Float"),
+            ("@toRational", "Rational"),
+            ("@floor", "Integer"),
+        ]
+        .map(|(ident, ty)| {
+            (
+                InstIdent(ident.to_string()),
+                Scheme::poly(&["a"], Type::arrow(param("a"), Type::con(ty))),
+            )
+        }),
+    )
+    .chain([
+        (
+            InstIdent("@compare".to_string()),
+            Scheme::poly(&["a"], binary(param("a"), Type::con("Ordering"))),
+        ),
+        (
+            InstIdent("@cons".to_string()),
+            Scheme::poly(
+                &["a"],
+                Type::arrow(param("a"), Type::arrow(list("a"), list("a"))),
+            ),
+        ),
+        (
+            InstIdent("@append".to_string()),
+            Scheme::poly(&["a"], binary(list("a"), list("a"))),
+        ),
+        (
+            InstIdent("@ord".to_string()),
+            Scheme::mono(Type::arrow(Type::con("Char"), Type::con("Int"))),
+        ),
+        (
+            InstIdent("@chr".to_string()),
+            Scheme::mono(Type::arrow(Type::con("Int"), Type::con("Char"))),
+        ),
+    ])
+    .collect()
+}
+
+/// `ty -> ty -> ret`
+fn binary(ty: Type, ret: Type) -> Type {
+    Type::arrow(ty.clone(), Type::arrow(ty, ret))
+}
+
+fn param(ident: &str) -> Type {
+    Type::Param(ident.to_string())
+}
+
+fn list(ident: &str) -> Type {
+    Type::List(Box::new(param(ident)))
+}
+
+/// `name :: <effects> scheme`, one definition per line.
+impl Display for TypeEnv {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let line = |node: Node, scheme: &Scheme| format!("{node} :: {}", self.typed(&node, scheme));
+        let mut lines = self
+            .functions
+            .iter()
+            .map(|(ident, scheme)| line(Node::Function(ident.clone()), scheme))
+            .chain(
+                self.handlers
+                    .iter()
+                    .map(|(ident, scheme)| line(Node::Handler(ident.clone()), scheme)),
+            )
+            .collect::<Vec<_>>();
+        lines.sort();
+
+        lines.iter().try_for_each(|line| writeln!(f, "{line}"))
+    }
+}
@@ -0,0 +1,176 @@
+use super::types::{Constraint, Scheme, Type};
+use crate::ir::{
+    DataConstructor, DataDef, DataExpr, DataTerm, ExistsIdent, TraitConstraint, TypeAbstruction,
+    TypeAbstructionEnv, TypeIdent, TypeValue,
+};
+
+impl From<&TypeAbstruction> for Type {
+    fn from(value: &TypeAbstruction) -> Self {
+        match value {
+            TypeAbstruction::Arrow(lhs, rhs) => {
+                Type::arrow(lhs.as_ref().into(), rhs.as_ref().into())
+            }
+            TypeAbstruction::Term(value) => value.into(),
+        }
+    }
+}
+
+impl From<&TypeValue> for Type {
+    fn from(value: &TypeValue) -> Self {
+        match value {
+            TypeValue::Top | TypeValue::Unknown => Type::Top,
+            TypeValue::Bottom => Type::Bottom,
+            TypeValue::Array(elem) => Type::List(Box::new(elem.as_ref().into())),
+            TypeValue::Constructor(constructor) => constructor.into(),
+            TypeValue::Tuple(_, items) => Type::Tuple(items.iter().map(Type::from).collect()),
+            TypeValue::Ident(ident) => constructor_type(ident, Vec::new()),
+            TypeValue::Coroutine(coroutine) => Type::Coroutine(
+                Box::new((&coroutine.resume).into()),
+                Box::new((&coroutine.ret).into()),
+            ),
+        }
+    }
+}
+
+impl From<&DataConstructor> for Type {
+    fn from(value: &DataConstructor) -> Self {
+        let DataConstructor(_, ident, args) = value;
+
+        constructor_type(ident, args.iter().map(Type::from).collect())
+    }
+}
+
+impl From<&DataTerm> for Type {
+    fn from(value: &DataTerm) -> Self {
+        match value {
+            DataTerm::Top | DataTerm::Unknown => Type::Top,
+            DataTerm::Bottom => Type::Bottom,
+            DataTerm::Unit => Type::unit(),
+            DataTerm::Parameter(ident) => Type::Param(ident.0.clone()),
+            DataTerm::Ident(ident) => {
+                constructor_type(&TypeIdent::ExistsIdent(ident.clone()), Vec::new())
+            }
+            DataTerm::Tuple(_, items) => Type::Tuple(items.iter().map(Type::from).collect()),
+            DataTerm::Array(elem) => Type::List(Box::new(elem.as_ref().into())),
+            DataTerm::Abstruction(abstruction) => abstruction.as_ref().into(),
+            DataTerm::Constructor(constructor) => constructor.into(),
+            DataTerm::Coroutine(coroutine) => Type::Coroutine(
+                Box::new((&coroutine.resume).into()),
+                Box::new((&coroutine.ret).into()),
+            ),
+        }
+    }
+}
+
+pub(super) fn constructor_type(ident: &TypeIdent, args: Vec<Type>) -> Type {
+    match ident {
+        // higher-kinded parameters are not supported, `f a` is `f`
+        TypeIdent::ForallIdent(ident) => Type::Param(ident.0.clone()),
+        TypeIdent::ExistsIdent(ident) if ident.0 == "Text" && args.is_empty() => Type::text(),
+        TypeIdent::ExistsIdent(ident) => Type::Con(ident.0.clone(), args),
+    }
+}
+
+impl From<&TypeAbstructionEnv> for Scheme {
+    fn from(value: &TypeAbstructionEnv) -> Self {
+        let ty = Type::from(&value.abstruction);
+
+        scheme(ty, &value.trait_constraints)
+    }
+}
+
+/// The type of a handler is the type of its resume, `*write :: <Text -> IO (),
+/// IO ()>` is applied as `Text -> IO ()`.
+pub(super) fn handler_scheme(value: &TypeAbstructionEnv) -> Scheme {
+    match Scheme::from(value) {
+        Scheme {
+            params,
+            constraints,
+            ty: Type::Coroutine(resume, _),
+        } => Scheme {
+            params,
+            constraints,
+            ty: *resume,
+        },
+        scheme => scheme,
+    }
+}
+
+fn scheme(ty: Type, constraints: &[TraitConstraint]) -> Scheme {
+    let mut params = Vec::new();
+    collect_params(&ty, &mut params);
+
+    Scheme {
+        params,
+        constraints: constraints.iter().map(Constraint::from).collect(),
+        ty,
+    }
+}
+
+impl From<&TraitConstraint> for Constraint {
+    fn from(value: &TraitConstraint) -> Self {
+        Constraint {
+            ident: value.ident.clone(),
+            args: value
+                .args
+                .iter()
+                .map(|arg| Type::Param(arg.0.clone()))
+                .collect(),
+        }
+    }
+}
+
+fn collect_params(ty: &Type, params: &mut Vec<String>) {
+    match ty {
+        Type::Param(param) if !params.contains(param) => params.push(param.clone()),
+        Type::Var(_) | Type::Param(_) | Type::Top | Type::Bottom => {}
+        Type::Con(_, args) | Type::Tuple(args) => {
+            args.iter().for_each(|arg| collect_params(arg, params))
+        }
+        Type::Arrow(lhs, rhs) | Type::Coroutine(lhs, rhs) => {
+            collect_params(lhs, params);
+            collect_params(rhs, params);
+        }
+        Type::List(elem) => collect_params(elem, params),
+    }
+}
+
+/// Types of the constructors a data definition introduces.
+///
+/// `data Option a = nominal Some a | nominal Nil` gives `Some :: a -> Option a`
+/// and `Nil :: Option a`. A `nominal data True = ()` on its own is a value of
+/// its own type, `True :: True`.
+pub(super) fn constructor_schemes(ident: &TypeIdent, def: &DataDef) -> Vec<(ExistsIdent, Scheme)> {
+    let ret = match ident {
+        TypeIdent::ExistsIdent(ident) => Type::Con(
+            ident.0.clone(),
+            def.args()
+                .iter()
+                .map(|arg| constructor_type(arg, Vec::new()))
+                .collect(),
+        ),
+        TypeIdent::ForallIdent(_) => return Vec::new(),
+    };
+
+    match (ident, def.expr()) {
+        (TypeIdent::ExistsIdent(ident), DataExpr::Term(DataTerm::Unit)) => {
+            vec![(ident.clone(), scheme(ret, &[]))]
+        }
+        (_, expr) => expr
+            .constructors()
+            .into_iter()
+            .map(|DataConstructor(_, ident, args)| {
+                let ty = args
+                    .iter()
+                    .rev()
+                    .fold(ret.clone(), |acc, arg| Type::arrow(arg.into(), acc));
+                let ident = match ident {
+                    TypeIdent::ExistsIdent(ident) => ident.clone(),
+                    TypeIdent::ForallIdent(_) => unreachable!(),
+                };
+
+                (ident, scheme(ty, &[]))
+            })
+            .collect(),
+    }
+}
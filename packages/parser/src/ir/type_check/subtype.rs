@@ -0,0 +1,185 @@
+use super::{error::TypeError, expr::Context, infer::Mismatch, types::Type, TypeEnv};
+use crate::ir::{
+    ir2::IR2,
+    lattice::{DataElem, JoinSemiLattice, MeetSemiLattice, SemiLattice},
+    DataConstructor, ExistsIdent, TypeIdent,
+};
+
+impl TypeEnv {
+    /// Puts the members of each union below it, in source order. A data type
+    /// already in a union cannot be a member of another.
+    pub(super) fn declare_lattice(&mut self, ir: &IR2) -> Vec<TypeError> {
+        let mut unions = ir
+            .data_symbols
+            .iter()
+            .filter_map(|(ident, def)| match ident {
+                TypeIdent::ExistsIdent(ident) => Some((ident, def)),
+                TypeIdent::ForallIdent(_) => None,
+            })
+            .collect::<Vec<_>>();
+        unions.sort_by_key(|(_, def)| def.span().start);
+
+        unions
+            .into_iter()
+            .flat_map(|(union, def)| {
+                def.expr().constructors().into_iter().filter_map(
+                    move |DataConstructor(_, member, _)| match member {
+                        TypeIdent::ExistsIdent(member) => Some((union, def.span(), member)),
+                        TypeIdent::ForallIdent(_) => None,
+                    },
+                )
+            })
+            .filter_map(|(union, span, member)| {
+                let first = self.lattice.insert(member.clone(), union.clone()).err()?;
+
+                Some(TypeError::AmbiguousMember {
+                    span,
+                    member: member.0.clone(),
+                    first: first.0,
+                    second: union.0.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+impl Context<'_> {
+    /// `found` stands where `expected` is wanted if it is below it, `True`
+    /// where `Bool` is. Otherwise the two unify.
+    pub(super) fn subsume(&mut self, expected: &Type, found: &Type) {
+        if self.subsume_at(expected, found).is_err() {
+            self.mismatch(expected, found);
+        }
+    }
+
+    /// Two types meet at their join, `[True, Unknown]` is a list of the union
+    /// of both.
+    pub(super) fn join(&mut self, lhs: &Type, rhs: &Type) -> Type {
+        match self.join_at(lhs, rhs) {
+            Ok(ty) => ty,
+            Err(_) => {
+                self.mismatch(lhs, rhs);
+
+                self.infer.resolve(lhs)
+            }
+        }
+    }
+
+    /// Checks an equation against the type of its definition. Each equation
+    /// matches part of the arguments, so they may be narrower than the
+    /// signature, and without one the definition widens to the join of its
+    /// equations, arguments included.
+    pub(super) fn equation(&mut self, expected: &Type, found: &Type) {
+        let result = match (expected, self.infer.resolve(expected)) {
+            (_, Type::Var(_)) => self.infer.unify(expected, found),
+            (Type::Var(var), widened) => self
+                .join_equations(&widened, found)
+                .map(|joined| self.infer.bind(*var, joined)),
+            _ => self.subsume_equation(expected, found),
+        };
+
+        if result.is_err() {
+            self.mismatch(expected, found);
+        }
+    }
+
+    fn mismatch(&mut self, expected: &Type, found: &Type) {
+        self.errors.push(TypeError::Mismatch {
+            span: self.span,
+            name: self.name.clone(),
+            expected: self.infer.resolve(expected),
+            found: self.infer.resolve(found),
+        });
+    }
+
+    fn subsume_at(&mut self, expected: &Type, found: &Type) -> Result<(), Mismatch> {
+        match (self.infer.resolve(expected), self.infer.resolve(found)) {
+            (Type::Con(expected, expected_args), Type::Con(found, found_args))
+                if expected != found && expected_args.is_empty() && found_args.is_empty() =>
+            {
+                match self.env.lattice.le(&data(&found), &data(&expected)) {
+                    true => Ok(()),
+                    false => Err(Mismatch),
+                }
+            }
+            (Type::Arrow(expected_arg, expected_ret), Type::Arrow(found_arg, found_ret)) => {
+                self.subsume_at(&found_arg, &expected_arg)?;
+                self.subsume_at(&expected_ret, &found_ret)
+            }
+            (Type::Tuple(expected), Type::Tuple(found)) if expected.len() == found.len() => {
+                expected
+                    .iter()
+                    .zip(&found)
+                    .try_for_each(|(expected, found)| self.subsume_at(expected, found))
+            }
+            (Type::List(expected), Type::List(found)) => self.subsume_at(&expected, &found),
+            (expected, found) => self.infer.unify(&expected, &found),
+        }
+    }
+
+    fn subsume_equation(&mut self, expected: &Type, found: &Type) -> Result<(), Mismatch> {
+        match (self.infer.resolve(expected), self.infer.resolve(found)) {
+            (Type::Arrow(expected_arg, expected_ret), Type::Arrow(found_arg, found_ret)) => {
+                self.subsume_at(&expected_arg, &found_arg)?;
+                self.subsume_equation(&expected_ret, &found_ret)
+            }
+            (expected, found) => self.subsume_at(&expected, &found),
+        }
+    }
+
+    fn join_equations(&mut self, lhs: &Type, rhs: &Type) -> Result<Type, Mismatch> {
+        match (self.infer.resolve(lhs), self.infer.resolve(rhs)) {
+            (Type::Arrow(lhs_arg, lhs_ret), Type::Arrow(rhs_arg, rhs_ret)) => Ok(Type::arrow(
+                self.join_at(&lhs_arg, &rhs_arg)?,
+                self.join_equations(&lhs_ret, &rhs_ret)?,
+            )),
+            (lhs, rhs) => self.join_at(&lhs, &rhs),
+        }
+    }
+
+    fn join_at(&mut self, lhs: &Type, rhs: &Type) -> Result<Type, Mismatch> {
+        self.bound_at(lhs, rhs, true)
+    }
+
+    /// The join of two types, or their meet when not `join`. Arguments of
+    /// functions go the other way.
+    fn bound_at(&mut self, lhs: &Type, rhs: &Type, join: bool) -> Result<Type, Mismatch> {
+        match (self.infer.resolve(lhs), self.infer.resolve(rhs)) {
+            (Type::Con(lhs, lhs_args), Type::Con(rhs, rhs_args))
+                if lhs != rhs && lhs_args.is_empty() && rhs_args.is_empty() =>
+            {
+                let bound = match join {
+                    true => self.env.lattice.join(&data(&lhs), &data(&rhs)),
+                    false => self.env.lattice.meet(&data(&lhs), &data(&rhs)),
+                };
+
+                match bound {
+                    DataElem::Data(ident) => Ok(Type::Con(ident.0, Vec::new())),
+                    DataElem::Top | DataElem::Bottom => Err(Mismatch),
+                }
+            }
+            (Type::Arrow(lhs_arg, lhs_ret), Type::Arrow(rhs_arg, rhs_ret)) => Ok(Type::arrow(
+                self.bound_at(&lhs_arg, &rhs_arg, !join)?,
+                self.bound_at(&lhs_ret, &rhs_ret, join)?,
+            )),
+            (Type::Tuple(lhs), Type::Tuple(rhs)) if lhs.len() == rhs.len() => lhs
+                .iter()
+                .zip(&rhs)
+                .map(|(lhs, rhs)| self.bound_at(lhs, rhs, join))
+                .collect::<Result<_, _>>()
+                .map(Type::Tuple),
+            (Type::List(lhs), Type::List(rhs)) => {
+                Ok(Type::List(Box::new(self.bound_at(&lhs, &rhs, join)?)))
+            }
+            (lhs, rhs) => {
+                self.infer.unify(&lhs, &rhs)?;
+
+                Ok(self.infer.resolve(&lhs))
+            }
+        }
+    }
+}
+
+fn data(ident: &str) -> DataElem {
+    DataElem::Data(ExistsIdent(ident.to_string()))
+}
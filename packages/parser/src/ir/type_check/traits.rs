@@ -0,0 +1,562 @@
+use super::{
+    error::TypeError,
+    signature::constructor_type,
+    types::{Constraint, Scheme, Type},
+    TypeEnv,
+};
+use crate::ir::{ir2::IR2, ExistsIdent, HandlerIdent, Ident, ImplDef, TraitIdent, TypeIdent};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+/// Evidence that a constraint holds, passed to the definitions that need it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Dict {
+    /// The impl for the heads of the constraint, applied to the dictionaries of
+    /// the constraints of the impl.
+    Impl(ImplKey, Vec<Dict>),
+    /// A dictionary parameter of the definition, in the order of its
+    /// constraints.
+    Param(usize),
+    /// The dictionary of a superclass, in the order the trait declares them.
+    Super(Box<Dict>, usize),
+}
+
+/// `impl Show Bool` is keyed by `Show` and the type constructor `Bool`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ImplKey {
+    pub ident: TraitIdent,
+    pub heads: Vec<String>,
+}
+
+/// The definition a reference is in.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Owner {
+    Function(Ident),
+    Handler(HandlerIdent),
+    Method(ImplKey, Ident),
+}
+
+/// A reference to a function that takes dictionaries, by the equation it is
+/// in and its position in `Abstruction::references`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Site {
+    pub owner: Owner,
+    pub equation: usize,
+    pub reference: usize,
+}
+
+#[derive(Debug)]
+pub(super) struct Trait {
+    params: Vec<String>,
+    superclasses: Vec<Constraint>,
+    methods: Vec<Ident>,
+}
+
+#[derive(Debug)]
+pub struct Impl {
+    /// The constraints of the impl, the first dictionaries of its methods.
+    pub constraints: Vec<Constraint>,
+    /// The dictionaries of the superclasses of the trait.
+    pub supers: Vec<Dict>,
+    args: Vec<Type>,
+}
+
+/// Impls the interpreter provides, unless the program declares them, for each
+/// `Show` there is.
+fn builtin_impls<'a>(traits: impl Iterator<Item = &'a TraitIdent>) -> Vec<(ImplKey, Impl)> {
+    let shows = traits
+        .filter(|ident| is_show(ident))
+        .cloned()
+        .chain([TraitIdent("Show".to_string())])
+        .collect::<HashSet<_>>();
+
+    shows
+        .into_iter()
+        .flat_map(|ident| {
+            [
+                Type::con("Int"),
+                Type::con("Integer"),
+                Type::con("Float"),
+                Type::con("Rational"),
+                Type::con("Char"),
+                Type::text(),
+            ]
+            .into_iter()
+            .map(move |ty| {
+                let key = ImplKey {
+                    ident: ident.clone(),
+                    heads: vec![head(&ty).unwrap()],
+                };
+
+                (
+                    key,
+                    Impl {
+                        constraints: Vec::new(),
+                        supers: Vec::new(),
+                        args: vec![ty],
+                    },
+                )
+            })
+        })
+        .collect()
+}
+
+/// Whether `ident` is the `Show` of the intrinsics. A module that defines its
+/// own `Show` where another one does as well has it as `Show'prelude`, and
+/// the intrinsics show any of them.
+pub(crate) fn is_show(ident: &TraitIdent) -> bool {
+    ident.0.split('\'').next() == Some("Show")
+}
+
+impl TypeEnv {
+    pub fn method(&self, ident: &Ident) -> Option<&TraitIdent> {
+        self.methods.get(ident)
+    }
+
+    pub fn impl_of(&self, key: &ImplKey) -> Option<&Impl> {
+        self.impls.get(key)
+    }
+
+    pub fn dictionaries(&self, site: &Site) -> Option<&[Dict]> {
+        self.dictionaries.get(site).map(Vec::as_slice)
+    }
+
+    /// The number of dictionaries the equations of `owner` take before their
+    /// arguments.
+    pub fn dictionary_params(&self, owner: &Owner) -> usize {
+        let constraints = |ident| {
+            self.functions
+                .get(ident)
+                .map_or(0, |scheme| scheme.constraints.len())
+        };
+
+        match owner {
+            Owner::Function(ident) => constraints(ident),
+            Owner::Handler(_) => 0,
+            Owner::Method(key, ident) => {
+                self.impls.get(key).map_or(0, |imp| imp.constraints.len())
+                    + constraints(ident).saturating_sub(1)
+            }
+        }
+    }
+
+    /// Methods are functions constrained by their trait, `show :: Show a => a
+    /// -> Text`.
+    pub(super) fn declare_traits(&mut self, ir: &IR2) {
+        ir.trait_symbols.iter().for_each(|(ident, def)| {
+            let params = def
+                .params
+                .iter()
+                .map(|param| param.0.clone())
+                .collect::<Vec<_>>();
+            let constraint = Constraint {
+                ident: ident.clone(),
+                args: params.iter().cloned().map(Type::Param).collect(),
+            };
+
+            def.methods.iter().for_each(|(method, sig)| {
+                let Scheme {
+                    params: method_params,
+                    constraints,
+                    ty,
+                } = Scheme::from(sig);
+                let method_params = params
+                    .iter()
+                    .filter(|param| !method_params.contains(param))
+                    .cloned()
+                    .chain(method_params.iter().cloned())
+                    .collect();
+
+                self.functions.insert(
+                    method.clone(),
+                    Scheme {
+                        params: method_params,
+                        constraints: std::iter::once(constraint.clone())
+                            .chain(constraints)
+                            .collect(),
+                        ty,
+                    },
+                );
+                self.methods.insert(method.clone(), ident.clone());
+            });
+
+            self.traits.insert(
+                ident.clone(),
+                Trait {
+                    params,
+                    superclasses: def.superclasses.iter().map(Constraint::from).collect(),
+                    methods: def
+                        .methods
+                        .iter()
+                        .map(|(method, _)| method.clone())
+                        .collect(),
+                },
+            );
+        });
+    }
+
+    /// Declares the impls in source order, an impl for the same heads as an
+    /// earlier one overlaps it. The superclasses of a trait must have impls for
+    /// the same heads.
+    pub(super) fn declare_impls(&mut self, ir: &IR2) -> Vec<TypeError> {
+        self.impls.extend(builtin_impls(self.traits.keys()));
+
+        let mut declared = Vec::new();
+        let mut errors = ir
+            .impls
+            .iter()
+            .filter_map(|def| {
+                let (key, imp) = match self.impl_head(ir, def) {
+                    Ok(imp) => imp,
+                    Err(ident) => {
+                        return Some(TypeError::Undefined {
+                            span: def.span,
+                            name: format!("impl {}", def.ident.0),
+                            ident,
+                        })
+                    }
+                };
+                if declared.iter().any(|(declared, _)| declared == &key) {
+                    return Some(TypeError::OverlappingImpl {
+                        span: def.span,
+                        key,
+                    });
+                }
+
+                declared.push((key.clone(), def.span));
+                self.impls.insert(key, imp);
+
+                None
+            })
+            .collect::<Vec<_>>();
+
+        let supers = declared
+            .iter()
+            .map(|(key, span)| {
+                let imp = &self.impls[key];
+                let superclasses = self.superclasses(&Constraint {
+                    ident: key.ident.clone(),
+                    args: imp.args.clone(),
+                });
+                let (supers, missing): (Vec<_>, Vec<_>) = superclasses
+                    .into_iter()
+                    .map(|superclass| {
+                        self.resolve(&superclass, &imp.constraints)
+                            .ok_or(superclass)
+                    })
+                    .partition(Result::is_ok);
+                errors.extend(missing.into_iter().map(|superclass| TypeError::NoImpl {
+                    span: *span,
+                    name: format!("impl {key}"),
+                    constraint: superclass.unwrap_err(),
+                }));
+
+                (
+                    key.clone(),
+                    supers.into_iter().map(Result::unwrap).collect(),
+                )
+            })
+            .collect::<Vec<_>>();
+        supers.into_iter().for_each(|(key, supers)| {
+            self.impls.get_mut(&key).unwrap().supers = supers;
+        });
+
+        errors
+    }
+
+    /// `impl Show Option` is for `Option a`, with the parameters of the data
+    /// definition. Fails with what is undefined.
+    fn impl_head(&self, ir: &IR2, def: &ImplDef) -> Result<(ImplKey, Impl), String> {
+        let params = match self.traits.get(&def.ident) {
+            Some(tr) => &tr.params,
+            None => return Err(def.ident.0.clone()),
+        };
+        if params.len() != def.args.len() {
+            return Err(format!("{} with {} arguments", def.ident.0, def.args.len()));
+        }
+        let key = ImplKey::of(def).ok_or("an impl for a type variable")?;
+
+        let args = key
+            .heads
+            .iter()
+            .map(|head| match head.as_str() {
+                "Int" | "Integer" | "Float" | "Rational" | "Char" | "Text" => Ok(constructor_type(
+                    &TypeIdent::ExistsIdent(ExistsIdent(head.clone())),
+                    Vec::new(),
+                )),
+                _ => {
+                    let ident = TypeIdent::ExistsIdent(ExistsIdent(head.clone()));
+                    match ir.data_symbols.find(&ident) {
+                        Some(data) => Ok(Type::Con(
+                            head.clone(),
+                            data.args()
+                                .iter()
+                                .map(|arg| constructor_type(arg, Vec::new()))
+                                .collect(),
+                        )),
+                        None => Err(head.clone()),
+                    }
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok((
+            key,
+            Impl {
+                constraints: def.constraints.iter().map(Constraint::from).collect(),
+                supers: Vec::new(),
+                args,
+            },
+        ))
+    }
+
+    /// The type of a method in an impl and the constraints its equations may
+    /// rely on, those of the impl and those of the method.
+    pub(super) fn impl_method(
+        &self,
+        key: &ImplKey,
+        method: &Ident,
+    ) -> Option<(Type, Vec<Constraint>)> {
+        let tr = self.traits.get(&key.ident)?;
+        let imp = self.impls.get(key)?;
+        let scheme = self.functions.get(method)?;
+        let subst = tr
+            .params
+            .iter()
+            .cloned()
+            .zip(imp.args.iter().cloned())
+            .collect::<HashMap<_, _>>();
+        let substitute = |ty: &Type| match ty {
+            Type::Param(param) => subst.get(param).cloned(),
+            _ => None,
+        };
+
+        let given = imp
+            .constraints
+            .iter()
+            .cloned()
+            .chain(
+                scheme
+                    .constraints
+                    .iter()
+                    .skip(1)
+                    .map(|constraint| constraint.map(&substitute)),
+            )
+            .collect();
+
+        Some((scheme.ty.map(&substitute), given))
+    }
+
+    /// Methods of the trait the impl does not define, and definitions that are
+    /// not methods of the trait.
+    pub(super) fn check_impl_methods(&self, def: &ImplDef, key: &ImplKey) -> Vec<TypeError> {
+        let tr = match self.traits.get(&key.ident) {
+            Some(tr) => tr,
+            None => return Vec::new(),
+        };
+        let missing = tr
+            .methods
+            .iter()
+            .filter(|method| !def.methods.iter().any(|f| &f.ident == *method))
+            .map(|method| TypeError::MissingMethod {
+                span: def.span,
+                key: key.clone(),
+                method: method.clone(),
+            });
+        let unknown = def
+            .methods
+            .iter()
+            .filter(|f| !tr.methods.contains(&f.ident))
+            .map(|f| TypeError::Undefined {
+                span: f.span,
+                name: format!("impl {key}"),
+                ident: format!("method {}", f.ident),
+            });
+
+        missing.chain(unknown).collect()
+    }
+
+    /// Finds the dictionary for `constraint`, from the constraints `given` to
+    /// the definition or from an impl.
+    pub(super) fn resolve(&self, constraint: &Constraint, given: &[Constraint]) -> Option<Dict> {
+        if let Some(dict) = given.iter().enumerate().find_map(|(idx, have)| {
+            self.entail(have, Dict::Param(idx), constraint, self.traits.len())
+        }) {
+            return Some(dict);
+        }
+
+        let key = ImplKey {
+            ident: constraint.ident.clone(),
+            heads: constraint
+                .args
+                .iter()
+                .map(head)
+                .collect::<Option<Vec<_>>>()?,
+        };
+        let imp = self.impls.get(&key)?;
+        let mut subst = HashMap::new();
+        if !imp
+            .args
+            .iter()
+            .zip(&constraint.args)
+            .all(|(pattern, ty)| matches(pattern, ty, &mut subst))
+        {
+            return None;
+        }
+        let args = imp
+            .constraints
+            .iter()
+            .map(|constraint| {
+                let constraint = constraint.map(&|ty| match ty {
+                    Type::Param(param) => subst.get(param).cloned(),
+                    _ => None,
+                });
+
+                self.resolve(&constraint, given)
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(Dict::Impl(key, args))
+    }
+
+    /// `Ord a` gives `Eq a` and `PartialEq a a`.
+    fn entail(
+        &self,
+        have: &Constraint,
+        dict: Dict,
+        want: &Constraint,
+        depth: usize,
+    ) -> Option<Dict> {
+        // the `Show` an intrinsic wants is any of them
+        let show = want.ident.0 == "Show" && is_show(&have.ident) && have.args == want.args;
+        if have == want || show {
+            return Some(dict);
+        }
+        if depth == 0 {
+            return None;
+        }
+
+        self.superclasses(have)
+            .iter()
+            .enumerate()
+            .find_map(|(idx, superclass)| {
+                let dict = Dict::Super(Box::new(dict.clone()), idx);
+
+                self.entail(superclass, dict, want, depth - 1)
+            })
+    }
+
+    fn superclasses(&self, constraint: &Constraint) -> Vec<Constraint> {
+        let tr = match self.traits.get(&constraint.ident) {
+            Some(tr) => tr,
+            None => return Vec::new(),
+        };
+        let subst = tr
+            .params
+            .iter()
+            .zip(&constraint.args)
+            .collect::<HashMap<_, _>>();
+
+        tr.superclasses
+            .iter()
+            .map(|superclass| {
+                superclass.map(&|ty| match ty {
+                    Type::Param(param) => subst.get(param).map(|ty| (*ty).clone()),
+                    _ => None,
+                })
+            })
+            .collect()
+    }
+}
+
+impl ImplKey {
+    /// `None` for an impl for a type variable.
+    pub fn of(def: &ImplDef) -> Option<Self> {
+        let heads = def
+            .args
+            .iter()
+            .map(|arg| match arg {
+                TypeIdent::ExistsIdent(ident) => Some(ident.0.clone()),
+                TypeIdent::ForallIdent(_) => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(ImplKey {
+            ident: def.ident.clone(),
+            heads,
+        })
+    }
+}
+
+/// The type constructor an impl is selected by.
+fn head(ty: &Type) -> Option<String> {
+    match ty {
+        Type::Con(ident, _) => Some(ident.clone()),
+        Type::List(elem) if elem.as_ref() == &Type::con("Char") => Some("Text".to_string()),
+        _ => None,
+    }
+}
+
+/// Binds the parameters of `pattern` so that it is `ty`.
+pub(super) fn matches(pattern: &Type, ty: &Type, subst: &mut HashMap<String, Type>) -> bool {
+    match (pattern, ty) {
+        (Type::Param(param), ty) => match subst.get(param) {
+            Some(bound) => bound == ty,
+            None => {
+                subst.insert(param.clone(), ty.clone());
+                true
+            }
+        },
+        (Type::Con(lhs, lhs_args), Type::Con(rhs, rhs_args)) => {
+            lhs == rhs
+                && lhs_args.len() == rhs_args.len()
+                && lhs_args
+                    .iter()
+                    .zip(rhs_args)
+                    .all(|(lhs, rhs)| matches(lhs, rhs, subst))
+        }
+        (Type::Tuple(lhs), Type::Tuple(rhs)) => {
+            lhs.len() == rhs.len()
+                && lhs
+                    .iter()
+                    .zip(rhs)
+                    .all(|(lhs, rhs)| matches(lhs, rhs, subst))
+        }
+        (Type::Arrow(lhs_arg, lhs_ret), Type::Arrow(rhs_arg, rhs_ret))
+        | (Type::Coroutine(lhs_arg, lhs_ret), Type::Coroutine(rhs_arg, rhs_ret)) => {
+            matches(lhs_arg, rhs_arg, subst) && matches(lhs_ret, rhs_ret, subst)
+        }
+        (Type::List(lhs), Type::List(rhs)) => matches(lhs, rhs, subst),
+        (lhs, rhs) => lhs == rhs,
+    }
+}
+
+/// The constraints of `scheme` where it is used at `ty`.
+pub(super) fn constraints_at(scheme: &Scheme, ty: &Type) -> Option<Vec<Constraint>> {
+    let mut subst = HashMap::new();
+    if !matches(&scheme.ty, ty, &mut subst) {
+        return None;
+    }
+
+    Some(
+        scheme
+            .constraints
+            .iter()
+            .map(|constraint| {
+                constraint.map(&|ty| match ty {
+                    Type::Param(param) => subst.get(param).cloned(),
+                    _ => None,
+                })
+            })
+            .collect(),
+    )
+}
+
+impl Display for ImplKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.ident.0)?;
+        self.heads.iter().try_for_each(|head| write!(f, " {head}"))
+    }
+}
@@ -0,0 +1,221 @@
+use crate::ir::TraitIdent;
+use std::fmt::Display;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Type {
+    /// Unification variable.
+    Var(usize),
+    /// Type parameter. Rigid while checking the definition that declares it,
+    /// replaced by fresh variables when a scheme is instantiated.
+    Param(String),
+    Con(String, Vec<Type>),
+    Arrow(Box<Type>, Box<Type>),
+    Tuple(Vec<Type>),
+    List(Box<Type>),
+    Coroutine(Box<Type>, Box<Type>),
+    Top,
+    /// `!`, unifies with anything.
+    Bottom,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constraint {
+    pub ident: TraitIdent,
+    pub args: Vec<Type>,
+}
+
+/// `forall params. constraints => ty`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scheme {
+    pub params: Vec<String>,
+    pub constraints: Vec<Constraint>,
+    pub ty: Type,
+}
+
+impl Type {
+    pub fn unit() -> Self {
+        Type::Tuple(Vec::new())
+    }
+
+    pub fn con(ident: &str) -> Self {
+        Type::Con(ident.to_string(), Vec::new())
+    }
+
+    /// `Text` is `[Char]`.
+    pub fn text() -> Self {
+        Type::List(Box::new(Type::con("Char")))
+    }
+
+    pub fn arrow(lhs: Type, rhs: Type) -> Self {
+        Type::Arrow(Box::new(lhs), Box::new(rhs))
+    }
+
+    pub fn vars(&self) -> Vec<usize> {
+        let mut vars = Vec::new();
+        self.collect_vars(&mut vars);
+
+        vars
+    }
+
+    fn collect_vars(&self, vars: &mut Vec<usize>) {
+        match self {
+            Type::Var(var) if !vars.contains(var) => vars.push(*var),
+            Type::Var(_) | Type::Param(_) | Type::Top | Type::Bottom => {}
+            Type::Con(_, args) | Type::Tuple(args) => {
+                args.iter().for_each(|arg| arg.collect_vars(vars))
+            }
+            Type::Arrow(lhs, rhs) | Type::Coroutine(lhs, rhs) => {
+                lhs.collect_vars(vars);
+                rhs.collect_vars(vars);
+            }
+            Type::List(elem) => elem.collect_vars(vars),
+        }
+    }
+
+    pub fn has_params(&self) -> bool {
+        match self {
+            Type::Param(_) => true,
+            Type::Var(_) | Type::Top | Type::Bottom => false,
+            Type::Con(_, args) | Type::Tuple(args) => args.iter().any(Type::has_params),
+            Type::Arrow(lhs, rhs) | Type::Coroutine(lhs, rhs) => {
+                lhs.has_params() || rhs.has_params()
+            }
+            Type::List(elem) => elem.has_params(),
+        }
+    }
+
+    /// Replaces variables and parameters.
+    pub fn map(&self, f: &impl Fn(&Type) -> Option<Type>) -> Type {
+        if let Some(ty) = f(self) {
+            return ty;
+        }
+
+        match self {
+            Type::Var(_) | Type::Param(_) | Type::Top | Type::Bottom => self.clone(),
+            Type::Con(ident, args) => {
+                Type::Con(ident.clone(), args.iter().map(|arg| arg.map(f)).collect())
+            }
+            Type::Arrow(lhs, rhs) => Type::arrow(lhs.map(f), rhs.map(f)),
+            Type::Tuple(items) => Type::Tuple(items.iter().map(|item| item.map(f)).collect()),
+            Type::List(elem) => Type::List(Box::new(elem.map(f))),
+            Type::Coroutine(resume, ret) => {
+                Type::Coroutine(Box::new(resume.map(f)), Box::new(ret.map(f)))
+            }
+        }
+    }
+
+    fn is_atom(&self) -> bool {
+        !matches!(self, Type::Arrow(_, _))
+            && !matches!(self, Type::Con(_, args) if !args.is_empty())
+    }
+}
+
+impl Constraint {
+    pub fn vars(&self) -> Vec<usize> {
+        let mut vars = Vec::new();
+        self.args.iter().for_each(|arg| arg.collect_vars(&mut vars));
+
+        vars
+    }
+
+    /// Whether the constraint is on a parameter of a signature.
+    pub fn has_params(&self) -> bool {
+        self.args.iter().any(Type::has_params)
+    }
+
+    pub fn map(&self, f: &impl Fn(&Type) -> Option<Type>) -> Constraint {
+        Constraint {
+            ident: self.ident.clone(),
+            args: self.args.iter().map(|arg| arg.map(f)).collect(),
+        }
+    }
+}
+
+impl Scheme {
+    pub fn mono(ty: Type) -> Self {
+        Self {
+            params: Vec::new(),
+            constraints: Vec::new(),
+            ty,
+        }
+    }
+
+    /// `forall params. ty`, without constraints.
+    pub fn poly(params: &[&str], ty: Type) -> Self {
+        Self {
+            params: params.iter().map(|param| param.to_string()).collect(),
+            constraints: Vec::new(),
+            ty,
+        }
+    }
+}
+
+struct Atom<'a>(&'a Type);
+
+impl Display for Atom<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0.is_atom() {
+            true => write!(f, "{}", self.0),
+            false => write!(f, "({})", self.0),
+        }
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Var(var) => write!(f, "t{var}"),
+            Type::Param(ident) => write!(f, "{ident}"),
+            Type::Con(ident, args) => {
+                write!(f, "{ident}")?;
+                args.iter().try_for_each(|arg| write!(f, " {}", Atom(arg)))
+            }
+            Type::Arrow(lhs, rhs) if matches!(lhs.as_ref(), Type::Arrow(_, _)) => {
+                write!(f, "({lhs}) -> {rhs}")
+            }
+            Type::Arrow(lhs, rhs) => write!(f, "{lhs} -> {rhs}"),
+            Type::Tuple(items) => write!(
+                f,
+                "({})",
+                items
+                    .iter()
+                    .map(|item| item.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            Type::List(elem) if elem.as_ref() == &Type::con("Char") => write!(f, "Text"),
+            Type::List(elem) => write!(f, "[{elem}]"),
+            Type::Coroutine(resume, ret) => write!(f, "<{resume}, {ret}>"),
+            Type::Top => write!(f, "_"),
+            Type::Bottom => write!(f, "!"),
+        }
+    }
+}
+
+impl Display for Constraint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.ident.0)?;
+        self.args
+            .iter()
+            .try_for_each(|arg| write!(f, " {}", Atom(arg)))
+    }
+}
+
+impl Display for Scheme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.constraints.as_slice() {
+            [] => write!(f, "{}", self.ty),
+            [constraint] => write!(f, "{constraint} => {}", self.ty),
+            constraints => write!(
+                f,
+                "({}) => {}",
+                constraints
+                    .iter()
+                    .map(|constraint| constraint.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                self.ty
+            ),
+        }
+    }
+}
@@ -4,8 +4,29 @@ pub mod ast;
 pub mod ir;
 mod prose_down;
 mod prose_down_script;
-// pub mod vm;
+pub mod vm;
 
 pub use self::prose_down::parse as prose_down_parse;
+pub use self::prose_down_script::analyze as prose_down_script_analyze;
+pub use self::prose_down_script::analyze_document as prose_down_script_analyze_document;
+pub use self::prose_down_script::document_tokens as prose_down_document_tokens;
+pub use self::prose_down_script::evaluate as prose_down_script_evaluate;
+pub use self::prose_down_script::format as prose_down_script_format;
+pub use self::prose_down_script::highlight as prose_down_script_highlight;
+pub use self::prose_down_script::imports as prose_down_script_imports;
 pub use self::prose_down_script::parse as prose_down_script_parse;
 pub use self::prose_down_script::run as prose_down_script_run;
+pub use self::prose_down_script::run_file as prose_down_script_run_file;
+pub use self::prose_down_script::run_file_with_io as prose_down_script_run_file_with_io;
+pub use self::prose_down_script::run_with_io as prose_down_script_run_with_io;
+pub use self::prose_down_script::tokens as prose_down_script_tokens;
+pub use self::prose_down_script::Analysis as ProseDownScriptAnalysis;
+pub use self::prose_down_script::Diagnostic as ProseDownScriptDiagnostic;
+pub use self::prose_down_script::Loader as ProseDownScriptLoader;
+pub use self::prose_down_script::Reply as ProseDownScriptReply;
+pub use self::prose_down_script::Section as ProseDownSection;
+pub use self::prose_down_script::Session as ProseDownScriptSession;
+pub use self::prose_down_script::Severity as ProseDownScriptSeverity;
+pub use self::prose_down_script::Symbol as ProseDownScriptSymbol;
+pub use self::prose_down_script::Token as ProseDownScriptToken;
+pub use self::prose_down_script::TokenKind as ProseDownScriptTokenKind;
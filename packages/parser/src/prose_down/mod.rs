@@ -1,3 +1,4 @@
 mod parser;
 
 pub use self::parser::parse;
+pub(crate) use self::parser::{parts, Part, Rule};
@@ -17,9 +17,57 @@ pub fn parse(input: &str) -> anyhow::Result<ast::Document> {
     Ok(ast::Document(blocks))
 }
 
+/// A part of a document an editor needs the place of.
+pub(crate) enum Part {
+    /// The code of a `@{...}` block.
+    Script(String),
+    /// The expression of a `{expr}` inline.
+    Expr(String),
+    /// The first line of a paragraph.
+    Paragraph(String),
+    ThemanticBreak,
+}
+
+/// The parts of a document, each at the 1-based `(line, column)` it starts at,
+/// in document order.
+pub(crate) fn parts(input: &str) -> anyhow::Result<Vec<((usize, usize), Part)>> {
+    let prose = ProseParser::parse(Rule::prose, input).context("parse error")?;
+    let parts = prose
+        .into_iter()
+        .flat_map(|pair| match pair.as_rule() {
+            Rule::pdsScript => pair
+                .into_inner()
+                .map(|body| (body.line_col(), Part::Script(body.as_str().to_string())))
+                .collect(),
+            Rule::paragraph => {
+                let first_line = pair.as_str().lines().next().unwrap_or_default();
+                let paragraph = (pair.line_col(), Part::Paragraph(first_line.to_string()));
+
+                [paragraph]
+                    .into_iter()
+                    .chain(
+                        pair.into_inner()
+                            .filter(|inline| inline.as_rule() == Rule::inlineExpr)
+                            .flat_map(Pair::into_inner)
+                            .map(|expr| (expr.line_col(), Part::Expr(expr.as_str().to_string()))),
+                    )
+                    .collect()
+            }
+            // the break starts with the line break before `---`
+            Rule::themanticBreak => vec![((pair.line_col().0 + 1, 1), Part::ThemanticBreak)],
+            _ => Vec::new(),
+        })
+        .collect();
+
+    Ok(parts)
+}
+
 fn block(pair: Pair<Rule>) -> ast::Block {
     match pair.as_rule() {
-        Rule::pdsScript => ast::Block::PdsScript(pair.into_inner().as_str().to_string()),
+        Rule::pdsScript => {
+            let body = pair.into_inner().next().unwrap();
+            ast::Block::PdsScript(body.as_str().to_string(), body.line_col())
+        }
         Rule::paragraph => ast::Block::Paragraph(pair.into_inner().map(inline).collect()),
         Rule::themanticBreak => ast::Block::ThemanticBreak,
         Rule::emptyLine => ast::Block::EmptyLine,
@@ -29,7 +77,10 @@ fn block(pair: Pair<Rule>) -> ast::Block {
 
 fn inline(pair: Pair<Rule>) -> ast::Inline {
     match pair.as_rule() {
-        Rule::inlineExpr => ast::Inline::Expr(pair.into_inner().as_str().to_string()),
+        Rule::inlineExpr => {
+            let expr = pair.into_inner().next().unwrap();
+            ast::Inline::Expr(expr.as_str().to_string(), expr.line_col())
+        }
         Rule::number => ast::Inline::Number(pair.into_inner().as_str().to_string()),
         Rule::text => ast::Inline::Text(pair.as_str().to_string()),
         _ => panic!("{}", pair),
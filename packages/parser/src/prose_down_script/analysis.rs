@@ -0,0 +1,569 @@
+use super::{
+    loader::Imported,
+    parser::{parse_unassociated, Rule},
+    rename::unqualified,
+    run::with_std,
+    Loader,
+};
+use crate::ast::{Module, Span, Statement};
+use crate::ir::{
+    ir1::transform1,
+    ir2::transform2,
+    matching::check_matches,
+    resolve::{definitions, Namespace},
+    type_check::{type_check, TypeEnv},
+    HandlerIdent, Ident,
+};
+use crate::prose_down::{self, Part};
+use pest::{error::LineColLocation, RuleType};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+/// Where the errors not about a statement of the file are.
+const START: Span = Span {
+    start: (1, 1),
+    end: (1, 1),
+};
+
+/// What the `{expr}` inlines of a document are checked as, `inline0` and so
+/// on.
+const INLINE: &str = "inline";
+
+/// What an editor shows of a script or a document.
+#[derive(Debug, Default)]
+pub struct Analysis {
+    pub diagnostics: Vec<Diagnostic>,
+    /// The names the file defines, in source order.
+    pub definitions: Vec<Symbol>,
+    /// The names the file imports, the standard library's in a document.
+    pub imports: Vec<Symbol>,
+    /// The sections of a document, at their first paragraphs.
+    pub sections: Vec<Section>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub span: Span,
+    pub severity: Severity,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub namespace: Namespace,
+    pub ident: String,
+    /// The module the name is defined in, `None` for the file analyzed.
+    pub path: Option<PathBuf>,
+    pub span: Span,
+    /// The type of a function, a constructor or a handler.
+    pub ty: Option<String>,
+}
+
+/// A part of a document after a thematic break, or before the first one.
+#[derive(Debug, Clone)]
+pub struct Section {
+    pub span: Span,
+    pub title: String,
+}
+
+/// Analyzes `source` as the script at `path`, which need not be saved. The
+/// modules it imports are loaded as they are.
+pub fn analyze(loader: &Loader, path: &Path, source: &str) -> Analysis {
+    let module = match parse_unassociated(source) {
+        Ok(module) => module,
+        Err(err) => return Analysis::failed(parse_error::<Rule>(&err)),
+    };
+
+    let definitions = defined(&module, |span| span);
+    let (linked, imported) = loader.load_overlay(path, source);
+    let (env, diagnostics) = match linked {
+        Ok(linked) => check(linked, &spans(&module), &names(&definitions)),
+        Err(err) => (None, loader_errors(&err, Some(&loader.name(path)))),
+    };
+
+    Analysis {
+        diagnostics,
+        definitions,
+        imports: imported.into_iter().map(Symbol::from).collect(),
+        sections: Vec::new(),
+    }
+    .typed(env.as_ref())
+}
+
+/// A `@{...}` block or a `{expr}` inline of a document, as a part of the
+/// program the document is checked as.
+pub(super) struct Chunk {
+    source: String,
+    /// What an inline is checked as.
+    pub(super) name: Option<String>,
+    /// Where the code is in the document.
+    pub(super) at: (usize, usize),
+    /// The columns the code is after in the first line of `source`.
+    indent: usize,
+    /// The line of the program `source` starts at.
+    line: usize,
+    /// The statements of `source`, once parsed.
+    module: Option<Module>,
+}
+
+impl Chunk {
+    fn script(at: (usize, usize), code: String) -> Self {
+        Self {
+            source: code,
+            name: None,
+            at,
+            indent: 0,
+            line: 0,
+            module: None,
+        }
+    }
+
+    fn inline(at: (usize, usize), name: String, expr: String) -> Self {
+        let prefix = format!("{name} = (@show (");
+
+        Self {
+            indent: prefix.chars().count(),
+            source: format!("{prefix}{expr}))"),
+            name: Some(name),
+            at,
+            line: 0,
+            module: None,
+        }
+    }
+
+    /// Where `span` of `source` is in the document.
+    fn locate(&self, span: Span) -> Span {
+        let position = |(line, column): (usize, usize)| match line {
+            1 => (
+                self.at.0,
+                self.at.1 + column.saturating_sub(self.indent + 1),
+            ),
+            _ => (self.at.0 + line - 1, column),
+        };
+
+        Span {
+            start: position(span.start),
+            end: position(span.end),
+        }
+    }
+
+    /// `message` without the name an inline is checked as.
+    fn unnamed(&self, message: String) -> String {
+        match &self.name {
+            Some(name) => message
+                .strip_prefix(&format!("in {name}: "))
+                .map(ToString::to_string)
+                .unwrap_or(message),
+            None => message,
+        }
+    }
+
+    /// Where `span` of the program is in the document, if it is in the chunk.
+    fn unshift(&self, span: Span) -> Option<Span> {
+        let lines = self.source.split('\n').count();
+        let local = |(line, column): (usize, usize)| (line + 1 - self.line, column);
+
+        (self.line..self.line + lines)
+            .contains(&span.start.0)
+            .then(|| {
+                self.locate(Span {
+                    start: local(span.start),
+                    end: local(span.end.max(span.start)),
+                })
+            })
+    }
+}
+
+/// Analyzes a document with the code of its `@{...}` blocks and `{expr}`
+/// inlines checked as `evaluate` runs it, without running it.
+pub fn analyze_document(loader: &Loader, source: &str) -> Analysis {
+    let parts = match prose_down::parts(source) {
+        Ok(parts) => parts,
+        Err(err) => return Analysis::failed(parse_error::<prose_down::Rule>(&err)),
+    };
+    let sections = sections(&parts);
+
+    let mut chunks = chunks(parts);
+    let parse_errors = parse_chunks(&mut chunks);
+    if !parse_errors.is_empty() || chunks.is_empty() {
+        return Analysis {
+            diagnostics: parse_errors,
+            sections,
+            ..Default::default()
+        };
+    }
+
+    let program = match program(loader, &mut chunks) {
+        Ok(program) => program,
+        Err(err) => return Analysis::failed(Diagnostic::error(START, format!("{err:#}"))),
+    };
+
+    let (linked, imported) = loader.load_source_with_imports(&program);
+    let spans = chunks
+        .iter()
+        .filter_map(|chunk| {
+            let module = chunk.module.as_ref()?;
+            let shift = |(line, column): (usize, usize)| (line + chunk.line - 1, column);
+            let spans = spans(module).into_iter().map(|span| Span {
+                start: shift(span.start),
+                end: shift(span.end),
+            });
+
+            Some(spans.collect::<Vec<_>>())
+        })
+        .flatten()
+        .collect::<Vec<_>>();
+    let definitions = chunks
+        .iter()
+        // the inlines define nothing the document refers to
+        .filter(|chunk| chunk.name.is_none())
+        .filter_map(|chunk| Some(defined(chunk.module.as_ref()?, |span| chunk.locate(span))))
+        .flatten()
+        .collect::<Vec<_>>();
+    let names = names(&definitions)
+        .into_iter()
+        .chain(chunks.iter().filter_map(|chunk| chunk.name.clone()))
+        .collect();
+    let (env, diagnostics) = match linked {
+        Ok(linked) => check(linked, &spans, &names),
+        Err(err) => (None, loader_errors(&err, None)),
+    };
+    let diagnostics = diagnostics
+        .into_iter()
+        .map(|diagnostic| relocate(&chunks, diagnostic))
+        .collect();
+
+    Analysis {
+        diagnostics,
+        definitions,
+        imports: imported.into_iter().map(Symbol::from).collect(),
+        sections,
+    }
+    .typed(env.as_ref())
+}
+
+/// The chunks of the code among `parts`, each inline checked as `inline0` and
+/// so on.
+pub(super) fn chunks(parts: impl IntoIterator<Item = ((usize, usize), Part)>) -> Vec<Chunk> {
+    parts
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, (at, part))| match part {
+            Part::Script(source) => Some(Chunk::script(at, source)),
+            Part::Expr(expr) => Some(Chunk::inline(at, format!("{INLINE}{index}"), expr)),
+            Part::Paragraph(_) | Part::ThemanticBreak => None,
+        })
+        .collect()
+}
+
+/// Parses every chunk, with the errors at their places in the document.
+pub(super) fn parse_chunks(chunks: &mut [Chunk]) -> Vec<Diagnostic> {
+    chunks
+        .iter_mut()
+        .filter_map(|chunk| match parse_unassociated(&chunk.source) {
+            Ok(module) => {
+                chunk.module = Some(module);
+                None
+            }
+            Err(err) => Some(parse_error::<Rule>(&err).at(|span| chunk.locate(span))),
+        })
+        .collect()
+}
+
+/// The program of `chunks` after an import of every module of the standard
+/// library, with the line each chunk starts at.
+pub(super) fn program(loader: &Loader, chunks: &mut [Chunk]) -> anyhow::Result<String> {
+    let header = loader.std_modules()?.len();
+    chunks.iter_mut().fold(header + 1, |line, chunk| {
+        chunk.line = line;
+        line + chunk.source.split('\n').count()
+    });
+
+    with_std(loader, chunks.iter().map(|chunk| chunk.source.clone()))
+}
+
+/// `diagnostic` about the program of `chunks` at the place in the document it
+/// is about, at the start for a place in no chunk.
+pub(super) fn relocate(chunks: &[Chunk], diagnostic: Diagnostic) -> Diagnostic {
+    match chunks
+        .iter()
+        .find_map(|chunk| Some((chunk, chunk.unshift(diagnostic.span)?)))
+    {
+        Some((chunk, span)) => Diagnostic {
+            span,
+            message: chunk.unnamed(diagnostic.message),
+            ..diagnostic
+        },
+        None => Diagnostic {
+            span: START,
+            ..diagnostic
+        },
+    }
+}
+
+impl Analysis {
+    fn failed(diagnostic: Diagnostic) -> Self {
+        Self {
+            diagnostics: vec![diagnostic],
+            ..Default::default()
+        }
+    }
+
+    fn typed(self, env: Option<&TypeEnv>) -> Self {
+        let Some(env) = env else {
+            return self;
+        };
+        let typed = |symbols: Vec<Symbol>| {
+            symbols
+                .into_iter()
+                .map(|symbol| Symbol {
+                    ty: symbol.ty_in(env),
+                    ..symbol
+                })
+                .collect()
+        };
+
+        Self {
+            definitions: typed(self.definitions),
+            imports: typed(self.imports),
+            ..self
+        }
+    }
+}
+
+impl Diagnostic {
+    fn error(span: Span, message: String) -> Self {
+        Self {
+            span,
+            severity: Severity::Error,
+            message,
+        }
+    }
+
+    fn at(self, locate: impl Fn(Span) -> Span) -> Self {
+        Self {
+            span: locate(self.span),
+            ..self
+        }
+    }
+}
+
+impl Symbol {
+    fn ty_in(&self, env: &TypeEnv) -> Option<String> {
+        let ident = unqualified(&self.ident).unwrap_or(&self.ident);
+
+        match self.namespace {
+            Namespace::Value | Namespace::Constructor => env.describe(&Ident::from(ident)),
+            Namespace::Handler => env
+                .handler(&HandlerIdent(ident.to_string()))
+                .map(ToString::to_string),
+            Namespace::Inst | Namespace::Type | Namespace::Trait => None,
+        }
+    }
+}
+
+impl From<Imported> for Symbol {
+    fn from(value: Imported) -> Self {
+        Self {
+            namespace: value.namespace,
+            ident: value.ident,
+            path: value.path,
+            span: value.span,
+            ty: None,
+        }
+    }
+}
+
+/// Types a linked program, with the errors and warnings about the statements
+/// that span `spans` and define `names`. The other statements are those of the
+/// modules it imports, which may be at the same places.
+fn check(
+    linked: Module,
+    spans: &[Span],
+    names: &HashSet<String>,
+) -> (Option<TypeEnv>, Vec<Diagnostic>) {
+    let ir1 = transform1(&linked);
+    let ir2 = match transform2(ir1, linked) {
+        Ok(ir2) => ir2,
+        Err(err) => return (None, vec![Diagnostic::error(START, format!("{err:#}"))]),
+    };
+    let (env, errors) = type_check(&ir2);
+    let errors = errors
+        .iter()
+        .map(|error| (error.span(), Severity::Error, error.to_string()));
+    let warnings = check_matches(&ir2)
+        .into_iter()
+        .map(|warning| (warning.span(), Severity::Warning, warning.to_string()))
+        .collect::<Vec<_>>();
+    let diagnostics = errors
+        .chain(warnings)
+        .filter(|(span, _, message)| {
+            let within = |statement: &Span| (statement.start..=statement.end).contains(&span.start);
+
+            spans.iter().any(within) && is_about(message, names)
+        })
+        .map(|(span, severity, message)| Diagnostic {
+            message: message
+                .strip_prefix(&format!("{span}: "))
+                .unwrap_or(&message)
+                .to_string(),
+            span,
+            severity,
+        })
+        .collect();
+
+    (Some(env), diagnostics)
+}
+
+/// Whether an error names one of `names` as where it is, or names none, `in
+/// main:` and `in impl Show Bool:` are the places.
+fn is_about(message: &str, names: &HashSet<String>) -> bool {
+    let place = message
+        .split_once(": in ")
+        .and_then(|(_, rest)| rest.split_once(": "))
+        .map(|(place, _)| place);
+
+    match place {
+        Some(place) if !place.starts_with("impl ") && !place.contains(" of impl ") => {
+            names.contains(place)
+        }
+        _ => true,
+    }
+}
+
+/// The errors the loader reports about the module named `name`, or the script
+/// that is not in a file, at the places they are about. The errors about other
+/// modules are at the start.
+pub(super) fn loader_errors(err: &anyhow::Error, name: Option<&str>) -> Vec<Diagnostic> {
+    format!("{err:#}")
+        .lines()
+        .fold(Vec::new(), |mut diagnostics: Vec<Diagnostic>, line| {
+            let located = match (name, line.split_once(':')) {
+                (Some(name), Some((prefix, rest))) if prefix == name => position(rest),
+                (None, _) => position(line),
+                _ => None,
+            };
+            let other = || {
+                let (_, rest) = line.split_once(':')?;
+                position(rest).map(|_| (START, line))
+            };
+
+            match located.or_else(other) {
+                Some((span, message)) => {
+                    diagnostics.push(Diagnostic::error(span, message.to_string()))
+                }
+                // the lines after the first of a parse error
+                None => match diagnostics.last_mut() {
+                    Some(last) => last.message = format!("{}\n{line}", last.message),
+                    None => diagnostics.push(Diagnostic::error(START, line.to_string())),
+                },
+            }
+
+            diagnostics
+        })
+}
+
+/// The place `line:column: message` is about, and the message.
+fn position(text: &str) -> Option<(Span, &str)> {
+    let (line, rest) = text.split_once(':')?;
+    let (column, message) = rest.split_once(':')?;
+    let start = (line.parse().ok()?, column.parse().ok()?);
+
+    Some((Span { start, end: start }, message.trim_start()))
+}
+
+fn parse_error<R: RuleType + 'static>(err: &anyhow::Error) -> Diagnostic {
+    match err.root_cause().downcast_ref::<pest::error::Error<R>>() {
+        Some(error) => {
+            let (start, end) = match error.line_col {
+                LineColLocation::Pos(position) => (position, position),
+                LineColLocation::Span(start, end) => (start, end),
+            };
+
+            Diagnostic::error(
+                Span { start, end },
+                format!("parse error: {}", error.variant.message()),
+            )
+        }
+        None => Diagnostic::error(START, format!("{err:#}")),
+    }
+}
+
+/// The names `module` defines, at the places `locate` makes of theirs.
+fn defined(module: &Module, locate: impl Fn(Span) -> Span) -> Vec<Symbol> {
+    definitions(&transform1(module))
+        .into_iter()
+        .map(|(namespace, ident, span)| Symbol {
+            namespace,
+            ident,
+            path: None,
+            span: locate(span),
+            ty: None,
+        })
+        .collect()
+}
+
+/// What the errors about the functions and handlers of `definitions` name
+/// them.
+fn names(definitions: &[Symbol]) -> HashSet<String> {
+    definitions
+        .iter()
+        .filter(|symbol| matches!(symbol.namespace, Namespace::Value | Namespace::Handler))
+        .map(|symbol| symbol.ident.clone())
+        .collect()
+}
+
+/// Where the statements of `module` are, those of their where-clauses are
+/// within them.
+fn spans(module: &Module) -> Vec<Span> {
+    module
+        .iter()
+        .filter_map(|statement| match statement {
+            Statement::Assign(assign) => Some(assign.span),
+            Statement::HandlerAssign(assign) => Some(assign.span),
+            Statement::TraitDef(def) => Some(def.span),
+            Statement::ImplTrait(def) => Some(def.span),
+            Statement::AssignDef(def) => Some(def.span),
+            Statement::HandlerDef(def) => Some(def.span),
+            Statement::InstDef(def) => Some(def.span),
+            Statement::DataAssign(def) => Some(def.span),
+            Statement::FixityDef(def) => Some(def.span),
+            Statement::Import(import) => Some(import.span),
+            Statement::Export(export) => Some(export.span),
+            Statement::LineComment(_) => None,
+        })
+        .collect()
+}
+
+/// A section at the first paragraph of the document, and one at the first
+/// paragraph after each thematic break.
+fn sections(parts: &[((usize, usize), Part)]) -> Vec<Section> {
+    parts
+        .iter()
+        .fold(
+            (Vec::new(), true),
+            |(mut sections, starts), (at, part)| match part {
+                Part::ThemanticBreak => (sections, true),
+                Part::Paragraph(line) if starts => {
+                    sections.push(Section {
+                        span: Span {
+                            start: *at,
+                            end: (at.0, at.1 + line.chars().count()),
+                        },
+                        title: line.trim().to_string(),
+                    });
+                    (sections, false)
+                }
+                _ => (sections, starts),
+            },
+        )
+        .0
+}
@@ -0,0 +1,28 @@
+use std::path::Path;
+
+/// Where the loader finds the bundled standard library, no such directory
+/// exists.
+pub(super) const ROOT: &str = "<std>";
+
+const MODULES: &[(&str, &str)] = &[
+    ("format", include_str!("../../std/format.pds")),
+    ("kanji", include_str!("../../std/kanji.pds")),
+    ("list", include_str!("../../std/list.pds")),
+    ("map", include_str!("../../std/map.pds")),
+    ("prelude", include_str!("../../std/prelude.pds")),
+    ("text", include_str!("../../std/text.pds")),
+];
+
+pub(super) fn modules() -> impl Iterator<Item = &'static str> {
+    MODULES.iter().map(|(name, _)| *name)
+}
+
+/// The source of the bundled module at `path`, `<std>/text.pds` for `text`.
+pub(super) fn source(path: &Path) -> Option<&'static str> {
+    let file = path.strip_prefix(ROOT).ok()?;
+
+    MODULES
+        .iter()
+        .find(|(name, _)| file == Path::new(&format!("{name}.pds")))
+        .map(|(_, source)| *source)
+}
@@ -0,0 +1,354 @@
+use crate::ast::{
+    Abstruction, Apply, ApplyEff, ApplyInst, Assign, Associativity, Expr, FixityDef, Handle,
+    HandlerAssign, Ident, ImplTrait, Lambda, Literal, Module, Span, Statement, TraitDef,
+};
+use anyhow::{anyhow, Result};
+use std::{collections::HashMap, fmt::Display, iter::Peekable, vec::IntoIter};
+
+/// An operator, where it is, applied to its right operand, after the left one.
+type Chain = Peekable<IntoIter<(Ident, Span, Expr)>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Fixity {
+    associativity: Associativity,
+    precedence: usize,
+}
+
+/// An operator without a declaration is `infixl 9`.
+impl Default for Fixity {
+    fn default() -> Self {
+        Self {
+            associativity: Associativity::Left,
+            precedence: 9,
+        }
+    }
+}
+
+impl Display for Fixity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let associativity = match self.associativity {
+            Associativity::Left => "infixl",
+            Associativity::Right => "infixr",
+            Associativity::None => "infix",
+        };
+
+        write!(f, "{associativity} {}", self.precedence)
+    }
+}
+
+struct Fixities(HashMap<Ident, Fixity>);
+
+/// The parser reads `a + b * c` as `a + (b * c)`. Reassociates each chain of
+/// operator applications outside parentheses by the fixity declarations at the
+/// top level of `module`, wherever they are in it, and those `imported` with
+/// the modules it imports.
+pub(super) fn reassociate(module: Module, imported: &[&FixityDef]) -> Result<Module> {
+    let fixities = Fixities::declared(&module, imported)?;
+
+    fixities.module(module)
+}
+
+impl Fixities {
+    fn declared(module: &Module, imported: &[&FixityDef]) -> Result<Self> {
+        let mut fixities = HashMap::new();
+        imported
+            .iter()
+            .copied()
+            .chain(module.iter().filter_map(|statement| match statement {
+                Statement::FixityDef(def) => Some(def),
+                _ => None,
+            }))
+            .try_for_each(|def| {
+                let FixityDef {
+                    associativity,
+                    precedence,
+                    operators,
+                    span,
+                } = def;
+                let fixity = Fixity {
+                    associativity: *associativity,
+                    precedence: *precedence,
+                };
+
+                operators
+                    .iter()
+                    .try_for_each(|op| match fixities.insert(op.clone(), fixity) {
+                        Some(_) => Err(anyhow!("{span}: fixity of {} is declared twice", op.0)),
+                        None => Ok(()),
+                    })
+            })?;
+
+        Ok(Self(fixities))
+    }
+
+    fn of(&self, op: &Ident) -> Fixity {
+        self.0.get(op).copied().unwrap_or_default()
+    }
+
+    fn module(&self, module: Module) -> Result<Module> {
+        let statements = module
+            .into_iter()
+            .map(|statement| self.statement(statement))
+            .collect::<Result<_>>()?;
+
+        Ok(Module { statements })
+    }
+
+    fn statement(&self, statement: Statement) -> Result<Statement> {
+        Ok(match statement {
+            Statement::Assign(assign) => Statement::Assign(Assign {
+                expr: self.expr(assign.expr)?,
+                where_clause: self.module(assign.where_clause)?,
+                ..assign
+            }),
+            Statement::HandlerAssign(assign) => Statement::HandlerAssign(self.handler(assign)?),
+            Statement::TraitDef(def) => Statement::TraitDef(TraitDef {
+                where_clause: self.module(def.where_clause)?,
+                ..def
+            }),
+            Statement::ImplTrait(def) => Statement::ImplTrait(ImplTrait {
+                where_clause: self.module(def.where_clause)?,
+                ..def
+            }),
+            statement => statement,
+        })
+    }
+
+    fn handler(&self, assign: HandlerAssign) -> Result<HandlerAssign> {
+        Ok(HandlerAssign {
+            expr: self.expr(assign.expr)?,
+            where_clause: self.module(assign.where_clause)?,
+            ..assign
+        })
+    }
+
+    fn expr(&self, expr: Expr) -> Result<Expr> {
+        Ok(match expr {
+            Expr::Apply(Apply {
+                abstruction:
+                    Abstruction {
+                        arg: Some(lhs),
+                        expr: op,
+                    },
+                expr: rhs,
+                span,
+            }) => {
+                let lhs = self.expr(*lhs)?;
+                let chain = self.chain(*op, span, *rhs)?;
+
+                self.climb(lhs, &mut chain.into_iter().peekable(), 0)?
+            }
+            Expr::Apply(Apply {
+                abstruction,
+                expr,
+                span,
+            }) => Expr::Apply(Apply {
+                abstruction: Abstruction {
+                    arg: None,
+                    expr: Box::new(self.expr(*abstruction.expr)?),
+                },
+                expr: Box::new(self.expr(*expr)?),
+                span,
+            }),
+            Expr::ApplyInst(ApplyInst { ident, expr, span }) => Expr::ApplyInst(ApplyInst {
+                ident,
+                expr: Box::new(self.expr(*expr)?),
+                span,
+            }),
+            Expr::ApplyEff(ApplyEff { ident, expr, span }) => Expr::ApplyEff(ApplyEff {
+                ident,
+                expr: Box::new(self.expr(*expr)?),
+                span,
+            }),
+            Expr::Literal(Literal::Tuple(size, items)) => {
+                Expr::Literal(Literal::Tuple(size, self.exprs(items)?))
+            }
+            Expr::Literal(Literal::Array(items)) => {
+                Expr::Literal(Literal::Array(self.exprs(items)?))
+            }
+            Expr::Abstruction(Lambda { params, expr }) => Expr::Abstruction(Lambda {
+                params,
+                expr: Box::new(self.expr(*expr)?),
+            }),
+            Expr::Handle(Handle { expr, handlers }) => Expr::Handle(Handle {
+                expr: Box::new(self.expr(*expr)?),
+                handlers: handlers
+                    .into_iter()
+                    .map(|handler| self.handler(handler))
+                    .collect::<Result<_>>()?,
+            }),
+            Expr::Group(expr) => Expr::Group(Box::new(self.expr(*expr)?)),
+            expr => expr,
+        })
+    }
+
+    fn exprs(&self, exprs: Vec<Expr>) -> Result<Vec<Expr>> {
+        exprs.into_iter().map(|expr| self.expr(expr)).collect()
+    }
+
+    /// The operators and right operands of `op rhs`, where `rhs` continues the
+    /// chain unless it is in parentheses.
+    fn chain(
+        &self,
+        mut op: Expr,
+        mut span: Span,
+        mut rhs: Expr,
+    ) -> Result<Vec<(Ident, Span, Expr)>> {
+        let mut chain = Vec::new();
+        loop {
+            let ident = match op {
+                Expr::Ident(op) => op,
+                op => return Err(anyhow!("{op:?} is not an operator")),
+            };
+
+            match rhs {
+                Expr::Apply(Apply {
+                    abstruction:
+                        Abstruction {
+                            arg: Some(lhs),
+                            expr: next,
+                        },
+                    expr: rest,
+                    span: next_span,
+                }) => {
+                    chain.push((ident, span, self.expr(*lhs)?));
+                    (op, span, rhs) = (*next, next_span, *rest);
+                }
+                rhs => {
+                    chain.push((ident, span, self.expr(rhs)?));
+                    return Ok(chain);
+                }
+            }
+        }
+    }
+
+    /// Applies the operators of `chain` that bind at least as tight as `min`
+    /// to `lhs`.
+    fn climb(&self, mut lhs: Expr, chain: &mut Chain, min: usize) -> Result<Expr> {
+        let mut last: Option<(Ident, Fixity)> = None;
+        while let Some((op, fixity)) = self.peek(chain).filter(|(_, f)| f.precedence >= min) {
+            match last {
+                Some((prev, prev_fixity)) if prev_fixity.precedence == fixity.precedence => {
+                    self.mix(&prev, prev_fixity, &op, fixity, Associativity::Left)?
+                }
+                _ => {}
+            }
+
+            let (_, span, mut rhs) = chain.next().unwrap();
+            while let Some((next, next_fixity)) = self.peek(chain) {
+                rhs = match next_fixity.precedence {
+                    precedence if precedence > fixity.precedence => {
+                        self.climb(rhs, chain, fixity.precedence + 1)?
+                    }
+                    precedence if precedence == fixity.precedence => {
+                        match self.mix(&op, fixity, &next, next_fixity, Associativity::Right) {
+                            Ok(()) => self.climb(rhs, chain, fixity.precedence)?,
+                            Err(_) => break,
+                        }
+                    }
+                    _ => break,
+                };
+            }
+
+            lhs = infix(lhs, op.clone(), span, rhs);
+            last = Some((op, fixity));
+        }
+
+        Ok(lhs)
+    }
+
+    fn peek(&self, chain: &mut Chain) -> Option<(Ident, Fixity)> {
+        chain.peek().map(|(op, _, _)| (op.clone(), self.of(op)))
+    }
+
+    /// Operators of the same precedence in a chain associate the same way.
+    fn mix(
+        &self,
+        lhs: &Ident,
+        lhs_fixity: Fixity,
+        rhs: &Ident,
+        rhs_fixity: Fixity,
+        associativity: Associativity,
+    ) -> Result<()> {
+        match lhs_fixity.associativity == associativity && rhs_fixity.associativity == associativity
+        {
+            true => Ok(()),
+            false => Err(anyhow!(
+                "cannot mix {} [{lhs_fixity}] and {} [{rhs_fixity}] in the same infix expression",
+                lhs.0,
+                rhs.0
+            )),
+        }
+    }
+}
+
+/// `lhs op rhs`, where `op` is at `span`
+fn infix(lhs: Expr, op: Ident, span: Span, rhs: Expr) -> Expr {
+    Expr::Apply(Apply {
+        abstruction: Abstruction {
+            arg: Some(Box::new(lhs)),
+            expr: Box::new(Expr::Ident(op)),
+        },
+        expr: Box::new(rhs),
+        span,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ast::{Abstruction, Apply, Expr, Statement},
+        prose_down_script::parse,
+    };
+
+    #[test]
+    fn non_associative_test() {
+        let err = parse("infix 4 ==\nf a b c = a == b == c\n").unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "cannot mix == [infix 4] and == [infix 4] in the same infix expression"
+        );
+    }
+
+    #[test]
+    fn mixed_associativity_test() {
+        let err = parse("infixl 6 +\ninfixr 6 ++\nf a b c = a + b ++ c\n").unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "cannot mix + [infixl 6] and ++ [infixr 6] in the same infix expression"
+        );
+    }
+
+    #[test]
+    fn declared_twice_test() {
+        let err = parse("infixl 6 +\ninfixr 6 +\n").unwrap_err();
+
+        assert_eq!(err.to_string(), "2:1: fixity of + is declared twice");
+    }
+
+    #[test]
+    fn long_chain_test() {
+        let count = 1000;
+        let input = format!(
+            "infixl 6 +\ninfixl 7 *\nf = 1{}\n",
+            " + 2 * 3".repeat(count)
+        );
+        let module = parse(&input).unwrap();
+        let Some(Statement::Assign(assign)) = module.statements.last() else {
+            panic!("{module:?}");
+        };
+        // `(((1 + 2 * 3) + 2 * 3) + ...)`
+        let sums = std::iter::successors(Some(&assign.expr), |expr| match expr {
+            Expr::Apply(Apply {
+                abstruction: Abstruction { arg: Some(lhs), .. },
+                ..
+            }) => Some(lhs),
+            _ => None,
+        })
+        .count();
+
+        assert_eq!(sums, count + 1);
+    }
+}
@@ -0,0 +1,652 @@
+use super::parser::parse_unassociated;
+use crate::ast::{
+    Abstruction, Apply, Associativity, CoroutineType, DataAssign, DataConstructor, DataExpr,
+    DataModifier, DataValue, EtaEnvs, Expr, FixityDef, HandlerAssign, Ident, ImplTrait, Import,
+    Literal, Module, ModuleName, PatternExpr, Span, Statement, TraitConstraint, TraitDef,
+    TypeAbstructionExpr, TypeIdent, TypeLiteral,
+};
+use anyhow::Result;
+
+const INDENT: &str = "  ";
+
+/// A script in the canonical layout: a statement on a line, a block indented
+/// by two spaces, and the `::` of the signatures in a row at one column. The
+/// comments are kept, and so are the blank lines between the statements,
+/// several as one.
+///
+/// The script parses to the same statements as before, only their places
+/// differ.
+pub fn format(source: &str) -> Result<String> {
+    let module = parse_unassociated(source)?;
+
+    Ok(match module.statements.is_empty() {
+        true => String::new(),
+        false => format!("{}\n", statements(&module, 0)),
+    })
+}
+
+/// A statement as it is laid out, with the comment after it on its line.
+struct Line {
+    span: Span,
+    text: Text,
+    comment: Option<String>,
+}
+
+enum Text {
+    /// `name` and `type` of `name :: type`
+    Signature(String, String),
+    Other(String),
+}
+
+impl Line {
+    fn is_signature(&self) -> bool {
+        matches!(self.text, Text::Signature(_, _))
+    }
+}
+
+/// The statements of a module at `depth`, without a line break at the end.
+fn statements(module: &Module, depth: usize) -> String {
+    let lines = module
+        .iter()
+        .fold(Vec::<Line>::new(), |mut lines, statement| {
+            match (statement, lines.last_mut()) {
+                (Statement::LineComment(comment), Some(last))
+                    if comment.span.start.0 == last.span.end.0 && last.comment.is_none() =>
+                {
+                    last.comment = Some(comment.text.clone());
+                }
+                _ => lines.push(Line {
+                    span: statement.span(),
+                    text: text(statement, depth),
+                    comment: None,
+                }),
+            }
+            lines
+        });
+    let paragraphs = lines
+        .into_iter()
+        .fold(Vec::<Vec<Line>>::new(), |mut paragraphs, line| {
+            match paragraphs.last_mut() {
+                Some(paragraph)
+                    if paragraph
+                        .last()
+                        .is_some_and(|last| line.span.start.0 <= last.span.end.0 + 1) =>
+                {
+                    paragraph.push(line)
+                }
+                _ => paragraphs.push(vec![line]),
+            }
+            paragraphs
+        });
+
+    paragraphs
+        .iter()
+        .map(|paragraph| {
+            paragraph
+                .chunk_by(|a, b| a.is_signature() == b.is_signature())
+                .flat_map(|lines| aligned(lines, depth))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// The lines with the `::` of their signatures at one column.
+fn aligned(lines: &[Line], depth: usize) -> Vec<String> {
+    let width = lines
+        .iter()
+        .map(|line| match &line.text {
+            Text::Signature(name, _) => name.chars().count(),
+            Text::Other(_) => 0,
+        })
+        .max()
+        .unwrap_or_default();
+
+    lines
+        .iter()
+        .map(|line| {
+            let text = match &line.text {
+                Text::Signature(name, ty) => format!("{name:<width$} :: {ty}"),
+                Text::Other(text) => text.clone(),
+            };
+            match &line.comment {
+                Some(comment) => format!("{}{text} {comment}", INDENT.repeat(depth)),
+                None => format!("{}{text}", INDENT.repeat(depth)),
+            }
+        })
+        .collect()
+}
+
+fn text(statement: &Statement, depth: usize) -> Text {
+    match statement {
+        Statement::AssignDef(def) => Text::Signature(
+            ident(&def.ident),
+            type_expr(
+                &def.expr.trait_constraints,
+                &def.expr.eta_envs,
+                &def.expr.expr,
+            ),
+        ),
+        Statement::HandlerDef(def) => Text::Signature(
+            def.ident.0.clone(),
+            type_expr(
+                &def.expr.trait_constraints,
+                &def.expr.eta_envs,
+                &def.expr.expr,
+            ),
+        ),
+        Statement::InstDef(def) => Text::Signature(
+            def.ident.0.clone(),
+            type_expr(
+                &def.expr.trait_constraints,
+                &def.expr.eta_envs,
+                &def.expr.expr,
+            ),
+        ),
+        Statement::Assign(assign) => Text::Other(format!(
+            "{}{} = {}{}",
+            ident(&assign.ident),
+            patterns(assign.args.iter()),
+            expr(&assign.expr, Place::Expr, depth),
+            where_clause(&assign.where_clause, depth, false)
+        )),
+        Statement::HandlerAssign(assign) => Text::Other(handler_assign(assign, depth)),
+        Statement::TraitDef(def) => Text::Other(trait_def(def, depth)),
+        Statement::ImplTrait(def) => Text::Other(impl_trait(def, depth)),
+        Statement::DataAssign(def) => Text::Other(data_assign(def)),
+        Statement::FixityDef(def) => Text::Other(fixity_def(def)),
+        Statement::Import(import) => Text::Other(import_def(import)),
+        Statement::Export(export) => Text::Other(format!(
+            "export {}",
+            export
+                .names
+                .iter()
+                .map(module_name)
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+        Statement::LineComment(comment) => Text::Other(comment.text.clone()),
+    }
+}
+
+/// ` where { ... }`, nothing for no statements unless `always`.
+fn where_clause(module: &Module, depth: usize, always: bool) -> String {
+    match (module.statements.is_empty(), always) {
+        (true, false) => String::new(),
+        (true, true) => " where {}".to_string(),
+        (false, _) => format!(
+            " where {{\n{}\n{}}}",
+            statements(module, depth + 1),
+            INDENT.repeat(depth)
+        ),
+    }
+}
+
+fn handler_assign(assign: &HandlerAssign, depth: usize) -> String {
+    format!(
+        "{}{} = {}{}",
+        assign.ident.0,
+        patterns(assign.args.iter()),
+        expr(&assign.expr, Place::Expr, depth),
+        where_clause(&assign.where_clause, depth, false)
+    )
+}
+
+fn trait_def(def: &TraitDef, depth: usize) -> String {
+    format!(
+        "trait {}{}{}{}",
+        constraints(&def.trait_constraints),
+        type_ident(&def.constructor.ident),
+        def.constructor
+            .args
+            .iter()
+            .map(|arg| format!(" {}", type_arg(arg)))
+            .collect::<String>(),
+        where_clause(&def.where_clause, depth, true)
+    )
+}
+
+fn impl_trait(def: &ImplTrait, depth: usize) -> String {
+    format!(
+        "impl {}{}{}{}",
+        constraints(&def.constraints),
+        type_ident(&def.ident),
+        def.args
+            .iter()
+            .map(|arg| format!(" {}", type_ident(arg)))
+            .collect::<String>(),
+        where_clause(&def.where_clause, depth, true)
+    )
+}
+
+fn data_assign(def: &DataAssign) -> String {
+    format!(
+        "{}data {}{}{} = {}",
+        modifier(&def.modifier),
+        constraints(&def.constraints),
+        type_ident(&def.ident),
+        def.args
+            .iter()
+            .map(|arg| format!(" {}", type_ident(arg)))
+            .collect::<String>(),
+        data_expr(&def.expr)
+    )
+}
+
+fn fixity_def(def: &FixityDef) -> String {
+    let associativity = match def.associativity {
+        Associativity::Left => "infixl",
+        Associativity::Right => "infixr",
+        Associativity::None => "infix",
+    };
+
+    format!(
+        "{associativity} {} {}",
+        def.precedence,
+        def.operators
+            .iter()
+            .map(operator)
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+fn import_def(import: &Import) -> String {
+    format!(
+        "import \"{}\"{}{}",
+        import.path,
+        import
+            .alias
+            .as_ref()
+            .map(|alias| format!(" as {}", alias.0))
+            .unwrap_or_default(),
+        import
+            .names
+            .as_ref()
+            .map(|names| format!(
+                " ({})",
+                names.iter().map(module_name).collect::<Vec<_>>().join(", ")
+            ))
+            .unwrap_or_default()
+    )
+}
+
+fn module_name(name: &ModuleName) -> String {
+    match name {
+        ModuleName::Value(value) => ident(value),
+        ModuleName::Handler(handler) => handler.0.clone(),
+        ModuleName::Type(ty) => ty.0.clone(),
+    }
+}
+
+/// `(+)` of an operator, which is written between its operands otherwise.
+fn ident(ident: &Ident) -> String {
+    match is_operator(ident) {
+        true => format!("({})", ident.0),
+        false => ident.0.clone(),
+    }
+}
+
+/// `+` of an operator, `` `times` `` of a name.
+fn operator(ident: &Ident) -> String {
+    match is_operator(ident) {
+        true => ident.0.clone(),
+        false => format!("`{}`", ident.0),
+    }
+}
+
+fn is_operator(ident: &Ident) -> bool {
+    !ident.0.starts_with(|c: char| c.is_ascii_alphabetic())
+}
+
+/// Where an expression is written, from the place taking any expression to
+/// the one taking a term only.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Place {
+    Expr,
+    /// The left of an operator.
+    Operand,
+    /// The function of an application.
+    Head,
+    Term,
+}
+
+fn expr(value: &Expr, place: Place, depth: usize) -> String {
+    let (text, binds) = match value {
+        Expr::Apply(Apply {
+            abstruction:
+                Abstruction {
+                    arg: Some(lhs),
+                    expr: op,
+                },
+            expr: rhs,
+            ..
+        }) => {
+            let op = match op.as_ref() {
+                Expr::Ident(op) => operator(op),
+                op => expr(op, Place::Term, depth),
+            };
+            let text = format!(
+                "{} {op} {}",
+                expr(lhs, Place::Operand, depth),
+                expr(rhs, Place::Expr, depth)
+            );
+
+            (text, Place::Expr)
+        }
+        Expr::Apply(Apply {
+            abstruction: Abstruction { arg: None, expr: f },
+            expr: arg,
+            ..
+        }) => (
+            format!("{} {}", head(f, depth), expr(arg, Place::Term, depth)),
+            Place::Operand,
+        ),
+        Expr::ApplyInst(apply) => (
+            format!(
+                "{} {}",
+                apply.ident.0,
+                expr(&apply.expr, Place::Term, depth)
+            ),
+            Place::Operand,
+        ),
+        Expr::ApplyEff(apply) => (
+            format!(
+                "eff {} {}",
+                apply.ident.0,
+                expr(&apply.expr, Place::Term, depth)
+            ),
+            Place::Operand,
+        ),
+        Expr::HandlerIdent(ident) => (format!("eff {}", ident.0), Place::Operand),
+        Expr::InstIdent(ident) => (ident.0.clone(), Place::Term),
+        Expr::Ident(value) => (ident(value), Place::Term),
+        Expr::TypeIdent(value) => (type_ident(value), Place::Term),
+        Expr::Literal(value) => (literal(value, depth), Place::Term),
+        Expr::Abstruction(lambda) => (
+            format!(
+                "\\{} -> {}",
+                lambda
+                    .params
+                    .iter()
+                    .map(pattern)
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                expr(&lambda.expr, Place::Expr, depth)
+            ),
+            Place::Expr,
+        ),
+        Expr::Handle(handle) => {
+            let handlers = match handle.handlers.is_empty() {
+                true => "{}".to_string(),
+                false => format!(
+                    "{{\n{}\n{}}}",
+                    handle
+                        .handlers
+                        .iter()
+                        .map(|handler| format!(
+                            "{}{}",
+                            INDENT.repeat(depth + 1),
+                            handler_assign(handler, depth + 1)
+                        ))
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                    INDENT.repeat(depth)
+                ),
+            };
+
+            (
+                format!(
+                    "handle {} where {handlers}",
+                    expr(&handle.expr, Place::Term, depth)
+                ),
+                Place::Operand,
+            )
+        }
+        Expr::Group(value) => (
+            format!("({})", expr(value, Place::Expr, depth)),
+            Place::Term,
+        ),
+    };
+
+    match binds < place {
+        true => format!("({text})"),
+        false => text,
+    }
+}
+
+/// The function of `f x`, which the arguments before `x` are written after.
+/// `(@write) x` differs from `@write x`.
+fn head(f: &Expr, depth: usize) -> String {
+    match f {
+        Expr::Apply(Apply {
+            abstruction: Abstruction { arg: None, .. },
+            ..
+        })
+        | Expr::ApplyInst(_)
+        | Expr::ApplyEff(_) => expr(f, Place::Operand, depth),
+        Expr::InstIdent(ident) => format!("({})", ident.0),
+        f => expr(f, Place::Head, depth),
+    }
+}
+
+fn literal(value: &Literal, depth: usize) -> String {
+    match value {
+        Literal::Char(c) => format!("'{}'", escape(&c.to_string(), '\'')),
+        Literal::Text(text) => format!("\"{}\"", escape(text, '"')),
+        Literal::Int(n) => n.to_string(),
+        // the literals too large for a float are infinite
+        Literal::Float(n) if n.is_infinite() => format!("{}1e999", if *n < 0.0 { "-" } else { "" }),
+        Literal::Float(n) => format!("{n:?}"),
+        Literal::Integer(n) => format!("{n}n"),
+        Literal::Rational(n) => format!("{n}r"),
+        Literal::Unit => "()".to_string(),
+        Literal::Array(items) => format!(
+            "[{}]",
+            items
+                .iter()
+                .map(|item| expr(item, Place::Term, depth))
+                .collect::<Vec<_>>()
+                .join(" ")
+        ),
+        Literal::Tuple(_, items) => tuple(items.iter().map(|item| expr(item, Place::Expr, depth))),
+    }
+}
+
+/// `(a, b)`, and `(a,)` of one item.
+fn tuple(items: impl Iterator<Item = String>) -> String {
+    let items = items.collect::<Vec<_>>();
+
+    match items.len() {
+        1 => format!("({},)", items[0]),
+        _ => format!("({})", items.join(", ")),
+    }
+}
+
+fn escape(text: &str, quote: char) -> String {
+    text.chars()
+        .map(|c| match c {
+            '\\' => "\\\\".to_string(),
+            '\n' => "\\n".to_string(),
+            '\r' => "\\r".to_string(),
+            '\t' => "\\t".to_string(),
+            '\0' => "\\0".to_string(),
+            c if c == quote => format!("\\{c}"),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+/// The arguments of a definition, each after a space.
+fn patterns<'a>(patterns: impl Iterator<Item = &'a PatternExpr>) -> String {
+    patterns
+        .map(|value| format!(" {}", pattern(value)))
+        .collect()
+}
+
+fn pattern(value: &PatternExpr) -> String {
+    match value {
+        PatternExpr::Or(lhs, rhs) => format!("{} | {}", pattern(lhs), pattern(rhs)),
+        PatternExpr::Literal(value) => literal(value, 0),
+        PatternExpr::Bind(ident) => ident.0.clone(),
+        PatternExpr::ListHead(head, tail) => format!("[{} : {}]", pattern(head), pattern(tail)),
+        PatternExpr::Constructor(constructor) => format!(
+            "{}{}",
+            type_ident(&constructor.ident),
+            constructor
+                .args
+                .iter()
+                .map(|arg| format!(" {}", type_arg(arg)))
+                .collect::<String>()
+        ),
+        PatternExpr::Tuple(_, items) => tuple(items.iter().map(pattern)),
+        PatternExpr::Any => "_".to_string(),
+    }
+}
+
+/// `Show a => <write: <Text, ()>> a -> IO ()`
+fn type_expr(
+    trait_constraints: &[TraitConstraint],
+    eta_envs: &EtaEnvs,
+    expr: &TypeAbstructionExpr,
+) -> String {
+    let eta_envs = match eta_envs.0.is_empty() {
+        true => String::new(),
+        false => format!(
+            "<{}> ",
+            eta_envs
+                .iter()
+                .map(|env| format!(
+                    "{}: {}",
+                    env.ident.0.trim_start_matches('*'),
+                    coroutine(&env.expr)
+                ))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+
+    format!(
+        "{}{eta_envs}{}",
+        constraints(trait_constraints),
+        abstruction(expr)
+    )
+}
+
+/// `Show a => ` of one constraint, `(Show a, Eq b) => ` of several.
+fn constraints(constraints: &[TraitConstraint]) -> String {
+    let items = constraints
+        .iter()
+        .map(|constraint| {
+            [constraint.ident.0.as_str()]
+                .into_iter()
+                .chain(constraint.args.iter().map(|arg| arg.0.as_str()))
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>();
+
+    match items.len() {
+        0 => String::new(),
+        1 => format!("{} => ", items[0]),
+        _ => format!("({}) => ", items.join(", ")),
+    }
+}
+
+fn coroutine(value: &CoroutineType) -> String {
+    format!(
+        "<{}, {}>",
+        abstruction(&value.resume),
+        abstruction(&value.ret)
+    )
+}
+
+fn abstruction(value: &TypeAbstructionExpr) -> String {
+    match value {
+        TypeAbstructionExpr::Arrow(lhs, rhs) => match lhs.as_ref() {
+            TypeAbstructionExpr::Arrow(_, _) => {
+                format!("({}) -> {}", abstruction(lhs), abstruction(rhs))
+            }
+            TypeAbstructionExpr::Literal(_) => {
+                format!("{} -> {}", abstruction(lhs), abstruction(rhs))
+            }
+        },
+        TypeAbstructionExpr::Literal(literal) => type_literal(literal),
+    }
+}
+
+fn type_literal(value: &TypeLiteral) -> String {
+    match value {
+        TypeLiteral::Top => "_".to_string(),
+        TypeLiteral::Bottom => "!".to_string(),
+        TypeLiteral::Array(item) => format!("[{}]", abstruction(item)),
+        TypeLiteral::Constructor(constructor) => format!(
+            "{}{}",
+            type_ident(&constructor.ident),
+            constructor
+                .args
+                .iter()
+                .map(|arg| format!(" {}", type_arg(arg)))
+                .collect::<String>()
+        ),
+        TypeLiteral::Tuple(_, items) if items.is_empty() => "()".to_string(),
+        TypeLiteral::Tuple(_, items) => tuple(items.iter().map(abstruction)),
+        TypeLiteral::Coroutine(value) => coroutine(value),
+        TypeLiteral::Abstruction(value) => format!("({})", abstruction(value)),
+    }
+}
+
+/// `(Option a)` of a constructor with arguments, which is an argument itself.
+fn type_arg(value: &TypeLiteral) -> String {
+    match value {
+        TypeLiteral::Constructor(constructor) if !constructor.args.is_empty() => {
+            format!("({})", type_literal(value))
+        }
+        value => type_literal(value),
+    }
+}
+
+fn type_ident(ident: &TypeIdent) -> String {
+    match ident {
+        TypeIdent::ForallIdent(ident) => ident.0.clone(),
+        TypeIdent::ExistsIdent(ident) => ident.0.clone(),
+    }
+}
+
+fn modifier(modifier: &Option<DataModifier>) -> &'static str {
+    match modifier {
+        Some(DataModifier::Nominal) => "nominal ",
+        Some(DataModifier::Structual) => "structual ",
+        None => "",
+    }
+}
+
+fn data_expr(value: &DataExpr) -> String {
+    match value {
+        DataExpr::Or(lhs, rhs) => match lhs.as_ref() {
+            DataExpr::Or(_, _) => format!("({}) | {}", data_expr(lhs), data_expr(rhs)),
+            DataExpr::Value(_) => format!("{} | {}", data_expr(lhs), data_expr(rhs)),
+        },
+        DataExpr::Value(DataValue::Unit) => "()".to_string(),
+        DataExpr::Value(DataValue::Constructor(constructor)) => data_constructor(constructor),
+    }
+}
+
+/// `nominal Some a`, its arguments the constructors written after it.
+fn data_constructor(constructor: &DataConstructor) -> String {
+    format!(
+        "{}{}{}",
+        modifier(&constructor.modifier),
+        type_ident(&constructor.ident),
+        constructor
+            .args
+            .iter()
+            .map(|arg| match arg {
+                TypeLiteral::Constructor(constructor) =>
+                    format!(" {}", data_constructor(constructor)),
+                arg => format!(" {}", type_arg(arg)),
+            })
+            .collect::<String>()
+    )
+}
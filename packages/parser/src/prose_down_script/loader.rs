@@ -0,0 +1,889 @@
+use super::{
+    bundled,
+    fixity::reassociate,
+    parser::parse_unassociated,
+    rename::{rename, unqualified, Names},
+};
+use crate::{
+    ast::{
+        DataConstructor, DataValue, FixityDef, Import, Module, ModuleName, Span, Statement,
+        TypeIdent,
+    },
+    ir::{
+        ir1::transform1,
+        resolve::{definitions, resolve_with, Definition, Namespace, Scope},
+        type_check::PRELUDE_NAMES,
+    },
+};
+use anyhow::{anyhow, bail, Context, Result};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Loads a script and the modules it imports, and links them into one module.
+/// `import "lib/text"` is `lib/text.pds` in the project root or else in the
+/// standard library, `import "./text"` is next to the importing module. Every
+/// module outside of the standard library imports its `prelude`, unless it
+/// imports it explicitly.
+#[derive(Debug, Clone)]
+pub struct Loader {
+    root: PathBuf,
+    std: Option<Std>,
+}
+
+#[derive(Debug, Clone)]
+enum Std {
+    Dir(PathBuf),
+    /// The standard library compiled into the crate.
+    Bundled,
+}
+
+impl Default for Loader {
+    fn default() -> Self {
+        Self::new(".")
+    }
+}
+
+impl Loader {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            std: None,
+        }
+    }
+
+    pub fn with_std(self, std: impl Into<PathBuf>) -> Self {
+        Self {
+            std: Some(Std::Dir(std.into())),
+            ..self
+        }
+    }
+
+    pub fn with_bundled_std(self) -> Self {
+        Self {
+            std: Some(Std::Bundled),
+            ..self
+        }
+    }
+
+    /// The names of the modules of the standard library, `import`ed as they
+    /// are.
+    pub fn std_modules(&self) -> Result<Vec<String>> {
+        let mut modules = match &self.std {
+            None => Vec::new(),
+            Some(Std::Bundled) => bundled::modules().map(ToString::to_string).collect(),
+            Some(Std::Dir(dir)) => fs::read_dir(dir)?
+                .map(|entry| Ok(entry?.path()))
+                .collect::<Result<Vec<_>>>()?
+                .into_iter()
+                .filter(|path| path.extension().is_some_and(|ext| ext == "pds"))
+                .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+                .collect(),
+        };
+        modules.sort();
+
+        Ok(modules)
+    }
+
+    /// The module at `path` and the modules it imports, each before the
+    /// modules importing it.
+    pub fn load(&self, path: &Path) -> Result<Module> {
+        let path = path
+            .canonicalize()
+            .with_context(|| format!("cannot find module {}", path.display()))?;
+        let mut linker = Linker::new(self);
+        linker.file(path)?;
+
+        linker.link()
+    }
+
+    /// A script that is not in a file, its relative imports are relative to
+    /// the root.
+    pub fn load_source(&self, source: &str) -> Result<Module> {
+        let mut linker = Linker::new(self);
+        linker.module(None, parse_unassociated(source)?)?;
+
+        linker.link()
+    }
+
+    /// `load` with `source` in place of the file at `path`, which need not
+    /// exist, along with the names the module imports. The names are there
+    /// when the modules fail to link as well.
+    pub(super) fn load_overlay(
+        &self,
+        path: &Path,
+        source: &str,
+    ) -> (Result<Module>, Vec<Imported>) {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let mut linker = Linker::new(self);
+        linker.overlay = Some((path.clone(), source.to_string()));
+
+        linker.with_imports(|linker| linker.file(path))
+    }
+
+    /// What the errors about the module at `path` are prefixed with.
+    pub(super) fn name(&self, path: &Path) -> String {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+
+        Linker::new(self).name(&path)
+    }
+
+    /// `module` linked as a script after the programs `after`, whose names are
+    /// in scope in it without an import. The modules it imports are linked
+    /// with it unless one of `after` is linked from them already, and the
+    /// errors about it are at the places in it they are about.
+    pub(super) fn link_after(&self, after: &[&Linked], module: Module) -> Result<Linked> {
+        let mut linker = Linker::new(self);
+        after.iter().for_each(|linked| {
+            linker.linked.extend(linked.paths.iter().cloned());
+            linker.before.extend(linked.defined.iter().cloned());
+            linker.fixities.extend(
+                linked
+                    .module
+                    .iter()
+                    .filter_map(|statement| match statement {
+                        Statement::FixityDef(def) => Some(def.clone()),
+                        _ => None,
+                    }),
+            );
+        });
+        linker.module(None, module)?;
+
+        linker.linked()
+    }
+
+    /// The files of the modules a script that is not in a file links with,
+    /// those it imports, those they import and the preludes, each with its
+    /// source.
+    pub fn imports(&self, source: &str) -> Result<Vec<(PathBuf, String)>> {
+        let mut linker = Linker::new(self);
+        linker.module(None, parse_unassociated(source)?)?;
+
+        linker
+            .loaded
+            .iter()
+            .filter_map(|loaded| loaded.path.as_ref())
+            .map(|path| Ok((path.clone(), linker.source(path)?)))
+            .collect()
+    }
+
+    /// `load_source` along with the names the script imports.
+    pub(super) fn load_source_with_imports(&self, source: &str) -> (Result<Module>, Vec<Imported>) {
+        Linker::new(self).with_imports(|linker| linker.module(None, parse_unassociated(source)?))
+    }
+}
+
+/// A name a module imports, as the module refers to it, and where it is
+/// defined.
+#[derive(Debug, Clone)]
+pub(super) struct Imported {
+    pub(super) namespace: Namespace,
+    pub(super) ident: String,
+    pub(super) path: Option<PathBuf>,
+    pub(super) span: Span,
+}
+
+/// A name a module defines or exports, and where it is defined.
+type Name = (Namespace, String, Span);
+
+/// A linked program, which scripts can be linked after as in a REPL.
+#[derive(Debug, Clone)]
+pub(super) struct Linked {
+    pub(super) module: Module,
+    /// The modules it is linked from.
+    paths: HashSet<PathBuf>,
+    /// The names it defines, each with the module it is defined in.
+    defined: Vec<(Name, Option<String>)>,
+}
+
+struct Loaded {
+    /// Where the module is, `None` for a script that is not in a file.
+    name: Option<String>,
+    path: Option<PathBuf>,
+    module: Module,
+    defined: Vec<Name>,
+    exports: Vec<Name>,
+    imported: Vec<Imported>,
+}
+
+struct Linker {
+    root: PathBuf,
+    /// The directory of the standard library, `bundled::ROOT` for the bundled
+    /// one.
+    std: Option<PathBuf>,
+    loaded: Vec<Loaded>,
+    paths: HashMap<PathBuf, usize>,
+    /// The modules being loaded, each imported by the one before.
+    loading: Vec<PathBuf>,
+    errors: Vec<String>,
+    /// The source of a module in place of its file.
+    overlay: Option<(PathBuf, String)>,
+    /// The modules of the programs the script is linked after, which are not
+    /// linked again.
+    linked: HashSet<PathBuf>,
+    /// The names those programs define, in scope in the script.
+    before: Vec<(Name, Option<String>)>,
+    /// The fixities those programs declare, in effect in the script.
+    fixities: Vec<FixityDef>,
+}
+
+impl Linker {
+    fn new(loader: &Loader) -> Self {
+        let canonical = |path: &PathBuf| path.canonicalize().unwrap_or_else(|_| path.clone());
+
+        Self {
+            root: canonical(&loader.root),
+            std: loader.std.as_ref().map(|std| match std {
+                Std::Dir(dir) => canonical(dir),
+                Std::Bundled => PathBuf::from(bundled::ROOT),
+            }),
+            loaded: Vec::new(),
+            paths: HashMap::new(),
+            loading: Vec::new(),
+            errors: Vec::new(),
+            overlay: None,
+            linked: HashSet::new(),
+            before: Vec::new(),
+            fixities: Vec::new(),
+        }
+    }
+
+    /// Links the module `load` loads, with the names it imports.
+    fn with_imports(
+        mut self,
+        load: impl FnOnce(&mut Self) -> Result<usize>,
+    ) -> (Result<Module>, Vec<Imported>) {
+        match load(&mut self) {
+            Ok(index) => {
+                let imported = self.loaded[index].imported.clone();
+                (self.link(), imported)
+            }
+            Err(err) => (Err(err), Vec::new()),
+        }
+    }
+
+    fn file(&mut self, path: PathBuf) -> Result<usize> {
+        if let Some(index) = self.paths.get(&path) {
+            return Ok(*index);
+        }
+
+        let name = self.name(&path);
+        let module = self
+            .source(&path)
+            .and_then(|source| parse_unassociated(&source))
+            .with_context(|| format!("failed to load {name}"))?;
+
+        self.loading.push(path.clone());
+        let index = self.module(Some(path.clone()), module)?;
+        self.loading.pop();
+        self.paths.insert(path, index);
+
+        Ok(index)
+    }
+
+    fn module(&mut self, path: Option<PathBuf>, module: Module) -> Result<usize> {
+        let name = path.as_deref().map(|path| self.name(path));
+        let dir = path
+            .as_deref()
+            .and_then(Path::parent)
+            .unwrap_or(&self.root)
+            .to_path_buf();
+        // the names and fixities linked before are in scope in the script
+        let script = path.is_none();
+
+        let mut imports = module
+            .iter()
+            .filter_map(|statement| match statement {
+                Statement::Import(import) => Some(import.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let mut targets = imports
+            .iter()
+            .map(|import| self.import(&name, &dir, import))
+            .collect::<Result<Vec<_>>>()?;
+        if let Some(prelude) = self.prelude(path.as_deref(), &targets) {
+            imports.push(Import {
+                path: "prelude".to_string(),
+                alias: None,
+                names: None,
+                span: Span::default(),
+            });
+            targets.push(self.file(prelude)?);
+        }
+
+        let imported = imports
+            .iter()
+            .zip(&targets)
+            .flat_map(|(import, target)| {
+                imported(import, &self.loaded[*target], &name, &mut self.errors)
+            })
+            .collect::<Vec<_>>();
+
+        // the fixities of the operators it imports, unless it declares its own
+        let declared = fixity_defs(&module)
+            .flat_map(|def| def.operators.iter().map(|operator| operator.0.as_str()))
+            .collect::<HashSet<_>>();
+        let operators = imported
+            .iter()
+            .filter(|imported| imported.namespace == Namespace::Value)
+            .map(|imported| (imported.path.as_ref(), imported.ident.as_str()))
+            .collect::<HashSet<_>>();
+        let fixities = targets
+            .iter()
+            .flat_map(|target| {
+                let loaded = &self.loaded[*target];
+                fixity_defs(&loaded.module).filter_map(|def| {
+                    let operators = def
+                        .operators
+                        .iter()
+                        .filter(|operator| {
+                            operators.contains(&(loaded.path.as_ref(), operator.0.as_str()))
+                        })
+                        .filter(|operator| !declared.contains(operator.0.as_str()))
+                        .cloned()
+                        .collect::<Vec<_>>();
+
+                    (!operators.is_empty()).then(|| FixityDef {
+                        operators,
+                        ..def.clone()
+                    })
+                })
+            })
+            .chain(self.fixities.iter().filter(|_| script).map(|def| {
+                FixityDef {
+                    operators: def
+                        .operators
+                        .iter()
+                        .filter(|operator| !declared.contains(operator.0.as_str()))
+                        .cloned()
+                        .collect(),
+                    ..def.clone()
+                }
+            }))
+            .collect::<Vec<_>>();
+        let module = reassociate(module, &fixities.iter().collect::<Vec<_>>())
+            .map_err(|err| anyhow!(located(&name, err)))?;
+
+        let ir1 = transform1(&module);
+        let defined = definitions(&ir1);
+        defined
+            .iter()
+            .filter(|(_, ident, _)| unqualified(ident).is_some())
+            .for_each(|(namespace, ident, span)| {
+                self.errors.push(located(
+                    &name,
+                    format!("{span}: {namespace} {ident} is defined with a qualifier"),
+                ))
+            });
+        let errors = ambiguous(&imported, &defined, |path| self.name(path));
+        self.errors
+            .extend(errors.iter().map(|error| located(&name, error)));
+
+        let before = self.before.iter().filter(|_| script);
+        let scope = imported
+            .iter()
+            .map(|imported| (imported.namespace, imported.ident.clone(), imported.span))
+            .chain(before.map(|((namespace, ident, span), _)| (*namespace, ident.clone(), *span)))
+            .map(|(namespace, ident, span)| ((namespace, ident), Definition::Imported(span)))
+            .collect::<Scope>();
+        let (_, errors) = resolve_with(&ir1, &module, scope);
+        self.errors
+            .extend(errors.iter().map(|error| located(&name, error)));
+
+        let exports = exports(&module, &defined, &name, &mut self.errors);
+        self.loaded.push(Loaded {
+            name,
+            path,
+            module,
+            defined,
+            exports,
+            imported,
+        });
+
+        Ok(self.loaded.len() - 1)
+    }
+
+    fn import(&mut self, name: &Option<String>, dir: &Path, import: &Import) -> Result<usize> {
+        let Import { path, span, .. } = import;
+        let target = self
+            .find(path, dir)
+            .ok_or_else(|| anyhow!(located(name, format!("{span}: cannot find module {path}"))))?;
+
+        if let Some(at) = self.loading.iter().position(|path| path == &target) {
+            let cycle = self.loading[at..]
+                .iter()
+                .chain([&target])
+                .map(|path| self.name(path))
+                .collect::<Vec<_>>();
+
+            bail!(located(
+                name,
+                format!("{span}: import cycle {}", cycle.join(" -> "))
+            ));
+        }
+
+        self.file(target)
+    }
+
+    fn find(&self, path: &str, dir: &Path) -> Option<PathBuf> {
+        let file = match Path::new(path).extension() {
+            Some(_) => PathBuf::from(path),
+            None => PathBuf::from(format!("{path}.pds")),
+        };
+        let candidates = match path.starts_with("./") || path.starts_with("../") {
+            true => vec![dir.join(&file)],
+            false => [Some(&self.root), self.std.as_ref()]
+                .into_iter()
+                .flatten()
+                .map(|dir| dir.join(&file))
+                .collect(),
+        };
+
+        candidates
+            .into_iter()
+            .find_map(|candidate| self.exists(candidate))
+    }
+
+    /// The path `path` is loaded from, if there is a module.
+    fn exists(&self, path: PathBuf) -> Option<PathBuf> {
+        match bundled::source(&path) {
+            Some(_) => Some(path),
+            None if path.is_file() => path.canonicalize().ok(),
+            None => None,
+        }
+    }
+
+    fn source(&self, path: &Path) -> Result<String> {
+        if let Some((_, source)) = self.overlay.as_ref().filter(|(overlay, _)| overlay == path) {
+            return Ok(source.clone());
+        }
+
+        match bundled::source(path) {
+            Some(source) => Ok(source.to_string()),
+            None => Ok(fs::read_to_string(path)?),
+        }
+    }
+
+    /// The prelude a module imports implicitly.
+    fn prelude(&self, path: Option<&Path>, targets: &[usize]) -> Option<PathBuf> {
+        let std = self.std.as_ref()?;
+        let prelude = self.exists(std.join("prelude.pds"))?;
+        let in_std = path.is_some_and(|path| path.starts_with(std));
+        let imported = targets
+            .iter()
+            .any(|target| self.paths.get(&prelude) == Some(target));
+
+        (!in_std && !imported && !self.linked.contains(&prelude)).then_some(prelude)
+    }
+
+    fn name(&self, path: &Path) -> String {
+        [Some(&self.root), self.std.as_ref()]
+            .into_iter()
+            .flatten()
+            .find_map(|dir| path.strip_prefix(dir).ok())
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    }
+
+    /// The modules in one, each after the modules it imports. A name defined
+    /// in several modules is kept by the last of them, which imports or is
+    /// linked after the others, and is renamed in the others, so that a
+    /// module sees the names it does not export and the ones it imports as
+    /// they were where they are defined.
+    fn link(self) -> Result<Module> {
+        Ok(self.linked()?.module)
+    }
+
+    /// `link`, along with what a script linked after the modules needs.
+    fn linked(self) -> Result<Linked> {
+        let Linker {
+            loaded,
+            mut errors,
+            linked,
+            before,
+            std,
+            ..
+        } = self;
+        let loaded = loaded
+            .into_iter()
+            .filter(|loaded| {
+                loaded
+                    .path
+                    .as_ref()
+                    .is_none_or(|path| !linked.contains(path))
+            })
+            .collect::<Vec<_>>();
+
+        // intrinsics are the interpreter's, they are declared once
+        let mut intrinsics = before
+            .iter()
+            .filter(|((namespace, _, _), _)| *namespace == Namespace::Inst)
+            .map(|((_, ident, _), name)| (ident.as_str(), name))
+            .collect::<HashMap<_, _>>();
+        loaded.iter().for_each(|loaded| {
+            loaded
+                .defined
+                .iter()
+                .filter(|(namespace, _, _)| *namespace == Namespace::Inst)
+                .for_each(|(namespace, ident, span)| {
+                    if let Some(other) = intrinsics.insert(ident, &loaded.name) {
+                        errors.push(already_defined(
+                            &loaded.name,
+                            *namespace,
+                            ident,
+                            span,
+                            other,
+                        ));
+                    }
+                })
+        });
+
+        // the names of the programs linked before are kept as they are, a
+        // script linked after them cannot define them again
+        let kept = before
+            .iter()
+            .filter(|((namespace, _, _), _)| *namespace != Namespace::Inst)
+            .map(|((_, ident, _), name)| (ident.as_str(), name))
+            .collect::<HashMap<_, _>>();
+        loaded
+            .iter()
+            .filter(|loaded| loaded.path.is_none())
+            .for_each(|loaded| {
+                loaded
+                    .defined
+                    .iter()
+                    .filter(|(namespace, _, _)| *namespace != Namespace::Inst)
+                    .for_each(|(namespace, ident, span)| {
+                        if let Some(other) = kept.get(ident.as_str()) {
+                            errors.push(already_defined(
+                                &loaded.name,
+                                *namespace,
+                                ident,
+                                span,
+                                other,
+                            ));
+                        }
+                    })
+            });
+        let mut seen = HashSet::new();
+        errors.retain(|error| seen.insert(error.clone()));
+
+        if !errors.is_empty() {
+            bail!(errors.join("\n"));
+        }
+
+        // the prelude keeps the names the intrinsics refer to
+        let prelude = std.map(|std| std.join("prelude.pds"));
+        let owners = loaded
+            .iter()
+            .enumerate()
+            .flat_map(|(index, loaded)| {
+                loaded
+                    .defined
+                    .iter()
+                    .filter(|(namespace, _, _)| *namespace != Namespace::Inst)
+                    .map(move |(_, ident, _)| (ident.as_str(), index))
+            })
+            .fold(HashMap::new(), |mut owners, (ident, index)| {
+                let kept = PRELUDE_NAMES.contains(&ident)
+                    && owners.get(ident).is_some_and(|owner: &usize| {
+                        prelude.is_some() && loaded[*owner].path == prelude
+                    });
+                if !kept {
+                    owners.insert(ident, index);
+                }
+
+                owners
+            });
+        let linked_name = |index: usize, ident: &str| match kept.contains_key(ident)
+            || owners.get(ident) != Some(&index)
+        {
+            true => mangle(&loaded[index].name, ident),
+            false => ident.to_string(),
+        };
+        let indices = loaded
+            .iter()
+            .enumerate()
+            .filter_map(|(index, loaded)| Some((loaded.path.as_ref()?, index)))
+            .collect::<HashMap<_, _>>();
+        let names = loaded
+            .iter()
+            .enumerate()
+            .map(|(index, loaded)| {
+                let mut names = Names::new();
+                loaded
+                    .imported
+                    .iter()
+                    .filter(|imported| imported.namespace != Namespace::Inst)
+                    .for_each(|imported| {
+                        let ident = unqualified(&imported.ident).unwrap_or(&imported.ident);
+                        let name = match imported.path.as_ref().and_then(|path| indices.get(path)) {
+                            Some(target) => linked_name(*target, ident),
+                            None => ident.to_string(),
+                        };
+                        names.insert(imported.ident.clone(), name);
+                    });
+                loaded
+                    .defined
+                    .iter()
+                    .filter(|(namespace, _, _)| *namespace != Namespace::Inst)
+                    .for_each(|(_, ident, _)| {
+                        names.insert(ident.clone(), linked_name(index, ident));
+                    });
+                names.retain(|ident, name| ident != name);
+
+                names
+            })
+            .collect::<Vec<_>>();
+
+        let paths = loaded
+            .iter()
+            .filter_map(|loaded| loaded.path.clone())
+            .collect();
+        let defined = loaded
+            .iter()
+            .zip(&names)
+            .flat_map(|(loaded, names)| {
+                loaded.defined.iter().map(|(namespace, ident, span)| {
+                    let ident = names.get(ident).unwrap_or(ident);
+                    ((*namespace, ident.clone(), *span), loaded.name.clone())
+                })
+            })
+            .collect();
+        let statements = loaded
+            .into_iter()
+            .zip(&names)
+            .flat_map(|(mut loaded, names)| {
+                rename(&mut loaded.module, names);
+                loaded.module
+            })
+            .filter(|statement| !matches!(statement, Statement::Import(_) | Statement::Export(_)))
+            .collect();
+
+        Ok(Linked {
+            module: Module { statements },
+            paths,
+            defined,
+        })
+    }
+}
+
+/// `name` of the module `module` as it is linked when another module defines
+/// it as well, `helper'lib/text`. No name that is written is so.
+fn mangle(module: &Option<String>, name: &str) -> String {
+    let module = module.as_deref().unwrap_or("script");
+
+    format!("{name}'{}", module.trim_end_matches(".pds"))
+}
+
+fn already_defined(
+    module: &Option<String>,
+    namespace: Namespace,
+    ident: &str,
+    span: &Span,
+    other: &Option<String>,
+) -> String {
+    located(
+        module,
+        format!(
+            "{span}: {namespace} {ident} is already defined in {}",
+            other.as_deref().unwrap_or("the script")
+        ),
+    )
+}
+
+/// The errors about the names a module imports from two modules, unless it
+/// defines them itself.
+fn ambiguous(
+    imported: &[Imported],
+    defined: &[Name],
+    module_name: impl Fn(&Path) -> String,
+) -> Vec<String> {
+    let defined = defined
+        .iter()
+        .map(|(namespace, ident, _)| (*namespace, ident.as_str()))
+        .collect::<HashSet<_>>();
+    let mut paths = HashMap::new();
+
+    imported
+        .iter()
+        .filter(|imported| !defined.contains(&(imported.namespace, imported.ident.as_str())))
+        .filter_map(|imported| {
+            let key = (imported.namespace, imported.ident.as_str());
+            let other = paths.insert(key, &imported.path)?;
+
+            (other != &imported.path).then(|| {
+                format!(
+                    "{}: {} {} is imported from both {} and {}",
+                    imported.span,
+                    imported.namespace,
+                    imported.ident,
+                    other.as_deref().map(&module_name).unwrap_or_default(),
+                    imported
+                        .path
+                        .as_deref()
+                        .map(&module_name)
+                        .unwrap_or_default(),
+                )
+            })
+        })
+        .collect()
+}
+
+fn fixity_defs(module: &Module) -> impl Iterator<Item = &FixityDef> {
+    module.iter().filter_map(|statement| match statement {
+        Statement::FixityDef(def) => Some(def),
+        _ => None,
+    })
+}
+
+/// The names `import` brings in scope, those of a module imported as `Text`
+/// qualified as `Text.words`. Operators and handlers cannot be qualified, they
+/// are in scope as they are.
+fn imported(
+    import: &Import,
+    target: &Loaded,
+    name: &Option<String>,
+    errors: &mut Vec<String>,
+) -> Vec<Imported> {
+    let names = match &import.names {
+        None => target.exports.clone(),
+        Some(names) => names
+            .iter()
+            .flat_map(|module_name| {
+                let found = select(&target.module, &target.exports, module_name);
+                if found.is_empty() {
+                    errors.push(located(
+                        name,
+                        format!(
+                            "{}: {} does not export {module_name}",
+                            import.span, import.path
+                        ),
+                    ));
+                }
+
+                found
+            })
+            .collect(),
+    };
+
+    names
+        .into_iter()
+        .map(|(namespace, ident, span)| {
+            let qualifiable = !matches!(namespace, Namespace::Handler | Namespace::Inst)
+                && ident.starts_with(|c: char| c.is_ascii_alphabetic());
+            let ident = match &import.alias {
+                Some(alias) if qualifiable => format!("{}.{ident}", alias.0),
+                _ => ident,
+            };
+
+            Imported {
+                namespace,
+                ident,
+                path: target.path.clone(),
+                span,
+            }
+        })
+        .collect()
+}
+
+/// What a module exports, everything it defines when it has no `export`.
+fn exports(
+    module: &Module,
+    defined: &[Name],
+    name: &Option<String>,
+    errors: &mut Vec<String>,
+) -> Vec<Name> {
+    let exports = module
+        .iter()
+        .filter_map(|statement| match statement {
+            Statement::Export(export) => Some(export),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if exports.is_empty() {
+        return defined.to_vec();
+    }
+
+    exports
+        .into_iter()
+        .flat_map(|export| export.names.iter().map(|name| (export.span, name)))
+        .flat_map(|(span, module_name)| {
+            let found = select(module, defined, module_name);
+            if found.is_empty() {
+                errors.push(located(
+                    name,
+                    format!("{span}: cannot export undefined {module_name}"),
+                ));
+            }
+
+            found
+        })
+        .collect()
+}
+
+/// The names of `names` `module_name` stands for.
+fn select(module: &Module, names: &[Name], module_name: &ModuleName) -> Vec<Name> {
+    let (namespaces, ident, children) = match module_name {
+        ModuleName::Value(ident) => (&[Namespace::Value][..], &ident.0, Vec::new()),
+        ModuleName::Handler(ident) => (&[Namespace::Handler][..], &ident.0, Vec::new()),
+        ModuleName::Type(ident) => (
+            &[Namespace::Type, Namespace::Trait, Namespace::Constructor][..],
+            &ident.0,
+            children(module, &ident.0),
+        ),
+    };
+
+    names
+        .iter()
+        .filter(|(namespace, name, _)| {
+            (namespaces.contains(namespace) && name == ident)
+                || children.contains(&(*namespace, name.as_str()))
+        })
+        .cloned()
+        .collect()
+}
+
+/// The constructors of a data type and the methods of a trait.
+fn children<'a>(module: &'a Module, ident: &str) -> Vec<(Namespace, &'a str)> {
+    let is = |type_ident: &TypeIdent| matches!(type_ident, TypeIdent::ExistsIdent(name) if name.0 == ident);
+
+    module
+        .iter()
+        .flat_map(|statement| match statement {
+            Statement::DataAssign(def) if is(&def.ident) => def
+                .expr
+                .iter()
+                .filter_map(|value| match value {
+                    DataValue::Constructor(DataConstructor {
+                        ident: TypeIdent::ExistsIdent(constructor),
+                        ..
+                    }) => Some((Namespace::Constructor, constructor.0.as_str())),
+                    _ => None,
+                })
+                .collect(),
+            Statement::TraitDef(def) if is(&def.constructor.ident) => def
+                .where_clause
+                .iter()
+                .filter_map(|statement| match statement {
+                    Statement::AssignDef(def) => Some((Namespace::Value, def.ident.0.as_str())),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        })
+        .collect()
+}
+
+/// Prefixes `message` with the module it is about.
+fn located(name: &Option<String>, message: impl Display) -> String {
+    match name {
+        Some(name) => format!("{name}:{message}"),
+        None => message.to_string(),
+    }
+}
@@ -1,5 +1,20 @@
+mod analysis;
+mod bundled;
+mod fixity;
+mod format;
+mod loader;
 mod parser;
+mod rename;
 mod run;
+mod session;
+mod tokens;
 
+pub use self::analysis::{
+    analyze, analyze_document, Analysis, Diagnostic, Section, Severity, Symbol,
+};
+pub use self::format::format;
+pub use self::loader::Loader;
 pub use self::parser::parse;
-pub use self::run::run;
+pub use self::run::{evaluate, imports, run, run_file, run_file_with_io, run_with_io};
+pub use self::session::{Reply, Session};
+pub use self::tokens::{document_tokens, highlight, tokens, Token, TokenKind};
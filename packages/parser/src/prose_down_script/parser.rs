@@ -1,16 +1,31 @@
 use anyhow::Context;
-use pest::iterators::Pair;
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::Zero;
+use pest::error::{Error, ErrorVariant};
+use pest::iterators::{Pair, Pairs};
 use pest::Parser;
 use pest_derive::Parser;
 
+use super::fixity::reassociate;
 use crate::ast::{
-    Abstruction, Apply, ApplyEff, ApplyInst, Assign, AssignArgs, AssignDef, CoroutineType,
-    DataAssign, DataConstructor, DataExpr, DataModifier, DataValue, EtaEnv, EtaEnvs, ExistsIdent,
-    Expr, ForallIdent, HandlerAssign, HandlerDef, HandlerIdent, HandlerTypeDefExpr, Ident,
-    ImplTrait, InstDef, InstIdent, LineComment, Literal, Module, PatternExpr, Statement,
-    TraitConstraint, TraitDef, TraitIdent, TypeAbstructionExpr, TypeExpr, TypeIdent, TypeLiteral,
+    Abstruction, Apply, ApplyEff, ApplyInst, Assign, AssignArgs, AssignDef, Associativity,
+    CoroutineType, DataAssign, DataConstructor, DataExpr, DataModifier, DataValue, EtaEnv, EtaEnvs,
+    ExistsIdent, Export, Expr, FixityDef, ForallIdent, Handle, HandlerAssign, HandlerDef,
+    HandlerIdent, HandlerTypeDefExpr, Ident, ImplTrait, Import, InstDef, InstIdent, Lambda,
+    LineComment, Literal, Module, ModuleName, PatternExpr, Span, Statement, TraitConstraint,
+    TraitDef, TraitIdent, TypeAbstructionExpr, TypeExpr, TypeIdent, TypeLiteral,
 };
 
+fn parse_span(pair: &Pair<Rule>) -> Span {
+    let span = pair.as_span();
+
+    Span {
+        start: span.start_pos().line_col(),
+        end: span.end_pos().line_col(),
+    }
+}
+
 fn unary(pair: Pair<Rule>) -> Pair<Rule> {
     pair.into_inner().next().unwrap()
 }
@@ -42,12 +57,6 @@ fn quadruple(pair: Pair<Rule>) -> (Pair<Rule>, Pair<Rule>, Pair<Rule>, Pair<Rule
     )
 }
 
-fn unary_or_binary(pair: Pair<Rule>) -> (Pair<Rule>, Option<Pair<Rule>>) {
-    let mut pairs = pair.into_inner();
-
-    (pairs.next().unwrap(), pairs.next())
-}
-
 fn unary_or_none(pair: Pair<Rule>) -> Option<Pair<Rule>> {
     let mut pairs = pair.into_inner();
 
@@ -60,7 +69,14 @@ fn unary_or_none(pair: Pair<Rule>) -> Option<Pair<Rule>> {
 pub struct PdsParser;
 
 pub fn parse(input: &str) -> anyhow::Result<Module> {
+    reassociate(parse_unassociated(input)?, &[])
+}
+
+/// Parses without reassociating operator chains, which waits for the fixity
+/// declarations of the imported modules.
+pub(super) fn parse_unassociated(input: &str) -> anyhow::Result<Module> {
     let stmts = PdsParser::parse(Rule::root, input)
+        .and_then(check_denominators)
         .context("parse error")?
         .map(parse_stmt)
         .collect::<Vec<_>>();
@@ -68,6 +84,63 @@ pub fn parse(input: &str) -> anyhow::Result<Module> {
     Ok(Module { statements: stmts })
 }
 
+/// Parses an expression on its own as the statement `ident = expr`, without
+/// reassociating it.
+pub(super) fn parse_expr_as(ident: &str, input: &str) -> anyhow::Result<Module> {
+    let pair = PdsParser::parse(Rule::exprRoot, input)
+        .and_then(check_denominators)
+        .context("parse error")?
+        .next()
+        .unwrap();
+    let assign = Assign {
+        ident: Ident(ident.to_string()),
+        args: AssignArgs {
+            patterns: Vec::new(),
+        },
+        span: parse_span(&pair),
+        expr: parse_expr(pair),
+        where_clause: Module {
+            statements: Vec::new(),
+        },
+    };
+
+    Ok(Module {
+        statements: vec![Statement::Assign(assign)],
+    })
+}
+
+/// Rejects a rational literal with a zero denominator, which the grammar takes
+/// so that `1/0r` is not read as `1 / 0r`.
+fn check_denominators(pairs: Pairs<Rule>) -> Result<Pairs<Rule>, Error<Rule>> {
+    let zero = pairs
+        .clone()
+        .flatten()
+        .filter(|pair| pair.as_rule() == Rule::rationalLiteral)
+        .find(|pair| {
+            let (_, denominator) = split_rational(pair.as_str());
+            denominator
+                .parse::<BigInt>()
+                .is_ok_and(|value| value.is_zero())
+        });
+
+    match zero {
+        Some(pair) => Err(Error::new_from_span(
+            ErrorVariant::CustomError {
+                message: "zero denominator".to_string(),
+            },
+            pair.as_span(),
+        )),
+        None => Ok(pairs),
+    }
+}
+
+/// The numerator and the denominator of `3/4r` or `2r`.
+fn split_rational(value: &str) -> (&str, &str) {
+    let value = value.trim_end_matches('r');
+
+    value.split_once('/').unwrap_or((value, "1"))
+}
+
 pub fn parse_module(pair: Pair<Rule>) -> Module {
     let module = pair.into_inner().map(parse_stmt).collect::<Vec<_>>();
 
@@ -84,6 +157,9 @@ fn parse_stmt(pair: Pair<Rule>) -> Statement {
         Rule::handlerTypeDef => Statement::HandlerDef(parse_handler_def(pair)),
         Rule::handlerAssign => Statement::HandlerAssign(parse_handler_assign(pair)),
         Rule::dataAssign => Statement::DataAssign(parse_data_assign(pair)),
+        Rule::fixityDef => Statement::FixityDef(parse_fixity_def(pair)),
+        Rule::importDef => Statement::Import(parse_import(pair)),
+        Rule::exportDef => Statement::Export(parse_export(pair)),
         Rule::lineComment => Statement::LineComment(parse_line_comment(pair)),
         Rule::stmt => parse_stmt(unary(pair)),
         _ => panic!("{pair}"),
@@ -91,6 +167,7 @@ fn parse_stmt(pair: Pair<Rule>) -> Statement {
 }
 
 fn parse_impl_trait(pair: Pair<Rule>) -> ImplTrait {
+    let span = parse_span(&pair);
     let (constraints, ident, args, where_clause) = quadruple(pair);
 
     ImplTrait {
@@ -101,10 +178,12 @@ fn parse_impl_trait(pair: Pair<Rule>) -> ImplTrait {
         ident: parse_type_ident(ident),
         args: args.into_inner().map(parse_type_ident).collect::<Vec<_>>(),
         where_clause: parse_where_clause(where_clause),
+        span,
     }
 }
 
 fn parse_data_assign(pair: Pair<Rule>) -> DataAssign {
+    let span = parse_span(&pair);
     let (modifier, constraints, ident, args, expr) = {
         let mut pairs = pair.into_inner();
 
@@ -126,6 +205,7 @@ fn parse_data_assign(pair: Pair<Rule>) -> DataAssign {
         ident: parse_type_ident(ident),
         args: args.into_inner().map(parse_type_ident).collect::<Vec<_>>(),
         expr: parse_data_expr(expr),
+        span,
     }
 }
 
@@ -170,11 +250,13 @@ fn parse_data_value(pair: Pair<Rule>) -> DataExpr {
 }
 
 fn parse_handler_def(pair: Pair<Rule>) -> HandlerDef {
+    let span = parse_span(&pair);
     let (ident, expr) = binary(pair);
 
     HandlerDef {
         ident: parse_handler_ident(ident),
         expr: parse_handler_type_def_expr(expr),
+        span,
     }
 }
 
@@ -192,15 +274,18 @@ fn parse_handler_type_def_expr(pair: Pair<Rule>) -> HandlerTypeDefExpr {
 }
 
 fn parse_inst_def(pair: Pair<Rule>) -> InstDef {
+    let span = parse_span(&pair);
     let (ident, expr) = binary(pair);
 
     InstDef {
         ident: parse_inst_ident(ident),
         expr: parse_type_expr(expr),
+        span,
     }
 }
 
 fn parse_trait_def(pair: Pair<Rule>) -> TraitDef {
+    let span = parse_span(&pair);
     let (constraints, ident, args, where_clause) = quadruple(pair);
 
     TraitDef {
@@ -217,6 +302,7 @@ fn parse_trait_def(pair: Pair<Rule>) -> TraitDef {
                 .collect::<Vec<_>>(),
         },
         where_clause: parse_where_clause(where_clause),
+        span,
     }
 }
 
@@ -252,118 +338,283 @@ fn parse_handler_type_expr(pair: Pair<Rule>) -> CoroutineType {
 }
 
 fn parse_trait_constraint(pair: Pair<Rule>) -> TraitConstraint {
-    let (ident, arg) = binary(pair);
+    let mut pairs = pair.into_inner();
 
     TraitConstraint {
-        ident: parse_trait_ident(ident),
-        arg: parse_forall_ident(arg),
+        ident: parse_trait_ident(pairs.next().unwrap()),
+        args: pairs.map(parse_forall_ident).collect(),
+    }
+}
+
+fn parse_import(pair: Pair<Rule>) -> Import {
+    let span = parse_span(&pair);
+    let (path, alias, names) = triple(pair);
+
+    Import {
+        path: unary(path).as_str().to_string(),
+        alias: unary_or_none(alias).map(|alias| ExistsIdent(alias.as_str().to_string())),
+        // `import "lib" ()` imports nothing, unlike `import "lib"`
+        names: match names.as_str().is_empty() {
+            true => None,
+            false => Some(names.into_inner().map(parse_module_name).collect()),
+        },
+        span,
+    }
+}
+
+fn parse_export(pair: Pair<Rule>) -> Export {
+    let span = parse_span(&pair);
+
+    Export {
+        names: pair.into_inner().map(parse_module_name).collect(),
+        span,
     }
 }
 
+fn parse_module_name(pair: Pair<Rule>) -> ModuleName {
+    let pair = unary(pair);
+
+    match pair.as_rule() {
+        Rule::handlerIdent => ModuleName::Handler(parse_handler_ident(pair)),
+        Rule::moduleAlias => ModuleName::Type(ExistsIdent(pair.as_str().to_string())),
+        _ => ModuleName::Value(parse_ident(pair)),
+    }
+}
+
+// without the indent and the line break
 fn parse_line_comment(pair: Pair<Rule>) -> LineComment {
-    LineComment(pair.to_string())
+    let (line, column) = pair.as_span().start_pos().line_col();
+    let indent = pair.as_str().find("//").unwrap_or_default();
+    let text = pair.as_str()[indent..].trim_end_matches(['\n', '\r']);
+
+    LineComment {
+        text: text.to_string(),
+        span: Span {
+            start: (line, column + indent),
+            end: (line, column + indent + text.chars().count()),
+        },
+    }
 }
 
 fn parse_expr(pair: Pair<Rule>) -> Expr {
     match pair.as_rule() {
         Rule::literal => Expr::Literal(parse_literal(unary(pair))),
         Rule::apply => parse_apply(pair),
+        Rule::applyPrefixOp => parse_apply_prefix_op(pair),
         Rule::instApply => parse_inst_apply(pair),
         Rule::effApply => parse_eff_apply(pair),
+        Rule::handle => parse_handle(pair),
+        Rule::lambda => parse_lambda(pair),
         Rule::variable => Expr::Ident(parse_ident(unary(pair))),
-        Rule::expr => parse_expr(unary(pair)),
+        Rule::instIdent => Expr::InstIdent(parse_inst_ident(pair)),
+        Rule::typeIdent => Expr::TypeIdent(parse_type_ident(pair)),
+        Rule::expr => parse_chain(pair),
+        Rule::operand => parse_expr(unary(pair)),
+        Rule::parenExpr | Rule::tupleExpr => parse_paren(pair),
         Rule::infixIdentSeparator => Expr::Ident(parse_ident(pair)),
         Rule::infixOperator => Expr::Ident(parse_ident(pair)),
         _ => unreachable!("{pair}"),
     }
 }
 
+fn apply(f: Expr, arg: Expr, span: Span) -> Expr {
+    Expr::Apply(Apply {
+        abstruction: Abstruction {
+            arg: None,
+            expr: Box::new(f),
+        },
+        expr: Box::new(arg),
+        span,
+    })
+}
+
+/// Applies `head` to each of `args` in turn, each application spans from
+/// `start` to its argument.
+fn apply_all<'a>(head: Expr, start: Span, args: impl Iterator<Item = Pair<'a, Rule>>) -> Expr {
+    args.fold(head, |f, arg| {
+        let span = Span {
+            end: parse_span(&arg).end,
+            ..start
+        };
+
+        apply(f, parse_expr(arg), span)
+    })
+}
+
+// `f x y` is `(f x) y`
 fn parse_apply(pair: Pair<Rule>) -> Expr {
-    let pair = unary(pair);
-    match pair.as_rule() {
-        Rule::applyPrefix => parse_apply_prefix(unary(pair)),
-        Rule::applyInfix => parse_apply_infix(pair),
-        Rule::apply => parse_apply(unary(pair)),
-        _ => unreachable!("{pair}"),
-    }
+    let span = parse_span(&pair);
+    let mut pairs = pair.into_inner();
+    let head = parse_expr(pairs.next().unwrap());
+
+    apply_all(head, span, pairs)
 }
 
-fn parse_apply_prefix(pair: Pair<Rule>) -> Expr {
-    let (ident, expr) = unary_or_binary(pair);
+fn parse_apply_prefix_op(pair: Pair<Rule>) -> Expr {
+    let span = parse_span(&pair);
+    let (op, term) = binary(pair);
 
-    match expr {
-        Some(expr) => Expr::Apply(Apply {
+    apply(Expr::Ident(parse_ident(op)), parse_expr(term), span)
+}
+
+// `a + b * c` is `a + (b * c)` until the fixity declarations associate it
+fn parse_chain(pair: Pair<Rule>) -> Expr {
+    let mut pairs = pair.into_inner().rev();
+    let last = parse_expr(pairs.next().unwrap());
+
+    std::iter::from_fn(|| Some((pairs.next()?, pairs.next()?))).fold(last, |rhs, (op, lhs)| {
+        let span = parse_span(&op);
+
+        Expr::Apply(Apply {
             abstruction: Abstruction {
-                arg: None,
-                expr: Box::new(parse_expr(ident)),
+                arg: Some(Box::new(parse_expr(lhs))),
+                expr: Box::new(parse_expr(op)),
             },
-            expr: Box::new(parse_expr(expr)),
-        }),
-        None => Expr::Ident(parse_ident(ident)),
-    }
+            expr: Box::new(rhs),
+            span,
+        })
+    })
 }
 
-fn parse_apply_infix(pair: Pair<Rule>) -> Expr {
-    let (lhs, ident, rhs) = triple(pair);
+/// `()` and the tuples are literals, an operator chain in parentheses is a
+/// group.
+fn parse_paren(pair: Pair<Rule>) -> Expr {
+    let mut pairs = pair.into_inner().collect::<Vec<_>>();
+    let trailing = pairs
+        .last()
+        .is_some_and(|pair| pair.as_rule() == Rule::tupleComma);
+    if trailing {
+        pairs.pop();
+    }
 
-    Expr::Apply(Apply {
-        abstruction: Abstruction {
-            arg: Some(Box::new(parse_expr(lhs))),
-            expr: Box::new(parse_expr(ident)),
-        },
-        expr: Box::new(parse_expr(rhs)),
-    })
+    match (pairs.as_slice(), trailing) {
+        ([], _) => Expr::Literal(Literal::Unit),
+        ([expr], false) if expr.clone().into_inner().count() > 1 => {
+            Expr::Group(Box::new(parse_expr(expr.clone())))
+        }
+        ([expr], false) => parse_expr(expr.clone()),
+        _ => {
+            let items = pairs.into_iter().map(parse_expr).collect::<Vec<_>>();
+
+            Expr::Literal(Literal::Tuple(items.len(), items))
+        }
+    }
 }
 
 fn parse_inst_apply(pair: Pair<Rule>) -> Expr {
-    let (ident, expr) = unary_or_binary(pair);
-
-    match expr {
-        Some(expr) => Expr::ApplyInst(ApplyInst {
-            ident: parse_inst_ident(ident),
-            expr: Box::new(parse_expr(expr)),
-        }),
-        None => Expr::InstIdent(parse_inst_ident(ident)),
+    let start = parse_span(&pair);
+    let mut pairs = pair.into_inner();
+    let ident = parse_inst_ident(pairs.next().unwrap());
+
+    match pairs.next() {
+        Some(expr) => {
+            let span = Span {
+                end: parse_span(&expr).end,
+                ..start
+            };
+            let head = Expr::ApplyInst(ApplyInst {
+                ident,
+                expr: Box::new(parse_expr(expr)),
+                span,
+            });
+
+            apply_all(head, start, pairs)
+        }
+        None => Expr::InstIdent(ident),
     }
 }
 
 fn parse_eff_apply(pair: Pair<Rule>) -> Expr {
-    let (ident, expr) = unary_or_binary(pair);
-
-    match expr {
-        Some(expr) => Expr::ApplyEff(ApplyEff {
-            ident: parse_handler_ident(ident),
-            expr: Box::new(parse_expr(expr)),
-        }),
-        None => Expr::HandlerIdent(parse_handler_ident(ident)),
+    let start = parse_span(&pair);
+    let mut pairs = pair.into_inner();
+    let ident = parse_handler_ident(pairs.next().unwrap());
+
+    match pairs.next() {
+        Some(expr) => {
+            let span = Span {
+                end: parse_span(&expr).end,
+                ..start
+            };
+            let head = Expr::ApplyEff(ApplyEff {
+                ident,
+                expr: Box::new(parse_expr(expr)),
+                span,
+            });
+
+            apply_all(head, start, pairs)
+        }
+        None => Expr::HandlerIdent(ident),
     }
 }
 
+fn parse_handle(pair: Pair<Rule>) -> Expr {
+    let mut pairs = pair.into_inner();
+
+    Expr::Handle(Handle {
+        expr: Box::new(parse_expr(pairs.next().unwrap())),
+        handlers: pairs.map(parse_handler_assign).collect(),
+    })
+}
+
+fn parse_lambda(pair: Pair<Rule>) -> Expr {
+    let (params, expr) = binary(pair);
+
+    Expr::Abstruction(Lambda {
+        params: params.into_inner().map(parse_pattern).collect(),
+        expr: Box::new(parse_expr(expr)),
+    })
+}
+
 fn parse_literal(pair: Pair<Rule>) -> Literal {
     match pair.as_rule() {
-        Rule::textLiteral => Literal::Text(pair.into_inner().as_str().to_string()),
+        Rule::textLiteral => Literal::Text(unescape(pair.into_inner().as_str())),
         Rule::charLiteral => {
-            let mut p = pair.as_str().chars();
-            p.next();
+            let value = pair.as_str();
 
-            Literal::Char(p.next().unwrap())
+            Literal::Char(unescape(&value[1..value.len() - 1]).chars().next().unwrap())
         }
         Rule::intLiteral => Literal::Int(pair.as_str().parse::<isize>().unwrap()),
-        Rule::unitLiteral => Literal::Unit,
+        Rule::floatLiteral => Literal::Float(pair.as_str().parse::<f64>().unwrap()),
+        Rule::integerLiteral => {
+            Literal::Integer(pair.as_str().trim_end_matches('n').parse().unwrap())
+        }
+        Rule::rationalLiteral => {
+            let (numerator, denominator) = split_rational(pair.as_str());
+
+            Literal::Rational(BigRational::new(
+                numerator.parse().unwrap(),
+                denominator.parse().unwrap(),
+            ))
+        }
         Rule::arrayLiteral => Literal::Array(pair.into_inner().map(parse_expr).collect::<Vec<_>>()),
-        Rule::tupleLiteral => parse_tuple(unary(pair)),
         Rule::literal => parse_literal(unary(pair)),
         _ => panic!("{pair}"),
     }
 }
 
-fn parse_tuple(pair: Pair<Rule>) -> Literal {
-    let tuple = pair.into_inner().map(parse_expr).collect::<Vec<_>>();
+fn unescape(value: &str) -> String {
+    let mut chars = value.chars();
+    let mut ret = String::with_capacity(value.len());
+
+    while let Some(c) = chars.next() {
+        ret.push(match c {
+            '\\' => match chars.next() {
+                Some('n') => '\n',
+                Some('r') => '\r',
+                Some('t') => '\t',
+                Some('0') => '\0',
+                Some(c) => c,
+                None => '\\',
+            },
+            c => c,
+        });
+    }
 
-    Literal::Tuple(tuple.len(), tuple)
+    ret
 }
 
 fn parse_assign(pair: Pair<Rule>) -> Assign {
+    let span = parse_span(&pair);
     let (ident, args, expr, where_clause) = quadruple(pair);
 
     Assign {
@@ -371,10 +622,12 @@ fn parse_assign(pair: Pair<Rule>) -> Assign {
         args: parse_assign_args(args),
         expr: parse_expr(expr),
         where_clause: parse_where_clause(where_clause),
+        span,
     }
 }
 
 fn parse_handler_assign(pair: Pair<Rule>) -> HandlerAssign {
+    let span = parse_span(&pair);
     let (ident, args, expr, where_clause) = quadruple(pair);
 
     HandlerAssign {
@@ -382,6 +635,7 @@ fn parse_handler_assign(pair: Pair<Rule>) -> HandlerAssign {
         args: parse_assign_args(args),
         expr: parse_expr(expr),
         where_clause: parse_where_clause(where_clause),
+        span,
     }
 }
 
@@ -393,33 +647,59 @@ fn parse_assign_args(pair: Pair<Rule>) -> AssignArgs {
 
 fn parse_pattern(pair: Pair<Rule>) -> PatternExpr {
     match pair.as_rule() {
-        Rule::patternOr => {
-            let (lhs, rhs) = binary(pair);
-            PatternExpr::Or(
-                Box::new(parse_pattern(lhs)),
-                Box::new(parse_pattern(unary(rhs))),
-            )
-        }
-        Rule::patternLiteral => PatternExpr::Literal(parse_literal(unary(pair))),
+        Rule::patternLiteral => match parse_expr(unary(pair)) {
+            Expr::Literal(literal) => PatternExpr::Literal(literal),
+            expr => unreachable!("{expr:?}"),
+        },
         Rule::patternIdent => PatternExpr::Bind(parse_ident(unary(pair))),
         Rule::patternListHead => {
-            todo!()
+            let (head, tail) = binary(pair);
+            PatternExpr::ListHead(Box::new(parse_pattern(head)), Box::new(parse_pattern(tail)))
         }
         Rule::patternAny => PatternExpr::Any,
         Rule::patternConstructor => {
             PatternExpr::Constructor(parse_type_expr_constructor(unary(pair)))
         }
-        Rule::pattern => parse_pattern(unary(pair)),
+        Rule::pattern => {
+            let mut pairs = pair.into_inner();
+            let lhs = parse_pattern(pairs.next().unwrap());
+
+            match pairs.next() {
+                Some(or) => {
+                    PatternExpr::Or(Box::new(lhs), Box::new(parse_pattern(unary(unary(or)))))
+                }
+                None => lhs,
+            }
+        }
         _ => unreachable!("{pair}"),
     }
 }
 
+fn parse_fixity_def(pair: Pair<Rule>) -> FixityDef {
+    let span = parse_span(&pair);
+    let mut pairs = pair.into_inner();
+    let associativity = match pairs.next().unwrap().as_str() {
+        "infixl" => Associativity::Left,
+        "infixr" => Associativity::Right,
+        _ => Associativity::None,
+    };
+
+    FixityDef {
+        associativity,
+        precedence: pairs.next().unwrap().as_str().parse().unwrap(),
+        operators: pairs.map(parse_ident).collect(),
+        span,
+    }
+}
+
 fn parse_assign_def(pair: Pair<Rule>) -> AssignDef {
+    let span = parse_span(&pair);
     let (ident, type_expr) = binary(pair);
 
     AssignDef {
         ident: parse_ident(ident),
         expr: parse_type_expr(type_expr),
+        span,
     }
 }
 
@@ -470,9 +750,7 @@ fn parse_type_literal(pair: Pair<Rule>) -> TypeLiteral {
             TypeLiteral::Array(Box::new(parse_type_abstruction_expr(unary(unary(pair)))))
         }
         Rule::typeExprDivergent => TypeLiteral::Bottom,
-        Rule::typeExprConstructor => {
-            TypeLiteral::Constructor(parse_type_expr_constructor(unary(pair)))
-        }
+        Rule::typeExprConstructor => TypeLiteral::Constructor(parse_type_expr_constructor(pair)),
         Rule::dataValueConstructor => TypeLiteral::Constructor(parse_type_expr_constructor(pair)),
         Rule::typeIdent => TypeLiteral::Constructor(parse_type_expr_constructor(pair)),
         Rule::typeExprCoroutine => {
@@ -504,11 +782,29 @@ fn parse_type_expr_constructor(pair: Pair<Rule>) -> DataConstructor {
             ident: parse_type_ident(pair),
             args: Vec::new(),
         },
-        Rule::typeExprConstructor => parse_type_expr_constructor(unary(pair)),
+        Rule::typeExprConstructor => {
+            let mut pairs = pair.into_inner();
+
+            DataConstructor {
+                modifier: None,
+                ident: parse_type_ident(pairs.next().unwrap()),
+                args: pairs.map(parse_type_arg).collect::<Vec<_>>(),
+            }
+        }
         _ => unreachable!("{pair}"),
     }
 }
 
+fn parse_type_arg(pair: Pair<Rule>) -> TypeLiteral {
+    match pair.as_rule() {
+        Rule::typeExprGroup => match parse_type_abstruction_expr(unary(pair)) {
+            TypeAbstructionExpr::Literal(literal) => literal,
+            expr => TypeLiteral::Abstruction(Box::new(expr)),
+        },
+        _ => parse_type_literal(pair),
+    }
+}
+
 fn parse_type_expr_coroutine(pair: Pair<Rule>) -> CoroutineType {
     let (resume, ret) = binary(pair);
 
@@ -520,9 +816,10 @@ fn parse_type_expr_coroutine(pair: Pair<Rule>) -> CoroutineType {
 
 fn parse_ident(pair: Pair<Rule>) -> Ident {
     match pair.as_rule() {
-        Rule::ident => Ident(pair.as_str().to_string()),
+        Rule::ident => parse_ident(unary(pair)),
         Rule::varIdent => Ident(pair.as_str().to_string()),
         Rule::infixOperator => parse_ident(unary(pair)),
+        Rule::infixIdent => parse_ident(pair.into_inner().nth(1).unwrap()),
         Rule::operator => Ident(pair.as_str().to_string()),
         _ => panic!("{pair}"),
     }
@@ -540,7 +837,8 @@ fn parse_type_ident(pair: Pair<Rule>) -> TypeIdent {
 fn parse_handler_ident(pair: Pair<Rule>) -> HandlerIdent {
     match pair.as_rule() {
         Rule::handlerIdent => HandlerIdent(pair.as_str().to_string()),
-        Rule::etaHandlerIdent => HandlerIdent(pair.as_str().to_string()),
+        // `<write: ...>` is the handler `*write`
+        Rule::etaHandlerIdent => HandlerIdent(format!("*{}", pair.as_str())),
         _ => panic!("{pair}"),
     }
 }
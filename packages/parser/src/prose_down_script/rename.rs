@@ -0,0 +1,227 @@
+use crate::ast::{
+    Abstruction, Apply, ApplyEff, ApplyInst, CoroutineType, DataConstructor, DataExpr, DataValue,
+    EtaEnvs, ExistsIdent, Expr, Handle, HandlerAssign, Lambda, Literal, Module, PatternExpr,
+    Statement, TraitConstraint, TypeAbstructionExpr, TypeExpr, TypeIdent, TypeLiteral,
+};
+use std::collections::HashMap;
+
+/// The names of a module as it refers to them, `Text.words` or `helper`, and
+/// what they are in the linked program.
+pub(super) type Names = HashMap<String, String>;
+
+/// Renames the names in `module` that `names` has, where they are defined and
+/// where they are referred to. A value, a type and a handler are told apart
+/// by how they are written, so a name stands for one of them. Type variables
+/// and intrinsics are kept as they are.
+pub(super) fn rename(module: &mut Module, names: &Names) {
+    module
+        .statements
+        .iter_mut()
+        .for_each(|statement| self::statement(statement, names));
+}
+
+fn statement(statement: &mut Statement, names: &Names) {
+    match statement {
+        Statement::Assign(assign) => {
+            name(&mut assign.ident.0, names);
+            assign
+                .args
+                .patterns
+                .iter_mut()
+                .for_each(|pattern| self::pattern(pattern, names));
+            expr(&mut assign.expr, names);
+            rename(&mut assign.where_clause, names);
+        }
+        Statement::AssignDef(def) => {
+            name(&mut def.ident.0, names);
+            type_expr(&mut def.expr, names);
+        }
+        Statement::HandlerDef(def) => {
+            name(&mut def.ident.0, names);
+            constraints(&mut def.expr.trait_constraints, names);
+            eta_envs(&mut def.expr.eta_envs, names);
+            type_abstruction(&mut def.expr.expr, names);
+        }
+        Statement::HandlerAssign(assign) => handler(assign, names),
+        Statement::TraitDef(def) => {
+            constraints(&mut def.trait_constraints, names);
+            data_constructor(&mut def.constructor, names);
+            rename(&mut def.where_clause, names);
+        }
+        Statement::ImplTrait(def) => {
+            constraints(&mut def.constraints, names);
+            type_ident(&mut def.ident, names);
+            def.args.iter_mut().for_each(|arg| type_ident(arg, names));
+            rename(&mut def.where_clause, names);
+        }
+        Statement::InstDef(def) => type_expr(&mut def.expr, names),
+        Statement::DataAssign(def) => {
+            constraints(&mut def.constraints, names);
+            type_ident(&mut def.ident, names);
+            data_expr(&mut def.expr, names);
+        }
+        Statement::FixityDef(def) => def
+            .operators
+            .iter_mut()
+            .for_each(|operator| name(&mut operator.0, names)),
+        Statement::Import(_) | Statement::Export(_) | Statement::LineComment(_) => {}
+    }
+}
+
+fn handler(assign: &mut HandlerAssign, names: &Names) {
+    name(&mut assign.ident.0, names);
+    assign
+        .args
+        .patterns
+        .iter_mut()
+        .for_each(|pattern| self::pattern(pattern, names));
+    expr(&mut assign.expr, names);
+    rename(&mut assign.where_clause, names);
+}
+
+fn expr(expr: &mut Expr, names: &Names) {
+    match expr {
+        Expr::Apply(Apply {
+            abstruction: Abstruction { arg, expr: f },
+            expr: arg_expr,
+            ..
+        }) => {
+            if let Some(arg) = arg {
+                self::expr(arg, names);
+            }
+            self::expr(f, names);
+            self::expr(arg_expr, names);
+        }
+        Expr::ApplyInst(ApplyInst { expr, .. }) => self::expr(expr, names),
+        Expr::ApplyEff(ApplyEff { ident, expr, .. }) => {
+            name(&mut ident.0, names);
+            self::expr(expr, names);
+        }
+        Expr::Ident(ident) => name(&mut ident.0, names),
+        Expr::HandlerIdent(ident) => name(&mut ident.0, names),
+        Expr::TypeIdent(ident) => type_ident(ident, names),
+        Expr::Literal(Literal::Array(items) | Literal::Tuple(_, items)) => {
+            items.iter_mut().for_each(|item| self::expr(item, names))
+        }
+        Expr::Abstruction(Lambda { params, expr }) => {
+            params.iter_mut().for_each(|param| pattern(param, names));
+            self::expr(expr, names);
+        }
+        Expr::Handle(Handle { expr, handlers }) => {
+            self::expr(expr, names);
+            handlers
+                .iter_mut()
+                .for_each(|assign| handler(assign, names));
+        }
+        Expr::Group(expr) => self::expr(expr, names),
+        Expr::InstIdent(_) | Expr::Literal(_) => {}
+    }
+}
+
+fn pattern(pattern: &mut PatternExpr, names: &Names) {
+    match pattern {
+        PatternExpr::Or(lhs, rhs) | PatternExpr::ListHead(lhs, rhs) => {
+            self::pattern(lhs, names);
+            self::pattern(rhs, names);
+        }
+        PatternExpr::Literal(Literal::Array(items) | Literal::Tuple(_, items)) => {
+            items.iter_mut().for_each(|item| expr(item, names))
+        }
+        PatternExpr::Constructor(constructor) => data_constructor(constructor, names),
+        PatternExpr::Tuple(_, items) => {
+            items.iter_mut().for_each(|item| self::pattern(item, names))
+        }
+        // an argument that is named as a renamed name is renamed with it
+        PatternExpr::Bind(ident) => name(&mut ident.0, names),
+        PatternExpr::Literal(_) | PatternExpr::Any => {}
+    }
+}
+
+fn type_expr(expr: &mut TypeExpr, names: &Names) {
+    constraints(&mut expr.trait_constraints, names);
+    eta_envs(&mut expr.eta_envs, names);
+    type_abstruction(&mut expr.expr, names);
+}
+
+fn constraints(constraints: &mut [TraitConstraint], names: &Names) {
+    constraints
+        .iter_mut()
+        .for_each(|constraint| name(&mut constraint.ident.0, names));
+}
+
+fn eta_envs(eta_envs: &mut EtaEnvs, names: &Names) {
+    eta_envs.0.iter_mut().for_each(|eta_env| {
+        name(&mut eta_env.ident.0, names);
+        coroutine(&mut eta_env.expr, names);
+    });
+}
+
+fn coroutine(coroutine: &mut CoroutineType, names: &Names) {
+    type_abstruction(&mut coroutine.resume, names);
+    type_abstruction(&mut coroutine.ret, names);
+}
+
+fn type_abstruction(expr: &mut TypeAbstructionExpr, names: &Names) {
+    match expr {
+        TypeAbstructionExpr::Arrow(lhs, rhs) => {
+            type_abstruction(lhs, names);
+            type_abstruction(rhs, names);
+        }
+        TypeAbstructionExpr::Literal(literal) => type_literal(literal, names),
+    }
+}
+
+fn type_literal(literal: &mut TypeLiteral, names: &Names) {
+    match literal {
+        TypeLiteral::Array(expr) | TypeLiteral::Abstruction(expr) => type_abstruction(expr, names),
+        TypeLiteral::Constructor(constructor) => data_constructor(constructor, names),
+        TypeLiteral::Tuple(_, items) => items
+            .iter_mut()
+            .for_each(|item| type_abstruction(item, names)),
+        TypeLiteral::Coroutine(expr) => coroutine(expr, names),
+        TypeLiteral::Top | TypeLiteral::Bottom => {}
+    }
+}
+
+fn data_expr(expr: &mut DataExpr, names: &Names) {
+    match expr {
+        DataExpr::Or(lhs, rhs) => {
+            data_expr(lhs, names);
+            data_expr(rhs, names);
+        }
+        DataExpr::Value(DataValue::Constructor(constructor)) => {
+            data_constructor(constructor, names)
+        }
+        DataExpr::Value(DataValue::Unit) => {}
+    }
+}
+
+fn data_constructor(constructor: &mut DataConstructor, names: &Names) {
+    type_ident(&mut constructor.ident, names);
+    constructor
+        .args
+        .iter_mut()
+        .for_each(|arg| type_literal(arg, names));
+}
+
+fn type_ident(ident: &mut TypeIdent, names: &Names) {
+    if let TypeIdent::ExistsIdent(ExistsIdent(ident)) = ident {
+        name(ident, names);
+    }
+}
+
+fn name(ident: &mut String, names: &Names) {
+    if let Some(name) = names.get(ident.as_str()) {
+        *ident = name.clone();
+    }
+}
+
+/// `words` of `Text.words`, none of an operator such as `.` or `<.>`.
+pub(super) fn unqualified(ident: &str) -> Option<&str> {
+    let (qualifier, name) = ident.split_once('.')?;
+    let qualified = qualifier.starts_with(|c: char| c.is_ascii_uppercase())
+        && qualifier.chars().all(|c| c.is_ascii_alphanumeric())
+        && !name.is_empty();
+
+    qualified.then_some(name)
+}
@@ -1,13 +1,218 @@
-// use super::parse;
-// use crate::vm::Vm;
-use anyhow::Result;
+use super::{
+    analysis::{chunks, loader_errors, parse_chunks, program, relocate, Chunk, Diagnostic},
+    Loader,
+};
+use crate::ast::{Block, Document, Inline, Module};
+use crate::ir::{
+    ir1::transform1,
+    ir2::{transform2, IR2},
+    matching::check_matches,
+    type_check::{type_check, TypeEnv},
+    Ident,
+};
+use crate::prose_down::Part;
+use crate::vm::{Value, Vm};
+use anyhow::{anyhow, Result};
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
 
-pub fn run(_script: &str) -> Result<()> {
-    Ok(())
-    // let ast = parse(&script)?;
-    // let mut vm = Vm::default();
+pub fn run(script: &str) -> Result<Value> {
+    run_with_io(script, &mut io::stdin().lock(), &mut io::stdout())
+}
+
+pub fn run_with_io(script: &str, stdin: &mut dyn BufRead, stdout: &mut dyn Write) -> Result<Value> {
+    run_module(
+        Loader::default().load_source(script)?,
+        &"main".into(),
+        stdin,
+        stdout,
+    )
+}
+
+pub fn run_file(loader: &Loader, path: &Path) -> Result<Value> {
+    run_file_with_io(loader, path, &mut io::stdin().lock(), &mut io::stdout())
+}
+
+pub fn run_file_with_io(
+    loader: &Loader,
+    path: &Path,
+    stdin: &mut dyn BufRead,
+    stdout: &mut dyn Write,
+) -> Result<Value> {
+    run_module(loader.load(path)?, &"main".into(), stdin, stdout)
+}
+
+/// Replaces the `{expr}` inlines of `document` with what `show` makes of
+/// them, and drops its `@{...}` blocks. The blocks define what the inlines may
+/// refer to, along with every module of the standard library of `loader`.
+/// Errors are at the places in the document they are about.
+pub fn evaluate(loader: &Loader, document: &Document) -> Result<Document> {
+    let parts = parts(document);
+    if parts.is_empty() {
+        return Ok(document.clone());
+    }
+
+    let mut chunks = chunks(parts);
+    let errors = parse_chunks(&mut chunks);
+    if !errors.is_empty() {
+        return Err(anyhow!(report(errors)));
+    }
+    let module = loader
+        .load_source(&program(loader, &mut chunks)?)
+        .map_err(|err| relocated(&chunks, &err))?;
+    let (ir2, env, warnings) = check(module).map_err(|err| relocated(&chunks, &err))?;
+    warnings.into_iter().for_each(|warning| {
+        eprintln!("warning: {}", relocated(&chunks, &anyhow!(warning)));
+    });
+
+    let (mut stdin, mut stdout) = (io::empty(), io::sink());
+    let mut vm = Vm::new(&mut stdin, &mut stdout);
+    vm.load(ir2, &env);
+    let mut texts = chunks
+        .iter()
+        .filter_map(|chunk| Some((chunk.name.as_deref()?, chunk.at)))
+        .map(|(name, (line, column))| {
+            vm.evaluate(&name.into())
+                .map(|text| text.to_string())
+                .map_err(|err| anyhow!("{line}:{column}: {err:#}"))
+        })
+        .collect::<Result<Vec<_>>>()?
+        .into_iter();
+
+    let blocks = document
+        .iter()
+        .filter(|block| !matches!(block, Block::PdsScript(..)))
+        .map(|block| match block {
+            Block::Paragraph(inlines) => Block::Paragraph(
+                inlines
+                    .iter()
+                    .map(|inline| match inline {
+                        Inline::Expr(..) => Inline::Text(texts.next().unwrap_or_default()),
+                        inline => inline.clone(),
+                    })
+                    .collect(),
+            ),
+            block => block.clone(),
+        })
+        .collect();
+
+    Ok(Document(blocks))
+}
+
+/// The files of the modules `evaluate` links the scripts of `document` with,
+/// each with its source. A document without scripts links with none.
+pub fn imports(loader: &Loader, document: &Document) -> Result<Vec<(PathBuf, String)>> {
+    let parts = parts(document);
+    if parts.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut chunks = chunks(parts);
+    let errors = parse_chunks(&mut chunks);
+    if !errors.is_empty() {
+        return Err(anyhow!(report(errors)));
+    }
+
+    loader
+        .imports(&program(loader, &mut chunks)?)
+        .map_err(|err| relocated(&chunks, &err))
+}
+
+/// The `@{...}` blocks and `{expr}` inlines of `document`, where they are.
+fn parts(document: &Document) -> Vec<((usize, usize), Part)> {
+    document
+        .iter()
+        .flat_map(|block| match block {
+            Block::PdsScript(script, at) => vec![(*at, Part::Script(script.clone()))],
+            Block::Paragraph(inlines) => inlines
+                .iter()
+                .filter_map(|inline| match inline {
+                    Inline::Expr(expr, at) => Some((*at, Part::Expr(expr.clone()))),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        })
+        .collect()
+}
+
+/// The errors of `err` about the program of `chunks`, at the places in the
+/// document they are about.
+fn relocated(chunks: &[Chunk], err: &anyhow::Error) -> anyhow::Error {
+    anyhow!(report(
+        loader_errors(err, None)
+            .into_iter()
+            .map(|diagnostic| relocate(chunks, diagnostic))
+    ))
+}
+
+/// `line:column: message` for each of `diagnostics`.
+fn report(diagnostics: impl IntoIterator<Item = Diagnostic>) -> String {
+    diagnostics
+        .into_iter()
+        .map(|diagnostic| format!("{}: {}", diagnostic.span, diagnostic.message))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// `sources` after an import of every module of the standard library of
+/// `loader`.
+pub(super) fn with_std(loader: &Loader, sources: impl Iterator<Item = String>) -> Result<String> {
+    let source = loader
+        .std_modules()?
+        .iter()
+        .map(|module| format!("import \"{module}\""))
+        .chain(sources)
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Ok(source)
+}
+
+/// Runs `entry` of a module the loader has linked, its names are resolved.
+fn run_module(
+    ast: Module,
+    entry: &Ident,
+    stdin: &mut dyn BufRead,
+    stdout: &mut dyn Write,
+) -> Result<Value> {
+    let (ir2, env, warnings) = check(ast)?;
+    warnings
+        .iter()
+        .for_each(|warning| eprintln!("warning: {warning}"));
+
+    execute(ir2, &env, entry, stdin, stdout)
+}
+
+/// Types a linked module, with the warnings about its patterns.
+pub(super) fn check(ast: Module) -> Result<(IR2, TypeEnv, Vec<String>)> {
+    let ir1 = transform1(&ast);
+    let ir2 = transform2(ir1, ast)?;
+    let (env, errors) = type_check(&ir2);
+    if !errors.is_empty() {
+        return Err(anyhow!(errors
+            .iter()
+            .map(|error| error.to_string())
+            .collect::<Vec<_>>()
+            .join("\n")));
+    }
+    let warnings = check_matches(&ir2)
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+
+    Ok((ir2, env, warnings))
+}
 
-    // vm.run(ast.into());
+pub(super) fn execute(
+    ir2: IR2,
+    env: &TypeEnv,
+    entry: &Ident,
+    stdin: &mut dyn BufRead,
+    stdout: &mut dyn Write,
+) -> Result<Value> {
+    let mut vm = Vm::new(stdin, stdout);
+    vm.load(ir2, env);
 
-    // Ok(())
+    vm.evaluate(entry)
 }
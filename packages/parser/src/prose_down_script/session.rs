@@ -0,0 +1,321 @@
+use super::{
+    loader::Linked,
+    parser::{parse_expr_as, parse_unassociated},
+    run::{check, execute},
+    Loader,
+};
+use crate::ast::{Import, Module, Span, Statement};
+use crate::ir::{
+    ir1::{transform1, IR1},
+    ir2::IR2,
+    resolve::definitions,
+    type_check::TypeEnv,
+};
+use crate::vm::Value;
+use anyhow::{anyhow, Context, Result};
+use std::{
+    collections::HashSet,
+    fmt::Display,
+    fs,
+    io::{self, BufRead, Write},
+    path::PathBuf,
+};
+
+/// What an expression entered in a session is evaluated as, a name a script
+/// cannot define.
+const IT: &str = "it'";
+
+/// The state of a REPL: the file loaded last and the definitions entered
+/// since. Every module of the standard library is imported.
+///
+/// An input is either statements, which are kept when the program they are
+/// added to type checks, or an expression, which is evaluated. The definitions
+/// of an input replace those of the earlier inputs that define the same names.
+/// The standard library and the file are linked once, and an input after them
+/// and the inputs kept, so errors are at the places in the input they are
+/// about.
+pub struct Session {
+    loader: Loader,
+    file: Option<PathBuf>,
+    /// The standard library and the file.
+    base: Entry,
+    inputs: Vec<Entry>,
+}
+
+/// Statements linked after the ones before them.
+struct Entry {
+    /// The names they define, in every namespace.
+    names: HashSet<String>,
+    linked: Linked,
+    /// The warnings about their patterns, reported once.
+    warnings: HashSet<String>,
+}
+
+#[derive(Debug)]
+pub enum Reply {
+    /// Statements were added, with the warnings about their patterns.
+    Defined(Vec<String>),
+    /// The value of an expression and its type.
+    Value(Value, String),
+    Type(String),
+    /// A file was loaded, with the warnings about its patterns.
+    Loaded(PathBuf, Vec<String>),
+    Help,
+    Quit,
+}
+
+impl Session {
+    /// A session with nothing loaded, the standard library is linked first.
+    pub fn new(loader: Loader) -> Result<Self> {
+        Ok(Self {
+            base: link_base(&loader, None)?,
+            loader,
+            file: None,
+            inputs: Vec::new(),
+        })
+    }
+
+    /// Evaluates an input, effects read `stdin` and write `stdout`.
+    pub fn input(
+        &mut self,
+        input: &str,
+        stdin: &mut dyn BufRead,
+        stdout: &mut dyn Write,
+    ) -> Result<Reply> {
+        let input = input.trim();
+        let (command, arg) = match input.strip_prefix(':') {
+            Some(command) => command.split_once(' ').unwrap_or((command, "")),
+            None => return self.statements_or_expr(input, stdin, stdout),
+        };
+
+        match (command, arg.trim()) {
+            ("type" | "t", expr) => self.type_of(expr).map(Reply::Type),
+            ("load" | "l", path) if !path.is_empty() => self.load(PathBuf::from(path)),
+            ("reload" | "r", "") => match self.file.clone() {
+                Some(path) => self.load(path),
+                None => Err(anyhow!("no file is loaded")),
+            },
+            ("help" | "h" | "?", "") => Ok(Reply::Help),
+            ("quit" | "q", "") => Ok(Reply::Quit),
+            _ => Err(anyhow!("unknown command :{command}, :help lists them")),
+        }
+    }
+
+    fn statements_or_expr(
+        &mut self,
+        input: &str,
+        stdin: &mut dyn BufRead,
+        stdout: &mut dyn Write,
+    ) -> Result<Reply> {
+        let module = match parse_unassociated(input) {
+            Ok(module) if module.iter().next().is_some() => module,
+            _ => return self.evaluate(input, stdin, stdout),
+        };
+
+        let names = names(&transform1(&module));
+        let after = [&self.base]
+            .into_iter()
+            .chain(
+                self.inputs
+                    .iter()
+                    .filter(|input| input.names.is_disjoint(&names)),
+            )
+            .collect::<Vec<_>>();
+        let (linked, warnings) = link(&self.loader, &after, module)?;
+        let reported = after
+            .iter()
+            .flat_map(|entry| &entry.warnings)
+            .collect::<HashSet<_>>();
+        let warnings = warnings
+            .into_iter()
+            .filter(|warning| !reported.contains(warning))
+            .collect::<HashSet<_>>();
+
+        self.inputs.retain(|input| input.names.is_disjoint(&names));
+        self.inputs.push(Entry {
+            names,
+            linked,
+            warnings: warnings.clone(),
+        });
+        let mut warnings = warnings.into_iter().collect::<Vec<_>>();
+        warnings.sort();
+
+        Ok(Reply::Defined(warnings))
+    }
+
+    /// Runs an expression, with a line of its own for what it writes.
+    fn evaluate(
+        &mut self,
+        expr: &str,
+        stdin: &mut dyn BufRead,
+        stdout: &mut dyn Write,
+    ) -> Result<Reply> {
+        let (ir2, env) = self.check_expr(expr)?;
+        let ty = env.describe(&IT.into()).unwrap_or_default();
+        let mut lines = Lines {
+            inner: stdout,
+            open: false,
+        };
+        let value = execute(ir2, &env, &IT.into(), stdin, &mut lines);
+        if lines.open {
+            writeln!(lines.inner)?;
+        }
+
+        Ok(Reply::Value(value?, ty))
+    }
+
+    fn type_of(&self, expr: &str) -> Result<String> {
+        let (_, env) = self.check_expr(expr)?;
+
+        Ok(env.describe(&IT.into()).unwrap_or_default())
+    }
+
+    fn check_expr(&self, expr: &str) -> Result<(IR2, TypeEnv)> {
+        let module = parse_expr_as(IT, expr)?;
+        let after = [&self.base]
+            .into_iter()
+            .chain(&self.inputs)
+            .collect::<Vec<_>>();
+        let linked = self
+            .loader
+            .link_after(&links(&after), module)
+            .map_err(unnamed)?;
+        let (ir2, env, _) = check(program(&after, &linked)).map_err(unnamed)?;
+
+        Ok((ir2, env))
+    }
+
+    /// Loads a file in place of the one loaded before, and drops the
+    /// definitions entered since.
+    fn load(&mut self, path: PathBuf) -> Result<Reply> {
+        let base = fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|source| link_base(&self.loader, Some(&source)))
+            .with_context(|| format!("cannot load {}", path.display()))?;
+        let mut warnings = base.warnings.iter().cloned().collect::<Vec<_>>();
+        warnings.sort();
+
+        self.base = base;
+        self.inputs.clear();
+        self.file = Some(path.clone());
+
+        Ok(Reply::Loaded(path, warnings))
+    }
+}
+
+/// `source` after an import of every module of the standard library,
+/// linked.
+fn link_base(loader: &Loader, source: Option<&str>) -> Result<Entry> {
+    let module = source.map(parse_unassociated).transpose()?;
+    let imports = loader.std_modules()?.into_iter().map(|path| {
+        Statement::Import(Import {
+            path,
+            alias: None,
+            names: None,
+            span: Span::default(),
+        })
+    });
+    let module = Module {
+        statements: imports.chain(module.into_iter().flatten()).collect(),
+    };
+    let (linked, warnings) = link(loader, &[], module)?;
+
+    Ok(Entry {
+        names: HashSet::new(),
+        linked,
+        warnings: warnings.into_iter().collect(),
+    })
+}
+
+/// `module` linked after `after`, if the program they make type checks,
+/// with the warnings about its patterns.
+fn link(loader: &Loader, after: &[&Entry], module: Module) -> Result<(Linked, Vec<String>)> {
+    let linked = loader.link_after(&links(after), module)?;
+    let (_, _, warnings) = check(program(after, &linked))?;
+
+    Ok((linked, warnings))
+}
+
+fn links<'a>(entries: &[&'a Entry]) -> Vec<&'a Linked> {
+    entries.iter().map(|entry| &entry.linked).collect()
+}
+
+/// The statements of `after` and then those of `linked`.
+fn program(after: &[&Entry], linked: &Linked) -> Module {
+    let statements = after
+        .iter()
+        .map(|entry| &entry.linked)
+        .chain([linked])
+        .flat_map(|linked| linked.module.iter().cloned())
+        .collect();
+
+    Module { statements }
+}
+
+/// `err` without the name an expression is evaluated as.
+fn unnamed(err: anyhow::Error) -> anyhow::Error {
+    anyhow!(format!("{err:#}").replace(&format!(": in {IT}: "), ": "))
+}
+
+/// Writes to `inner`, and remembers whether the last line written is left
+/// open.
+struct Lines<'a> {
+    inner: &'a mut dyn Write,
+    open: bool,
+}
+
+impl Write for Lines<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        if let Some(last) = buf[..written].last() {
+            self.open = *last != b'\n';
+        }
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// The names a module defines, in every namespace.
+fn names(ir1: &IR1) -> HashSet<String> {
+    definitions(ir1)
+        .into_iter()
+        .map(|(namespace, ident, _)| format!("{namespace} {ident}"))
+        .collect()
+}
+
+/// `value :: type`, nothing for `()`.
+impl Display for Reply {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Reply::Defined(warnings) => write_warnings(f, warnings),
+            Reply::Value(Value::Tuple(items), _) if items.is_empty() => Ok(()),
+            Reply::Value(value, ty) => writeln!(f, "{value} :: {ty}"),
+            Reply::Type(ty) => writeln!(f, "{ty}"),
+            Reply::Loaded(path, warnings) => {
+                write_warnings(f, warnings)?;
+                writeln!(f, "loaded {}", path.display())
+            }
+            Reply::Help => f.write_str(HELP),
+            Reply::Quit => Ok(()),
+        }
+    }
+}
+
+fn write_warnings(f: &mut std::fmt::Formatter<'_>, warnings: &[String]) -> std::fmt::Result {
+    warnings
+        .iter()
+        .try_for_each(|warning| writeln!(f, "warning: {warning}"))
+}
+
+const HELP: &str = "\
+<statements>     define, replacing the definitions of the same names
+<expr>           evaluate, and print the value with its type
+:type <expr>     print the type of an expression
+:load <file>     load a file, dropping the definitions entered so far
+:reload          load the file again
+:quit            leave
+";
@@ -0,0 +1,289 @@
+use super::parser::{PdsParser, Rule};
+use crate::ast::Span;
+use crate::prose_down::{self, Part};
+use anyhow::{Context, Result};
+use pest::{iterators::Pair, Parser};
+use std::{fmt::Display, ops::Range};
+
+/// What a token of a script is, as an editor highlights it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    Keyword,
+    Comment,
+    Variable,
+    /// `*write`
+    Handler,
+    /// `@write`
+    Inst,
+    Type,
+    /// `a` of `a -> a`
+    TypeVariable,
+    Trait,
+    /// `Text` of `Text.words`
+    Module,
+    Operator,
+    Text,
+    Number,
+}
+
+/// A token on one line, `end` is after its last character.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub span: Span,
+    pub kind: TokenKind,
+}
+
+/// The symbols the grammar writes out, which are no `operator`.
+const PUNCTUATIONS: [&str; 7] = ["::", "=", "->", "=>", "|", "\\", "&"];
+
+/// The tokens of a script in source order. The parser tells what they are,
+/// whitespace and brackets are none.
+pub fn tokens(source: &str) -> Result<Vec<Token>> {
+    Ok(located(source, &classify(source, Rule::root)?, (1, 1)))
+}
+
+/// The tokens of the `@{...}` blocks and the `{expr}` inlines of a document.
+pub fn document_tokens(source: &str) -> Result<Vec<Token>> {
+    prose_down::parts(source)?
+        .into_iter()
+        .map(|(at, part)| match part {
+            Part::Script(code) => Ok(located(&code, &classify(&code, Rule::root)?, at)),
+            Part::Expr(code) => Ok(located(&code, &classify(&code, Rule::expr)?, at)),
+            Part::Paragraph(_) | Part::ThemanticBreak => Ok(Vec::new()),
+        })
+        .collect::<Result<Vec<_>>>()
+        .map(|tokens| tokens.into_iter().flatten().collect())
+}
+
+/// A script as HTML, its tokens in `<span class="pds-keyword">` and the like.
+pub fn highlight(source: &str) -> Result<String> {
+    let classified = classify(source, Rule::root)?;
+    let (html, rest) = classified
+        .iter()
+        .fold((String::new(), 0), |(html, at), (range, kind)| {
+            (
+                format!(
+                    "{html}{}<span class=\"pds-{kind}\">{}</span>",
+                    escape(&source[at..range.start]),
+                    escape(&source[range.clone()])
+                ),
+                range.end,
+            )
+        });
+
+    Ok(format!(
+        "<pre class=\"pds\"><code>{html}{}</code></pre>",
+        escape(&source[rest..])
+    ))
+}
+
+/// The byte ranges of the tokens of `source` as `rule`, in source order.
+fn classify(source: &str, rule: Rule) -> Result<Vec<(Range<usize>, TokenKind)>> {
+    let mut tokens = PdsParser::parse(rule, source)
+        .context("parse error")?
+        .flat_map(classify_pair)
+        .collect::<Vec<_>>();
+    tokens.sort_by_key(|(range, _)| range.start);
+
+    let ends = [0]
+        .into_iter()
+        .chain(tokens.iter().map(|(range, _)| range.end));
+    let starts = tokens
+        .iter()
+        .map(|(range, _)| range.start)
+        .chain([source.len()]);
+    let between = ends
+        .zip(starts)
+        .flat_map(|(start, end)| punctuations(source, start..end))
+        .collect::<Vec<_>>();
+    tokens.extend(between);
+    tokens.sort_by_key(|(range, _)| range.start);
+
+    Ok(tokens)
+}
+
+fn classify_pair(pair: Pair<Rule>) -> Vec<(Range<usize>, TokenKind)> {
+    let span = pair.as_span();
+    let token = |kind| vec![(span.start()..span.end(), kind)];
+
+    match pair.as_rule() {
+        // without the indent and the line break
+        Rule::lineComment => {
+            let text = pair.as_str();
+            let start = span.start() + text.find("//").unwrap_or_default();
+            let end = span.start() + text.trim_end_matches(['\n', '\r']).len();
+
+            vec![(start..end, TokenKind::Comment)]
+        }
+        Rule::varIdent => qualified(span.start(), pair.as_str(), TokenKind::Variable),
+        Rule::existsIdent => qualified(span.start(), pair.as_str(), TokenKind::Type),
+        Rule::traitIdent => qualified(span.start(), pair.as_str(), TokenKind::Trait),
+        Rule::forallIdent => token(TokenKind::TypeVariable),
+        Rule::handlerIdent | Rule::etaHandlerIdent => token(TokenKind::Handler),
+        Rule::instIdent => token(TokenKind::Inst),
+        Rule::moduleAlias => token(TokenKind::Module),
+        Rule::operator | Rule::infixIdent | Rule::traitOp => token(TokenKind::Operator),
+        Rule::textLiteral | Rule::charLiteral | Rule::importPath => token(TokenKind::Text),
+        Rule::intLiteral
+        | Rule::floatLiteral
+        | Rule::integerLiteral
+        | Rule::rationalLiteral
+        | Rule::fixityPrecedence => token(TokenKind::Number),
+        Rule::dataModifierNominal | Rule::dataModifierStructual | Rule::fixityAssociativity => {
+            token(TokenKind::Keyword)
+        }
+        // `Show` of `trait Show a` and `impl Show Bool` is a trait
+        Rule::traitDef | Rule::implAssign => {
+            let mut named = false;
+            pair.into_inner()
+                .flat_map(|pair| match pair.as_rule() {
+                    Rule::typeIdent | Rule::existsIdent if !named => {
+                        named = true;
+                        qualified(pair.as_span().start(), pair.as_str(), TokenKind::Trait)
+                    }
+                    _ => classify_pair(pair),
+                })
+                .collect()
+        }
+        // `r` of `area Circle r` is bound, not a type
+        Rule::patternConstructor => pair
+            .into_inner()
+            .flat_map(classify_pair)
+            .map(|(range, kind)| match kind {
+                TokenKind::TypeVariable => (range, TokenKind::Variable),
+                kind => (range, kind),
+            })
+            .collect(),
+        // `#`, `##` and `#%`
+        Rule::variable if pair.clone().into_inner().next().is_none() => token(TokenKind::Variable),
+        _ => pair.into_inner().flat_map(classify_pair).collect(),
+    }
+}
+
+/// `Text.words` as the module `Text` and the name `words`.
+fn qualified(start: usize, text: &str, kind: TokenKind) -> Vec<(Range<usize>, TokenKind)> {
+    match text.split_once('.') {
+        Some((module, _)) => vec![
+            (start..start + module.len(), TokenKind::Module),
+            (start + module.len() + 1..start + text.len(), kind),
+        ],
+        None => vec![(start..start + text.len(), kind)],
+    }
+}
+
+/// Characters of a kind in a row.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Run {
+    Word,
+    Symbol,
+    Other,
+}
+
+/// The keywords and the symbols the grammar writes out in `range`, which the
+/// parser leaves between the tokens it tells.
+fn punctuations(source: &str, range: Range<usize>) -> Vec<(Range<usize>, TokenKind)> {
+    let is_symbol = |c: char| "=:->|\\&".contains(c);
+    let class = |c: char| match c {
+        c if c.is_ascii_alphanumeric() => Run::Word,
+        c if is_symbol(c) => Run::Symbol,
+        _ => Run::Other,
+    };
+    let runs = source[range.clone()].char_indices().fold(
+        Vec::<(Range<usize>, Run)>::new(),
+        |mut runs, (at, c)| {
+            let at = range.start + at;
+            match runs.last_mut() {
+                Some((run, last)) if run.end == at && *last == class(c) => run.end += c.len_utf8(),
+                _ => runs.push((at..at + c.len_utf8(), class(c))),
+            }
+            runs
+        },
+    );
+
+    runs.into_iter()
+        .filter_map(|(run, class)| {
+            let text = &source[run.clone()];
+            let kind = match class {
+                Run::Word if is_keyword(text) => TokenKind::Keyword,
+                Run::Symbol if PUNCTUATIONS.contains(&text) => TokenKind::Operator,
+                _ => return None,
+            };
+
+            Some((run, kind))
+        })
+        .collect()
+}
+
+/// Whether the grammar reads `word` as a keyword.
+fn is_keyword(word: &str) -> bool {
+    PdsParser::parse(Rule::keywords, word)
+        .is_ok_and(|mut pairs| pairs.next().is_some_and(|pair| pair.as_str() == word))
+}
+
+/// The tokens at lines and columns, those of a source starting at `at`. A
+/// token over several lines is one on each.
+fn located(source: &str, tokens: &[(Range<usize>, TokenKind)], at: (usize, usize)) -> Vec<Token> {
+    let lines = [0]
+        .into_iter()
+        .chain(source.match_indices('\n').map(|(newline, _)| newline + 1))
+        .collect::<Vec<_>>();
+    let position = |byte: usize| {
+        let line = lines.partition_point(|start| *start <= byte) - 1;
+        let column = source[lines[line]..byte].chars().count();
+
+        match line {
+            0 => (at.0, at.1 + column),
+            _ => (at.0 + line, column + 1),
+        }
+    };
+
+    tokens
+        .iter()
+        .flat_map(|(range, kind)| {
+            source[range.clone()]
+                .split('\n')
+                .scan(range.start, |start, line| {
+                    let line = line.trim_end_matches('\r');
+                    let span = Span {
+                        start: position(*start),
+                        end: position(*start + line.len()),
+                    };
+                    *start += line.len() + 1;
+
+                    Some(span)
+                })
+                .filter(|span| span.start != span.end)
+                .map(|span| Token { span, kind: *kind })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// The class of the HTML a token is in, after `pds-`.
+impl Display for TokenKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self {
+            TokenKind::Keyword => "keyword",
+            TokenKind::Comment => "comment",
+            TokenKind::Variable => "variable",
+            TokenKind::Handler => "handler",
+            TokenKind::Inst => "inst",
+            TokenKind::Type => "type",
+            TokenKind::TypeVariable => "type-variable",
+            TokenKind::Trait => "trait",
+            TokenKind::Module => "module",
+            TokenKind::Operator => "operator",
+            TokenKind::Text => "text",
+            TokenKind::Number => "number",
+        };
+
+        write!(f, "{kind}")
+    }
+}
@@ -0,0 +1,233 @@
+use super::{Value, Vm};
+use crate::ir::{
+    type_check::{is_show, ImplKey},
+    ExistsIdent, Ident, InstIdent,
+};
+use anyhow::{anyhow, Result};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{CheckedDiv, CheckedEuclid, ToPrimitive};
+use std::cmp::Ordering;
+
+/// Intrinsics, the `@inst` values that are implemented by the interpreter.
+pub(super) struct EmbeddedEnvironment;
+
+impl EmbeddedEnvironment {
+    pub(super) fn arity(ident: &InstIdent) -> Option<usize> {
+        match ident.0.as_str() {
+            "@atomic" => Some(0),
+            "@write" | "@read" | "@show" | "@ord" | "@chr" | "@toInt" | "@toInteger"
+            | "@toFloat" | "@toRational" | "@floor" => Some(1),
+            "@add" | "@sub" | "@mul" | "@div" | "@mod" | "@compare" | "@cons" | "@append" => {
+                Some(2)
+            }
+            _ => None,
+        }
+    }
+
+    pub(super) fn exec(vm: &Vm, ident: &InstIdent, args: Vec<Value>) -> Result<Value> {
+        match (ident.0.as_str(), args.as_slice()) {
+            ("@atomic", []) => Ok(Value::unit()),
+            ("@write", [value]) => Self::write(vm, value),
+            ("@read", [_]) => Self::read(vm),
+            ("@show", [value]) => Ok(Value::Text(Self::text(value))),
+            ("@add", [Value::Int(lhs), Value::Int(rhs)]) => Self::int(ident, lhs.checked_add(*rhs)),
+            ("@sub", [Value::Int(lhs), Value::Int(rhs)]) => Self::int(ident, lhs.checked_sub(*rhs)),
+            ("@mul", [Value::Int(lhs), Value::Int(rhs)]) => Self::int(ident, lhs.checked_mul(*rhs)),
+            ("@div" | "@mod", [Value::Int(_), Value::Int(0)]) => {
+                Err(anyhow!("{}: division by zero", ident.0))
+            }
+            ("@div", [Value::Int(lhs), Value::Int(rhs)]) => {
+                Self::int(ident, lhs.checked_div_euclid(rhs))
+            }
+            ("@mod", [Value::Int(lhs), Value::Int(rhs)]) => {
+                Self::int(ident, lhs.checked_rem_euclid(rhs))
+            }
+            ("@add", [Value::Integer(lhs), Value::Integer(rhs)]) => Ok(Value::Integer(lhs + rhs)),
+            ("@sub", [Value::Integer(lhs), Value::Integer(rhs)]) => Ok(Value::Integer(lhs - rhs)),
+            ("@mul", [Value::Integer(lhs), Value::Integer(rhs)]) => Ok(Value::Integer(lhs * rhs)),
+            ("@div", [Value::Integer(lhs), Value::Integer(rhs)]) => lhs
+                .checked_div_euclid(rhs)
+                .map(Value::Integer)
+                .ok_or_else(|| anyhow!("{}: division by zero", ident.0)),
+            ("@mod", [Value::Integer(lhs), Value::Integer(rhs)]) => lhs
+                .checked_rem_euclid(rhs)
+                .map(Value::Integer)
+                .ok_or_else(|| anyhow!("{}: division by zero", ident.0)),
+            ("@add", [Value::Float(lhs), Value::Float(rhs)]) => Ok(Value::Float(lhs + rhs)),
+            ("@sub", [Value::Float(lhs), Value::Float(rhs)]) => Ok(Value::Float(lhs - rhs)),
+            ("@mul", [Value::Float(lhs), Value::Float(rhs)]) => Ok(Value::Float(lhs * rhs)),
+            ("@div", [Value::Float(lhs), Value::Float(rhs)]) => Ok(Value::Float(lhs / rhs)),
+            ("@add", [Value::Rational(lhs), Value::Rational(rhs)]) => {
+                Ok(Value::Rational(lhs + rhs))
+            }
+            ("@sub", [Value::Rational(lhs), Value::Rational(rhs)]) => {
+                Ok(Value::Rational(lhs - rhs))
+            }
+            ("@mul", [Value::Rational(lhs), Value::Rational(rhs)]) => {
+                Ok(Value::Rational(lhs * rhs))
+            }
+            ("@div", [Value::Rational(lhs), Value::Rational(rhs)]) => lhs
+                .checked_div(rhs)
+                .map(Value::Rational)
+                .ok_or_else(|| anyhow!("{}: division by zero", ident.0)),
+            ("@toInt", [Value::Int(value)]) => Ok(Value::Int(*value)),
+            ("@toInt", [Value::Integer(value)]) => value
+                .to_isize()
+                .map(Value::Int)
+                .ok_or_else(|| anyhow!("@toInt: {value} does not fit in Int")),
+            ("@toInteger", [Value::Int(value)]) => Ok(Value::Integer(BigInt::from(*value))),
+            ("@toInteger", [Value::Integer(value)]) => Ok(Value::Integer(value.clone())),
+            ("@toFloat", [Value::Int(value)]) => Ok(Value::Float(*value as f64)),
+            ("@toFloat", [Value::Integer(value)]) => value
+                .to_f64()
+                .map(Value::Float)
+                .ok_or_else(|| anyhow!("@toFloat: {value} is not a float")),
+            ("@toFloat", [Value::Float(value)]) => Ok(Value::Float(*value)),
+            ("@toFloat", [Value::Rational(value)]) => value
+                .to_f64()
+                .map(Value::Float)
+                .ok_or_else(|| anyhow!("@toFloat: {value} is not a float")),
+            ("@toRational", [Value::Int(value)]) => Ok(Value::Rational(BigRational::from_integer(
+                BigInt::from(*value),
+            ))),
+            ("@toRational", [Value::Integer(value)]) => {
+                Ok(Value::Rational(BigRational::from_integer(value.clone())))
+            }
+            ("@toRational", [Value::Float(value)]) => BigRational::from_float(*value)
+                .map(Value::Rational)
+                .ok_or_else(|| anyhow!("@toRational: {value} is not finite")),
+            ("@toRational", [Value::Rational(value)]) => Ok(Value::Rational(value.clone())),
+            ("@floor", [Value::Float(value)]) => BigRational::from_float(*value)
+                .map(|value| Value::Integer(value.floor().to_integer()))
+                .ok_or_else(|| anyhow!("@floor: {value} is not finite")),
+            ("@floor", [Value::Rational(value)]) => Ok(Value::Integer(value.floor().to_integer())),
+            ("@compare", [lhs, rhs]) => Self::compare(lhs, rhs).map(Self::ordering),
+            ("@cons", [head, tail]) => Ok(Value::list(
+                [head.clone()]
+                    .into_iter()
+                    .chain(Self::items(tail)?)
+                    .collect(),
+            )),
+            ("@append", [lhs, rhs]) => Ok(Value::list(
+                Self::items(lhs)?
+                    .into_iter()
+                    .chain(Self::items(rhs)?)
+                    .collect(),
+            )),
+            ("@ord", [Value::Char(c)]) => Ok(Value::Int(*c as isize)),
+            ("@chr", [Value::Int(code)]) => u32::try_from(*code)
+                .ok()
+                .and_then(char::from_u32)
+                .map(Value::Char)
+                .ok_or_else(|| anyhow!("@chr: {code} is not a character")),
+            _ => Err(anyhow!("undefined intrinsic {}", ident.0)),
+        }
+    }
+
+    /// An `Int` result, `None` when it does not fit in one.
+    fn int(ident: &InstIdent, value: Option<isize>) -> Result<Value> {
+        value
+            .map(Value::Int)
+            .ok_or_else(|| anyhow!("integer overflow in {}", ident.0))
+    }
+
+    /// The methods of the impls the type checker provides, `show` of `impl
+    /// Show Int`, of any `Show`.
+    pub(super) fn method(key: &ImplKey, ident: &Ident) -> Option<InstIdent> {
+        let method = ident.0.split('\'').next();
+        match (is_show(&key.ident), method) {
+            (true, Some("show")) => Some(InstIdent("@show".to_string())),
+            _ => None,
+        }
+    }
+
+    /// What `show` of `value` is. A list is shown only as a `Text`, the empty
+    /// one as well.
+    fn text(value: &Value) -> String {
+        match value {
+            Value::List(items) if items.is_empty() => String::new(),
+            value => value.to_string(),
+        }
+    }
+
+    /// The items of a list, the characters of a text.
+    fn items(value: &Value) -> Result<Vec<Value>> {
+        match value {
+            Value::List(items) => Ok(items.clone()),
+            Value::Text(text) => Ok(text.chars().map(Value::Char).collect()),
+            value => Err(anyhow!("{value} is not a list")),
+        }
+    }
+
+    /// Orders values of the same type, data by the order of their
+    /// constructors' names and then by their arguments.
+    fn compare(lhs: &Value, rhs: &Value) -> Result<Ordering> {
+        match (lhs, rhs) {
+            (Value::Char(lhs), Value::Char(rhs)) => Ok(lhs.cmp(rhs)),
+            (Value::Int(lhs), Value::Int(rhs)) => Ok(lhs.cmp(rhs)),
+            (Value::Integer(lhs), Value::Integer(rhs)) => Ok(lhs.cmp(rhs)),
+            (Value::Rational(lhs), Value::Rational(rhs)) => Ok(lhs.cmp(rhs)),
+            (Value::Float(lhs), Value::Float(rhs)) => lhs
+                .partial_cmp(rhs)
+                .ok_or_else(|| anyhow!("@compare: cannot compare {lhs} with {rhs}")),
+            (Value::Text(lhs), Value::Text(rhs)) => Ok(lhs.cmp(rhs)),
+            (Value::Tuple(lhs), Value::Tuple(rhs)) => Self::compare_all(lhs, rhs),
+            (Value::Data(lhs, lhs_args), Value::Data(rhs, rhs_args)) => match lhs.0.cmp(&rhs.0) {
+                Ordering::Equal => Self::compare_all(lhs_args, rhs_args),
+                ordering => Ok(ordering),
+            },
+            (Value::List(_) | Value::Text(_), Value::List(_) | Value::Text(_)) => {
+                Self::compare_all(&Self::items(lhs)?, &Self::items(rhs)?)
+            }
+            _ => Err(anyhow!("@compare: cannot compare {lhs} with {rhs}")),
+        }
+    }
+
+    fn compare_all(lhs: &[Value], rhs: &[Value]) -> Result<Ordering> {
+        lhs.iter()
+            .zip(rhs)
+            .try_fold(Ordering::Equal, |ordering, (lhs, rhs)| match ordering {
+                Ordering::Equal => Self::compare(lhs, rhs),
+                ordering => Ok(ordering),
+            })
+            .map(|ordering| ordering.then(lhs.len().cmp(&rhs.len())))
+    }
+
+    /// `LT`, `EQ` or `GT` of the `Ordering` of the prelude.
+    fn ordering(ordering: Ordering) -> Value {
+        let ident = match ordering {
+            Ordering::Less => "LT",
+            Ordering::Equal => "EQ",
+            Ordering::Greater => "GT",
+        };
+
+        Value::Data(ExistsIdent(ident.to_string()), Vec::new())
+    }
+
+    fn write(vm: &Vm, value: &Value) -> Result<Value> {
+        let mut stdout = vm.stdout.borrow_mut();
+        write!(stdout, "{}", Self::text(value))?;
+        stdout.flush()?;
+
+        Ok(Value::unit())
+    }
+
+    /// Reads one character.
+    fn read(vm: &Vm) -> Result<Value> {
+        let mut stdin = vm.stdin.borrow_mut();
+        let len = match stdin.fill_buf()?.first() {
+            Some(byte) if *byte < 0x80 => 1,
+            Some(byte) if *byte >= 0xf0 => 4,
+            Some(byte) if *byte >= 0xe0 => 3,
+            Some(_) => 2,
+            None => return Err(anyhow!("@read: end of input")),
+        };
+        let mut buf = vec![0; len];
+        stdin.read_exact(&mut buf)?;
+
+        let c = std::str::from_utf8(&buf)?.chars().next().unwrap();
+
+        Ok(Value::Char(c))
+    }
+}
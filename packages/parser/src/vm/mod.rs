@@ -1,153 +1,836 @@
-use anyhow::anyhow;
+mod embedded;
+mod term;
+mod value;
+
+pub use self::value::{Callable, Closure, Instance, Local, Resumption, Value};
+
+use self::{
+    embedded::EmbeddedEnvironment,
+    term::{clauses, dict_param, Handlers, Let, Locals, Lower, Match, Term},
+};
+use crate::ir::{
+    ir2::IR2,
+    matching::{Constructors, Decision, Head},
+    type_check::{builtins, Dict, ImplKey, Owner, TypeEnv},
+    DataConstructor, DataTerm, ExistsIdent, Expr, HandlerIdent, Ident, InstIdent, Literal,
+    PatternExpr, TypeIdent,
+};
+use anyhow::{anyhow, Result};
 use std::{
+    borrow::Cow,
     cell::RefCell,
-    collections::BTreeMap,
-    fmt::Debug,
-    io::{stdout, Write},
+    collections::HashMap,
+    io::{BufRead, Write},
     rc::Rc,
 };
-use {
-    crate::ir,
-    ir::{Apply, ApplyEmbedded, Assign, Expr, Ident, Literal, Module},
-};
 
-pub struct Vm {
-    stack: Vec<Rc<Expr>>,
-    environment: Environment,
-    gamma_environment: GammaEnvironment,
-    stdout: Box<RefCell<dyn Write>>,
+type Scope = HashMap<Ident, Value>;
+
+/// Interpreter over `IR2`, a CEK machine whose continuation is an explicit
+/// stack of frames.
+///
+/// Arguments are evaluated eagerly, left to right. A function defined by
+/// several equations runs the first one whose patterns match, which the
+/// decision tree they compile to selects. `eff *handler` runs the clause of the
+/// innermost `handle` that handles it, with the frames up to that `handle`
+/// captured as `resume`. A clause that does not resume returns its value from
+/// the `handle`. The handlers defined at the top level handle what is left,
+/// their value is what the effect returns unless they call `resume`, and the
+/// `@handler` intrinsic handles what they do not define.
+///
+/// Traits are passed as dictionaries: a function constrained by a trait takes
+/// an `Instance` of the impl for each constraint, and a method runs the
+/// equations of the impl of its first one.
+pub struct Vm<'a> {
+    functions: HashMap<Ident, Match>,
+    handlers: Rc<Handlers>,
+    /// The number of dictionaries a method takes.
+    methods: HashMap<Ident, usize>,
+    implementations: HashMap<(ImplKey, Ident), Match>,
+    supers: HashMap<ImplKey, Vec<Dict>>,
+    constructors: Constructors,
+    stdin: RefCell<&'a mut dyn BufRead>,
+    stdout: RefCell<&'a mut dyn Write>,
 }
 
-impl Default for Vm {
-    fn default() -> Self {
-        Vm {
-            stack: Vec::new(),
-            environment: Environment::default(),
-            gamma_environment: GammaEnvironment::default(),
-            stdout: Box::new(RefCell::new(stdout())),
-        }
-    }
+enum State {
+    Eval(Rc<Term>, Rc<Scope>),
+    Return(Value),
 }
 
-impl Debug for Vm {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "")
-    }
+#[derive(Debug, Clone)]
+enum Frame {
+    /// Evaluates the argument once the function is a value.
+    Arg(Rc<Term>, Rc<Scope>),
+    /// Applies the function once the argument is a value.
+    Apply(Value),
+    Let(Rc<Let>, Rc<Scope>),
+    /// The items of a tuple or a list evaluated so far.
+    Items(Rc<Term>, Vec<Value>, Rc<Scope>),
+    /// Arguments beyond the arity of a call, applied to its result.
+    Args(Vec<Value>),
+    /// The handlers at the top level also handle the effects of their own
+    /// clauses.
+    Handle {
+        handlers: Rc<Handlers>,
+        scope: Rc<Scope>,
+        top_level: bool,
+    },
+    /// Reinstates the frames of a handler defined at the top level that does
+    /// not call `resume`, its value is what the effect returns.
+    Resume(Rc<Vec<Frame>>),
 }
 
-#[derive(Default)]
-pub struct EmbeddedEnvironment();
-impl EmbeddedEnvironment {
-    fn write(vm: &mut Vm, expr: Rc<Expr>) {
-        vm.push(expr.clone());
-        vm.call_top();
+impl<'a> Vm<'a> {
+    pub fn new(stdin: &'a mut dyn BufRead, stdout: &'a mut dyn Write) -> Self {
+        Self {
+            functions: HashMap::new(),
+            handlers: Rc::new(Handlers::new()),
+            methods: HashMap::new(),
+            implementations: HashMap::new(),
+            supers: HashMap::new(),
+            constructors: Constructors::default(),
+            stdin: RefCell::new(stdin),
+            stdout: RefCell::new(stdout),
+        }
+    }
+
+    /// Loads a program `env` is the types of.
+    pub fn load(&mut self, ir: IR2, env: &TypeEnv) {
+        self.constructors = Constructors::from(&ir);
+        let constructors = &self.constructors;
+
+        ir.ident_symbols
+            .into_iter()
+            .filter(|(_, (functions, _))| !functions.is_empty())
+            .for_each(|(ident, (functions, _))| {
+                let mut lower = Lower::new(env, constructors, Owner::Function(ident.clone()));
+                let equations = functions
+                    .into_iter()
+                    .map(|f| lower.equation(f.expr, f.where_clause))
+                    .collect();
+                self.functions
+                    .insert(ident, Match::new(equations, constructors));
+            });
+
+        // frames holding the handlers are dropped when `run` returns
+        let mut equations = ir
+            .handler_symbols
+            .into_iter()
+            .flat_map(|(ident, (functions, _))| {
+                let mut lower = Lower::new(env, constructors, Owner::Handler(ident));
+                functions
+                    .into_iter()
+                    .map(|f| {
+                        let resume = "resume".into();
+                        let resumes =
+                            f.expr.expr.mentions(&resume) || f.where_clause.mentions(&resume);
 
-        let expr = vm.pop();
-        let value = expr.as_literal().to_string();
+                        (
+                            f.span,
+                            f.ident,
+                            resumes,
+                            lower.equation(f.expr, f.where_clause),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        equations.sort_by_key(|(span, _, _, _)| span.start);
+        let equations = equations
+            .into_iter()
+            .map(|(_, ident, resumes, equation)| (ident, resumes, equation))
+            .collect();
+        *Rc::get_mut(&mut self.handlers).unwrap() = clauses(equations, constructors);
 
-        let result = write!(vm.stdout.get_mut(), "{value}");
-        match result {
-            Ok(_) => {}
-            Err(e) => panic!("{e}"),
+        ir.trait_symbols.iter().for_each(|(_, def)| {
+            def.methods.iter().for_each(|(ident, _)| {
+                let dicts = env.dictionary_params(&Owner::Function(ident.clone()));
+                self.methods.insert(ident.clone(), dicts);
+            })
+        });
+        // an impl that overlaps an earlier one is an error of the type checker
+        ir.impls
+            .into_iter()
+            .filter_map(|def| Some((ImplKey::of(&def)?, def)))
+            .for_each(|(key, def)| {
+                let imp = match env.impl_of(&key) {
+                    Some(imp) if !self.supers.contains_key(&key) => imp,
+                    _ => return,
+                };
+                self.supers.insert(key.clone(), imp.supers.clone());
+
+                let mut lowers = HashMap::new();
+                let mut methods = HashMap::<Ident, Vec<_>>::new();
+                def.methods.into_iter().for_each(|f| {
+                    let equation = lowers
+                        .entry(f.ident.clone())
+                        .or_insert_with(|| {
+                            let owner = Owner::Method(key.clone(), f.ident.clone());
+                            Lower::new(env, constructors, owner)
+                        })
+                        .equation(f.expr, f.where_clause);
+                    methods.entry(f.ident).or_default().push(equation);
+                });
+                methods.into_iter().for_each(|(ident, equations)| {
+                    self.implementations
+                        .insert((key.clone(), ident), Match::new(equations, constructors));
+                });
+            });
+    }
+
+    pub fn run(&self) -> Result<Value> {
+        self.evaluate(&"main".into())
+    }
+
+    /// The value of the function `ident` that takes no arguments.
+    pub fn evaluate(&self, ident: &Ident) -> Result<Value> {
+        if !self.functions.contains_key(ident) {
+            return Err(anyhow!("{ident} is not defined"));
         }
 
-        vm.push(Rc::new(Expr::Literal(Literal::Unit)))
+        let mut stack = vec![Frame::Handle {
+            handlers: self.handlers.clone(),
+            scope: Rc::new(Scope::new()),
+            top_level: true,
+        }];
+        let mut state = State::Eval(Rc::new(Term::Var(ident.clone())), Rc::new(Scope::new()));
+        loop {
+            state = match state {
+                State::Eval(term, scope) => self.eval(term, scope, &mut stack)?,
+                State::Return(value) => match stack.pop() {
+                    Some(frame) => self.ret(frame, value, &mut stack)?,
+                    None => return Ok(value),
+                },
+            };
+        }
     }
 
-    pub fn exec(vm: &mut Vm, ident: &Ident, expr: Rc<Expr>) {
-        match ident.ident.as_str() {
-            "write" => EmbeddedEnvironment::write(vm, expr),
-            _ => panic!(),
+    fn eval(&self, term: Rc<Term>, scope: Rc<Scope>, stack: &mut Vec<Frame>) -> Result<State> {
+        match term.as_ref() {
+            Term::Char(value) => Ok(State::Return(Value::Char(*value))),
+            Term::Text(value) => Ok(State::Return(Value::Text(value.clone()))),
+            Term::Int(value) => Ok(State::Return(Value::Int(*value))),
+            Term::Float(value) => Ok(State::Return(Value::Float(*value))),
+            Term::Integer(value) => Ok(State::Return(Value::Integer(value.clone()))),
+            Term::Rational(value) => Ok(State::Return(Value::Rational(value.clone()))),
+            Term::Tuple(items) if items.is_empty() => Ok(State::Return(Value::unit())),
+            Term::List(items) if items.is_empty() => Ok(State::Return(Value::List(Vec::new()))),
+            Term::Tuple(items) | Term::List(items) => {
+                let item = items[0].clone();
+                stack.push(Frame::Items(term.clone(), Vec::new(), scope.clone()));
+
+                Ok(State::Eval(item, scope))
+            }
+            Term::Apply(f, arg) => {
+                stack.push(Frame::Arg(arg.clone(), scope.clone()));
+
+                Ok(State::Eval(f.clone(), scope))
+            }
+            Term::Let(binding, value) => {
+                stack.push(Frame::Let(binding.clone(), scope.clone()));
+
+                Ok(State::Eval(value.clone(), scope))
+            }
+            Term::Var(ident) => match scope.get(ident) {
+                // a function of a where-clause without arguments runs when it
+                // is referred to
+                Some(Value::Function(callable @ Callable::Local(_), args)) if args.is_empty() => {
+                    self.call(callable.clone(), Vec::new(), stack)
+                }
+                Some(value) => Ok(State::Return(value.clone())),
+                None if self.functions.contains_key(ident) => {
+                    self.call(Callable::Function(ident.clone()), Vec::new(), stack)
+                }
+                None if self.methods.contains_key(ident) => {
+                    self.call(Callable::Method(ident.clone()), Vec::new(), stack)
+                }
+                // `resume` outside of a handler and `await` resume nothing
+                None if builtins().iter().any(|(builtin, _)| builtin == ident) => {
+                    let resume = Callable::Resume(Resumption(Rc::new(Vec::new())));
+
+                    Ok(State::Return(Value::Function(resume, Vec::new())))
+                }
+                None => Err(anyhow!("undefined reference {ident}")),
+            },
+            Term::Inst(ident) => self.call(Callable::Inst(ident.clone()), Vec::new(), stack),
+            Term::Handler(ident) => self.call(Callable::Handler(ident.clone()), Vec::new(), stack),
+            Term::Constructor(ident) => {
+                self.call(Callable::Constructor(ident.clone()), Vec::new(), stack)
+            }
+            Term::Handle(body, handlers) => {
+                stack.push(Frame::Handle {
+                    handlers: handlers.clone(),
+                    scope: scope.clone(),
+                    top_level: false,
+                });
+
+                Ok(State::Eval(body.clone(), scope))
+            }
+            Term::Lambda(lambda) => {
+                let captured = lambda
+                    .captures
+                    .iter()
+                    .filter_map(|ident| Some((ident.clone(), scope.get(ident)?.clone())))
+                    .collect();
+                let closure = Closure(lambda.clone(), Rc::new(captured));
+
+                self.call(Callable::Closure(closure), Vec::new(), stack)
+            }
+            Term::Dict(dict) => Ok(State::Return(self.dict(dict, &scope)?)),
+            Term::Error(message) => Err(anyhow!("{message}")),
         }
     }
-}
 
-#[derive(Debug, Default)]
-struct Environment(BTreeMap<Ident, Rc<Assign>>);
+    /// The instance `dict` is in `scope`. The superclasses of an impl are
+    /// built from the dictionaries of its constraints.
+    fn dict(&self, dict: &Dict, scope: &Scope) -> Result<Value> {
+        match dict {
+            Dict::Param(idx) => scope
+                .get(&dict_param(*idx))
+                .cloned()
+                .ok_or_else(|| anyhow!("undefined dictionary {idx}")),
+            Dict::Impl(key, args) => Ok(Value::Dict(Rc::new(Instance {
+                key: key.clone(),
+                args: args
+                    .iter()
+                    .map(|arg| self.dict(arg, scope))
+                    .collect::<Result<_>>()?,
+            }))),
+            Dict::Super(dict, idx) => match self.dict(dict, scope)? {
+                Value::Dict(instance) => {
+                    let supers = self
+                        .supers
+                        .get(&instance.key)
+                        .ok_or_else(|| anyhow!("impl {} has no superclasses", instance.key))?;
+                    let scope = instance
+                        .args
+                        .iter()
+                        .enumerate()
+                        .map(|(idx, arg)| (dict_param(idx), arg.clone()))
+                        .collect();
 
-impl Environment {
-    fn find_by_name(&self, ident: &Ident) -> Option<Rc<Assign>> {
-        self.0.values().find(|f| &f.ident_name == ident).cloned()
+                    self.dict(&supers[*idx], &scope)
+                }
+                value => Err(anyhow!("{value} is not a dictionary")),
+            },
+        }
     }
 
-    fn push(&mut self, ident: Ident, f: Rc<Assign>) {
-        self.0.insert(ident, f);
+    /// Returns `value` to `frame`.
+    fn ret(&self, frame: Frame, value: Value, stack: &mut Vec<Frame>) -> Result<State> {
+        match frame {
+            Frame::Arg(arg, scope) => {
+                stack.push(Frame::Apply(value));
+
+                Ok(State::Eval(arg, scope))
+            }
+            Frame::Apply(f) => self.apply(f, value, stack),
+            Frame::Let(binding, scope) => {
+                let mut scope = (*scope).clone();
+                match bind(&binding.pattern, &value, &mut scope) {
+                    true => Ok(State::Eval(binding.body.clone(), Rc::new(scope))),
+                    false => Err(anyhow!("{value} does not match the pattern")),
+                }
+            }
+            Frame::Items(term, mut values, scope) => {
+                let (items, list) = match term.as_ref() {
+                    Term::Tuple(items) => (items, false),
+                    Term::List(items) => (items, true),
+                    _ => unreachable!(),
+                };
+                values.push(value);
+
+                match (items.get(values.len()), list) {
+                    (Some(item), _) => {
+                        let item = item.clone();
+                        stack.push(Frame::Items(term.clone(), values, scope.clone()));
+
+                        Ok(State::Eval(item, scope))
+                    }
+                    (None, false) => Ok(State::Return(Value::Tuple(values))),
+                    (None, true) => Ok(State::Return(Value::list(values))),
+                }
+            }
+            Frame::Args(mut args) => {
+                let arg = args.remove(0);
+                if !args.is_empty() {
+                    stack.push(Frame::Args(args));
+                }
+
+                self.apply(value, arg, stack)
+            }
+            Frame::Handle { .. } => Ok(State::Return(value)),
+            Frame::Resume(frames) => {
+                stack.extend(frames.iter().cloned());
+
+                Ok(State::Return(value))
+            }
+        }
     }
 
-    fn find(&self, ident: &Ident) -> Option<Rc<Assign>> {
-        self.0.get(ident).cloned()
+    fn apply(&self, f: Value, arg: Value, stack: &mut Vec<Frame>) -> Result<State> {
+        match f {
+            Value::Function(callable, mut args) => {
+                args.push(arg);
+                self.call(callable, args, stack)
+            }
+            value => Err(anyhow!("{value} is not a function")),
+        }
     }
-}
 
-#[derive(Debug, Default)]
-struct GammaEnvironment(BTreeMap<Ident, TypeExpr>);
+    /// Calls `callable` once it has all of its arguments. Arguments beyond its
+    /// arity are applied to the result.
+    fn call(
+        &self,
+        callable: Callable,
+        mut args: Vec<Value>,
+        stack: &mut Vec<Frame>,
+    ) -> Result<State> {
+        let arity = self.arity(&callable, stack)?;
+        if args.len() < arity {
+            return Ok(State::Return(Value::Function(callable, args)));
+        }
+
+        let rest = args.split_off(arity);
+        if !rest.is_empty() {
+            stack.push(Frame::Args(rest));
+        }
 
-impl Vm {
-    pub fn push(&mut self, value: Rc<Expr>) {
-        match value {
-            value => self.stack.push(value),
+        match &callable {
+            Callable::Function(ident) => {
+                self.invoke(&callable, &self.functions[ident], args, Scope::new())
+            }
+            Callable::Handler(ident) => self.perform(ident, args, stack),
+            Callable::Inst(ident) => {
+                Ok(State::Return(EmbeddedEnvironment::exec(self, ident, args)?))
+            }
+            Callable::Constructor(ident) => Ok(State::Return(Value::Data(ident.clone(), args))),
+            Callable::Resume(Resumption(frames)) => {
+                stack.extend(frames.iter().cloned());
+
+                Ok(State::Return(args.pop().unwrap()))
+            }
+            Callable::Method(ident) => self.dispatch(ident, args, stack),
+            Callable::Implementation(key, ident) => self.invoke(
+                &callable,
+                &self.implementations[&(key.clone(), ident.clone())],
+                args,
+                Scope::new(),
+            ),
+            Callable::Closure(Closure(lambda, captured)) => {
+                let mut scope = (**captured).clone();
+                match lambda
+                    .equation
+                    .patterns
+                    .iter()
+                    .zip(&args)
+                    .all(|(pattern, arg)| bind(pattern, arg, &mut scope))
+                {
+                    true => Ok(State::Eval(
+                        lambda.equation.body.clone(),
+                        define(&lambda.equation.locals, scope),
+                    )),
+                    false => Err(anyhow!(
+                        "{} does not match the pattern",
+                        args.iter()
+                            .map(|arg| arg.to_string())
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                    )),
+                }
+            }
+            Callable::Local(Local(locals, ident, outer)) => {
+                let scope = with_locals(locals, outer.clone());
+
+                self.invoke(&callable, &locals[ident], args, scope)
+            }
         }
     }
 
-    pub fn pop(&mut self) -> Rc<Expr> {
-        match self.stack.pop() {
-            Some(v) => v,
-            None => panic!("{}", anyhow!("empty stack")),
+    /// Calls the method of the impl of the first dictionary with the
+    /// dictionaries of the impl and the rest. An impl the program does not
+    /// define is an intrinsic.
+    fn dispatch(&self, ident: &Ident, args: Vec<Value>, stack: &mut Vec<Frame>) -> Result<State> {
+        let (instance, rest) = match args.split_first() {
+            Some((Value::Dict(instance), rest)) => (instance, rest),
+            _ => return Err(anyhow!("{ident} is not given an impl")),
+        };
+        let key = (instance.key.clone(), ident.clone());
+        if self.implementations.contains_key(&key) {
+            let args = instance.args.iter().chain(rest).cloned().collect();
+
+            return self.call(Callable::Implementation(key.0, key.1), args, stack);
+        }
+
+        match EmbeddedEnvironment::method(&instance.key, ident) {
+            Some(inst) => self.call(Callable::Inst(inst), Vec::new(), stack),
+            None => Err(anyhow!("{ident} is not defined for impl {}", instance.key)),
         }
     }
 
-    pub fn ret(&mut self) -> Rc<Expr> {
-        if self.stack.len() != 1 {
-            dbg!(&self.stack);
-            panic!("{}", anyhow!("stack error"));
+    fn invoke(
+        &self,
+        callable: &Callable,
+        matching: &Match,
+        args: Vec<Value>,
+        mut scope: Scope,
+    ) -> Result<State> {
+        if matching
+            .equations
+            .iter()
+            .any(|equation| equation.patterns.len() != args.len())
+        {
+            return Err(anyhow!(
+                "equations of {callable} take different numbers of arguments"
+            ));
         }
 
-        self.stack.pop().unwrap()
+        let equation = select(&matching.tree, &args).map(|index| &matching.equations[index]);
+        if let Some(equation) = equation {
+            if equation
+                .patterns
+                .iter()
+                .zip(&args)
+                .all(|(pattern, arg)| bind(pattern, arg, &mut scope))
+            {
+                return Ok(State::Eval(
+                    equation.body.clone(),
+                    define(&equation.locals, scope),
+                ));
+            }
+        }
+
+        Err(anyhow!(
+            "no equation of {callable} matches {}",
+            args.iter()
+                .map(|arg| arg.to_string())
+                .collect::<Vec<_>>()
+                .join(" ")
+        ))
     }
 
-    pub fn call_top(&mut self) {
-        let inst = match self.stack.pop() {
-            Some(inst) => inst,
-            None => panic!("{}", anyhow!("empty stack")),
+    /// Runs the clause of the innermost handler of `ident` outside of it. The
+    /// handler itself is captured with the frames, so it also handles what the
+    /// computation performs after it is resumed.
+    fn perform(
+        &self,
+        ident: &HandlerIdent,
+        args: Vec<Value>,
+        stack: &mut Vec<Frame>,
+    ) -> Result<State> {
+        let (index, handlers, scope, top_level) = match handling(ident, stack) {
+            Some(handler) => handler,
+            None => {
+                let value = EmbeddedEnvironment::exec(self, &fallback(ident), args)?;
+
+                return Ok(State::Return(value));
+            }
         };
+        let clause = &handlers[ident];
+        let frames = Rc::new(stack.split_off(match top_level {
+            true => index + 1,
+            false => index,
+        }));
 
-        match inst.as_ref() {
-            Expr::Apply(Apply { ident, expr }) => {
-                dbg!(ident, expr);
-                let f = self.environment.find(ident).unwrap();
-                let f = f.apply(expr.clone());
-                let ident = f.ident.clone();
+        let mut scope = (*scope).clone();
+        match clause.resumes {
+            true => {
+                let resume = Callable::Resume(Resumption(frames));
+                scope.insert("resume".into(), Value::Function(resume, Vec::new()));
+            }
+            false => stack.push(Frame::Resume(frames)),
+        }
+
+        self.invoke(
+            &Callable::Handler(ident.clone()),
+            &clause.matching,
+            args,
+            scope,
+        )
+    }
+
+    fn arity(&self, callable: &Callable, stack: &[Frame]) -> Result<usize> {
+        match callable {
+            Callable::Function(ident) => self
+                .functions
+                .get(ident)
+                .and_then(Match::arity)
+                .ok_or_else(|| anyhow!("undefined reference {ident}")),
+            Callable::Handler(ident) => match handling(ident, stack) {
+                Some((_, handlers, _, _)) => handlers[ident]
+                    .matching
+                    .arity()
+                    .ok_or_else(|| anyhow!("{} has no equations", ident.0)),
+                None => EmbeddedEnvironment::arity(&fallback(ident))
+                    .ok_or_else(|| anyhow!("unhandled effect {}", ident.0)),
+            },
+            Callable::Inst(ident) => EmbeddedEnvironment::arity(ident)
+                .ok_or_else(|| anyhow!("undefined intrinsic {}", ident.0)),
+            Callable::Constructor(ident) => self
+                .constructors
+                .arity(ident)
+                .ok_or_else(|| anyhow!("undefined constructor {}", ident.0)),
+            Callable::Resume(_) => Ok(1),
+            Callable::Method(ident) => self
+                .methods
+                .get(ident)
+                .copied()
+                .ok_or_else(|| anyhow!("undefined method {ident}")),
+            Callable::Implementation(key, ident) => self
+                .implementations
+                .get(&(key.clone(), ident.clone()))
+                .and_then(Match::arity)
+                .ok_or_else(|| anyhow!("{ident} is not defined for impl {key}")),
+            Callable::Closure(Closure(lambda, _)) => Ok(lambda.equation.patterns.len()),
+            Callable::Local(Local(locals, ident, _)) => locals[ident]
+                .arity()
+                .ok_or_else(|| anyhow!("{ident} has no equations")),
+        }
+    }
+}
 
-                self.environment.push(ident.clone(), Rc::new(f));
-                self.push(Rc::new(Expr::Reference(ident)));
-                self.call_top();
+/// The scope of the body of an equation, the arguments and the functions of
+/// its where-clause.
+fn define(locals: &Rc<Locals>, scope: Scope) -> Rc<Scope> {
+    match locals.is_empty() {
+        true => Rc::new(scope),
+        false => Rc::new(with_locals(locals, Rc::new(scope))),
+    }
+}
+
+/// `outer` with the functions of a where-clause, each closed over `outer`.
+fn with_locals(locals: &Rc<Locals>, outer: Rc<Scope>) -> Scope {
+    let mut scope = (*outer).clone();
+    scope.extend(locals.keys().map(|ident| {
+        let local = Local(locals.clone(), ident.clone(), outer.clone());
+
+        (
+            ident.clone(),
+            Value::Function(Callable::Local(local), Vec::new()),
+        )
+    }));
+
+    scope
+}
+
+/// The innermost `handle` frame with a clause for `ident`.
+fn handling(
+    ident: &HandlerIdent,
+    stack: &[Frame],
+) -> Option<(usize, Rc<Handlers>, Rc<Scope>, bool)> {
+    stack
+        .iter()
+        .enumerate()
+        .rev()
+        .find_map(|(index, frame)| match frame {
+            Frame::Handle {
+                handlers,
+                scope,
+                top_level,
+            } if handlers.contains_key(ident) => {
+                Some((index, handlers.clone(), scope.clone(), *top_level))
             }
-            Expr::ApplyEmbedded(ApplyEmbedded { ident, expr }) => {
-                EmbeddedEnvironment::exec(self, ident, expr.clone());
+            _ => None,
+        })
+}
+
+/// `*write` falls back to `@write` when no handler is defined.
+fn fallback(ident: &HandlerIdent) -> InstIdent {
+    InstIdent(ident.0.replacen('*', "@", 1))
+}
+
+/// The equation `tree` selects for `args`.
+fn select(tree: &Decision, args: &[Value]) -> Option<usize> {
+    match tree {
+        Decision::Fail => None,
+        Decision::Leaf(index) => Some(*index),
+        Decision::Switch {
+            path,
+            cases,
+            default,
+        } => {
+            let value = at(args, path)?;
+            match cases.iter().find(|(head, _)| is(&value, head)) {
+                Some((_, tree)) => select(tree, args),
+                None => select(default.as_deref()?, args),
             }
-            Expr::Literal(_) => self.push(inst),
-            Expr::Reference(ident) => match self.environment.find(ident) {
-                Some(value) => {
-                    self.push(value.expr.clone());
+        }
+    }
+}
+
+/// The value at `path`, the index of the argument and then of the fields in
+/// it.
+fn at<'a>(args: &'a [Value], path: &[usize]) -> Option<Cow<'a, Value>> {
+    let (first, rest) = path.split_first()?;
+
+    rest.iter().try_fold(
+        Cow::Borrowed(args.get(*first)?),
+        |value, index| match value {
+            Cow::Borrowed(value) => field(value, *index),
+            Cow::Owned(value) => field(&value, *index).map(|field| Cow::Owned(field.into_owned())),
+        },
+    )
+}
+
+/// The `index`th field of `value`, the head or the tail of a list.
+fn field(value: &Value, index: usize) -> Option<Cow<'_, Value>> {
+    match value {
+        Value::Tuple(values) | Value::Data(_, values) => values.get(index).map(Cow::Borrowed),
+        value => {
+            let (head, tail) = uncons(value)?;
+
+            Some(Cow::Owned(if index == 0 { head } else { tail }))
+        }
+    }
+}
+
+/// The head and the tail of a non-empty list, `Text` being `[Char]`.
+fn uncons(value: &Value) -> Option<(Value, Value)> {
+    match value {
+        Value::List(values) => {
+            let (head, tail) = values.split_first()?;
+
+            Some((head.clone(), Value::List(tail.to_vec())))
+        }
+        Value::Text(text) => {
+            let mut chars = text.chars();
+            let head = chars.next()?;
+
+            Some((Value::Char(head), Value::Text(chars.as_str().to_string())))
+        }
+        _ => None,
+    }
+}
+
+fn is(value: &Value, head: &Head) -> bool {
+    match (head, value) {
+        (Head::Constructor(ident), Value::Data(name, _)) => ident == name,
+        (Head::Tuple(len), Value::Tuple(values)) => *len == values.len(),
+        (Head::Nil, Value::List(values)) => values.is_empty(),
+        (Head::Nil, Value::Text(text)) => text.is_empty(),
+        (Head::Cons, Value::List(values)) => !values.is_empty(),
+        (Head::Cons, Value::Text(text)) => !text.is_empty(),
+        (Head::Char(lhs), Value::Char(rhs)) => lhs == rhs,
+        (Head::Int(lhs), Value::Int(rhs)) => lhs == rhs,
+        (Head::Float(lhs), Value::Float(rhs)) => f64::from_bits(*lhs) == *rhs,
+        (Head::Integer(lhs), Value::Integer(rhs)) => lhs == rhs,
+        (Head::Rational(lhs), Value::Rational(rhs)) => lhs == rhs,
+        _ => false,
+    }
+}
+
+fn bind(pattern: &PatternExpr, value: &Value, scope: &mut Scope) -> bool {
+    match (pattern, value) {
+        (PatternExpr::Any, _) => true,
+        (PatternExpr::Bind(ident), value) => {
+            scope.insert(ident.clone(), value.clone());
+            true
+        }
+        (PatternExpr::Or(lhs, rhs), value) => {
+            let mut lhs_scope = scope.clone();
+            match bind(lhs, value, &mut lhs_scope) {
+                true => {
+                    *scope = lhs_scope;
+                    true
                 }
-                None => panic!("{}", anyhow!("undefined reference")),
-            },
+                false => bind(rhs, value, scope),
+            }
         }
+        (PatternExpr::Literal(literal), value) => bind_literal(literal, value, scope),
+        (PatternExpr::ListHead(head, tail), value) => match uncons(value) {
+            Some((first, rest)) => bind(head, &first, scope) && bind(tail, &rest, scope),
+            None => false,
+        },
+        (PatternExpr::Tuple(_, patterns), Value::Tuple(values)) => {
+            patterns.len() == values.len()
+                && patterns
+                    .iter()
+                    .zip(values)
+                    .all(|(pattern, value)| bind(pattern, value, scope))
+        }
+        (
+            PatternExpr::Constructor(DataConstructor(_, TypeIdent::ExistsIdent(ident), args)),
+            value,
+        ) => bind_constructor(ident, args, value, scope),
+        _ => false,
     }
+}
 
-    pub fn load_module(&mut self, module: Module) {
-        module.values.into_iter().for_each(|f| {
-            dbg!(&f.ident);
-            self.environment.0.insert(f.ident.clone(), Rc::new(f));
-        })
+fn bind_literal(literal: &Literal, value: &Value, scope: &mut Scope) -> bool {
+    match (literal, value) {
+        (Literal::Char(lhs), Value::Char(rhs)) => lhs == rhs,
+        (Literal::Text(lhs), Value::Text(rhs)) => lhs == rhs,
+        (Literal::Text(lhs), Value::List(values)) => {
+            lhs.chars().count() == values.len()
+                && lhs
+                    .chars()
+                    .zip(values)
+                    .all(|(lhs, rhs)| matches!(rhs, Value::Char(rhs) if lhs == *rhs))
+        }
+        (Literal::Int(lhs), Value::Int(rhs)) => lhs == rhs,
+        (Literal::Float(lhs), Value::Float(rhs)) => lhs == rhs,
+        (Literal::Integer(lhs), Value::Integer(rhs)) => lhs == rhs,
+        (Literal::Rational(lhs), Value::Rational(rhs)) => lhs == rhs,
+        (Literal::Tuple(_, items), Value::Tuple(values))
+        | (Literal::List(items), Value::List(values)) => {
+            items.len() == values.len()
+                && items
+                    .iter()
+                    .zip(values)
+                    .all(|(item, value)| bind_item(item, value, scope))
+        }
+        (Literal::List(items), Value::Text(text)) => {
+            items.len() == text.chars().count()
+                && items
+                    .iter()
+                    .zip(text.chars())
+                    .all(|(item, value)| bind_item(item, &Value::Char(value), scope))
+        }
+        _ => false,
     }
+}
 
-    pub fn run(&mut self, module: Module) -> Rc<Expr> {
-        self.load_module(module);
-        let e = self.environment.find_by_name(&"main".into());
+/// The items of tuple and list patterns are parsed as expressions.
+fn bind_item(item: &Expr, value: &Value, scope: &mut Scope) -> bool {
+    match item {
+        Expr::Literal(literal) => bind_literal(literal, value, scope),
+        Expr::Reference(ident) => {
+            scope.insert(ident.clone(), value.clone());
+            true
+        }
+        Expr::ReferenceData(TypeIdent::ExistsIdent(ident)) => {
+            matches!(value, Value::Data(name, values) if name == ident && values.is_empty())
+        }
+        _ => false,
+    }
+}
 
-        self.push(e.unwrap().expr.clone());
-        self.call_top();
-        self.ret()
+/// Constructor patterns are parsed as type expressions, so their arguments are
+/// `DataTerm`s: a lower-case ident binds, an upper-case one matches.
+fn bind_constructor(
+    ident: &ExistsIdent,
+    args: &[DataTerm],
+    value: &Value,
+    scope: &mut Scope,
+) -> bool {
+    match value {
+        Value::Data(name, values) if name == ident && values.len() == args.len() => {
+            args.iter().zip(values).all(|(arg, value)| match arg {
+                DataTerm::Constructor(DataConstructor(_, TypeIdent::ForallIdent(ident), args))
+                    if args.is_empty() =>
+                {
+                    scope.insert(Ident(ident.0.clone()), value.clone());
+                    true
+                }
+                DataTerm::Constructor(DataConstructor(_, TypeIdent::ExistsIdent(ident), args)) => {
+                    bind_constructor(ident, args, value, scope)
+                }
+                DataTerm::Unit => matches!(value, Value::Tuple(values) if values.is_empty()),
+                DataTerm::Top => true,
+                _ => false,
+            })
+        }
+        _ => false,
     }
 }
@@ -0,0 +1,329 @@
+use crate::ir::{
+    ir2::IR2,
+    matching::{Constructors, Decision},
+    type_check::{Dict, Owner, Site, TypeEnv},
+    Abstruction, Apply, ApplyEff, ApplyInst, ExistsIdent, Expr, Handle, HandlerIdent, Ident,
+    InstIdent, Literal, PatternExpr, TypeIdent,
+};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use std::{collections::HashMap, rc::Rc};
+
+/// `Expr` as the machine runs it. Terms are shared by the frames and the
+/// continuations that refer to them.
+#[derive(Debug)]
+pub(super) enum Term {
+    Char(char),
+    Text(String),
+    Int(isize),
+    Float(f64),
+    Integer(BigInt),
+    Rational(BigRational),
+    Tuple(Vec<Rc<Term>>),
+    List(Vec<Rc<Term>>),
+    Apply(Rc<Term>, Rc<Term>),
+    /// `(\pattern -> body) value`
+    Let(Rc<Let>, Rc<Term>),
+    Var(Ident),
+    Inst(InstIdent),
+    Handler(HandlerIdent),
+    Constructor(ExistsIdent),
+    Handle(Rc<Term>, Rc<Handlers>),
+    /// `\patterns -> body`, closed over the scope it is evaluated in.
+    Lambda(Rc<Lambda>),
+    /// The evidence for a trait constraint a function is applied to.
+    Dict(Dict),
+    /// Fails when it is evaluated.
+    Error(String),
+}
+
+#[derive(Debug)]
+pub(super) struct Let {
+    pub(super) pattern: PatternExpr,
+    pub(super) body: Rc<Term>,
+}
+
+#[derive(Debug)]
+pub(super) struct Equation {
+    pub(super) patterns: Vec<PatternExpr>,
+    pub(super) body: Rc<Term>,
+    pub(super) locals: Rc<Locals>,
+}
+
+/// The functions of a where-clause, closed over the arguments of the
+/// equation.
+pub(super) type Locals = HashMap<Ident, Match>;
+
+/// An abstraction in an expression and the variables of the enclosing scope
+/// its body refers to.
+#[derive(Debug)]
+pub(super) struct Lambda {
+    pub(super) equation: Equation,
+    pub(super) captures: Vec<Ident>,
+}
+
+/// The equations of a definition and the decision tree that selects one of
+/// them for its arguments.
+#[derive(Debug)]
+pub(super) struct Match {
+    pub(super) equations: Vec<Equation>,
+    pub(super) tree: Decision,
+}
+
+/// The equations of a handler. A handler defined at the top level that never
+/// calls `resume` returns its value to the effect, a clause of a `handle`
+/// resumes only by calling it.
+#[derive(Debug)]
+pub(super) struct Clause {
+    pub(super) matching: Match,
+    pub(super) resumes: bool,
+}
+
+pub(super) type Handlers = HashMap<HandlerIdent, Clause>;
+
+/// Lowers the equations of one definition. A reference to a function
+/// constrained by traits is applied to the dictionaries the type checker
+/// resolved for it, and the equations of such a function take them as their
+/// first arguments.
+pub(super) struct Lower<'a> {
+    env: &'a TypeEnv,
+    constructors: &'a Constructors,
+    owner: Owner,
+    equation: usize,
+    reference: usize,
+}
+
+impl<'a> Lower<'a> {
+    pub(super) fn new(env: &'a TypeEnv, constructors: &'a Constructors, owner: Owner) -> Self {
+        Self {
+            env,
+            constructors,
+            owner,
+            equation: 0,
+            reference: 0,
+        }
+    }
+
+    /// Lowers the next equation of the definition.
+    pub(super) fn equation(&mut self, abstruction: Abstruction, where_clause: IR2) -> Equation {
+        self.reference = 0;
+        let mut equation = self.local(abstruction, where_clause);
+        equation.patterns.splice(
+            0..0,
+            (0..self.env.dictionary_params(&self.owner))
+                .map(|idx| PatternExpr::Bind(dict_param(idx))),
+        );
+        self.equation += 1;
+
+        equation
+    }
+
+    /// References in a where-clause are numbered after the ones of the
+    /// equation, in the order of `IR2::references`.
+    fn local(&mut self, abstruction: Abstruction, where_clause: IR2) -> Equation {
+        let mut equation = self.abstruction(abstruction);
+        equation.locals = Rc::new(self.locals(where_clause));
+
+        equation
+    }
+
+    fn locals(&mut self, mut where_clause: IR2) -> Locals {
+        let idents = where_clause
+            .functions()
+            .into_iter()
+            .map(|(ident, _)| ident.clone())
+            .collect::<Vec<_>>();
+
+        idents
+            .into_iter()
+            .filter_map(|ident| {
+                let (functions, _) = where_clause.ident_symbols.remove(&ident)?;
+                let equations = functions
+                    .into_iter()
+                    .map(|f| self.local(f.expr, f.where_clause))
+                    .collect();
+
+                Some((ident, Match::new(equations, self.constructors)))
+            })
+            .collect()
+    }
+
+    /// Splits curried abstractions into their argument patterns and the body.
+    fn abstruction(&mut self, abstruction: Abstruction) -> Equation {
+        match abstruction {
+            Abstruction {
+                arg: Some(pattern),
+                expr: Expr::Abstruction(inner),
+            } => {
+                let mut equation = self.abstruction(*inner);
+                equation.patterns.insert(0, pattern);
+
+                equation
+            }
+            Abstruction { arg: None, expr } => Equation {
+                patterns: Vec::new(),
+                body: Rc::new(self.term(expr)),
+                locals: Rc::default(),
+            },
+            Abstruction {
+                arg: Some(pattern),
+                expr,
+            } => Equation {
+                patterns: vec![pattern],
+                body: Rc::new(self.term(expr)),
+                locals: Rc::default(),
+            },
+        }
+    }
+
+    /// References are numbered in the order of `Expr::references`.
+    fn term(&mut self, expr: Expr) -> Term {
+        match expr {
+            Expr::Literal(Literal::Char(value)) => Term::Char(value),
+            Expr::Literal(Literal::Text(value)) => Term::Text(value),
+            Expr::Literal(Literal::Int(value)) => Term::Int(value),
+            Expr::Literal(Literal::Float(value)) => Term::Float(value),
+            Expr::Literal(Literal::Integer(value)) => Term::Integer(value),
+            Expr::Literal(Literal::Rational(value)) => Term::Rational(value),
+            Expr::Literal(Literal::Tuple(_, items)) => Term::Tuple(self.terms(items)),
+            Expr::Literal(Literal::List(items)) => Term::List(self.terms(items)),
+            Expr::Apply(Apply {
+                abstruction, expr, ..
+            }) => match *abstruction {
+                Abstruction { arg: None, expr: f } => {
+                    let f = self.term(f);
+
+                    Term::Apply(Rc::new(f), Rc::new(self.term(*expr)))
+                }
+                Abstruction {
+                    arg: Some(pattern),
+                    expr: body,
+                } => {
+                    let body = self.term(body);
+
+                    Term::Let(
+                        Rc::new(Let {
+                            pattern,
+                            body: Rc::new(body),
+                        }),
+                        Rc::new(self.term(*expr)),
+                    )
+                }
+            },
+            Expr::ApplyEmbedded(ApplyInst { ident, expr, .. }) => {
+                Term::Apply(Rc::new(Term::Inst(ident)), Rc::new(self.term(*expr)))
+            }
+            Expr::ApplyEff(ApplyEff { ident, expr, .. }) => {
+                Term::Apply(Rc::new(Term::Handler(ident)), Rc::new(self.term(*expr)))
+            }
+            Expr::Reference(ident) => {
+                let site = Site {
+                    owner: self.owner.clone(),
+                    equation: self.equation,
+                    reference: self.reference,
+                };
+                self.reference += 1;
+
+                self.env
+                    .dictionaries(&site)
+                    .unwrap_or_default()
+                    .iter()
+                    .fold(Term::Var(ident), |acc, dict| {
+                        Term::Apply(Rc::new(acc), Rc::new(Term::Dict(dict.clone())))
+                    })
+            }
+            Expr::ReferenceInst(ident) => Term::Inst(ident),
+            Expr::ReferenceHandler(ident) => Term::Handler(ident),
+            Expr::ReferenceData(TypeIdent::ExistsIdent(ident)) => Term::Constructor(ident),
+            Expr::ReferenceData(ident) => Term::Error(format!("{ident} is not a constructor")),
+            Expr::Handle(Handle { expr, handlers }) => {
+                let body = self.term(*expr);
+                let equations = handlers
+                    .into_iter()
+                    .map(|handler| (handler.ident, true, self.abstruction(handler.expr)))
+                    .collect();
+
+                Term::Handle(
+                    Rc::new(body),
+                    Rc::new(clauses(equations, self.constructors)),
+                )
+            }
+            Expr::Abstruction(abstruction) => {
+                let captures = abstruction
+                    .captures()
+                    .into_iter()
+                    .chain((0..self.env.dictionary_params(&self.owner)).map(dict_param))
+                    .collect();
+
+                Term::Lambda(Rc::new(Lambda {
+                    equation: self.abstruction(*abstruction),
+                    captures,
+                }))
+            }
+            Expr::Pattern(_) => Term::Error("pattern expressions are not supported yet".into()),
+        }
+    }
+
+    fn terms(&mut self, exprs: Vec<Expr>) -> Vec<Rc<Term>> {
+        exprs
+            .into_iter()
+            .map(|expr| Rc::new(self.term(expr)))
+            .collect()
+    }
+}
+
+impl Match {
+    pub(super) fn new(equations: Vec<Equation>, constructors: &Constructors) -> Self {
+        let patterns = equations
+            .iter()
+            .map(|equation| equation.patterns.iter().collect())
+            .collect::<Vec<_>>();
+
+        Self {
+            tree: Decision::compile(&patterns, constructors),
+            equations,
+        }
+    }
+
+    pub(super) fn arity(&self) -> Option<usize> {
+        self.equations
+            .first()
+            .map(|equation| equation.patterns.len())
+    }
+}
+
+/// The clause of each handler, from its equations in order.
+pub(super) fn clauses(
+    equations: Vec<(HandlerIdent, bool, Equation)>,
+    constructors: &Constructors,
+) -> Handlers {
+    let mut grouped = Vec::<(HandlerIdent, bool, Vec<Equation>)>::new();
+    equations
+        .into_iter()
+        .for_each(|(ident, resumes, equation)| {
+            match grouped.iter_mut().find(|(other, _, _)| other == &ident) {
+                Some((_, any, equations)) => {
+                    *any |= resumes;
+                    equations.push(equation);
+                }
+                None => grouped.push((ident, resumes, vec![equation])),
+            }
+        });
+
+    grouped
+        .into_iter()
+        .map(|(ident, resumes, equations)| {
+            let clause = Clause {
+                matching: Match::new(equations, constructors),
+                resumes,
+            };
+
+            (ident, clause)
+        })
+        .collect()
+}
+
+/// The `i`th dictionary parameter of an equation, not a valid identifier.
+pub(super) fn dict_param(idx: usize) -> Ident {
+    Ident(format!("#{idx}"))
+}
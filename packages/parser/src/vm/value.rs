@@ -0,0 +1,133 @@
+use super::{
+    term::{Lambda, Locals},
+    Frame, Scope,
+};
+use crate::ir::{type_check::ImplKey, ExistsIdent, HandlerIdent, Ident, InstIdent};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use std::{fmt::Display, rc::Rc};
+
+#[derive(Debug, Clone)]
+pub enum Value {
+    Char(char),
+    Text(String),
+    Int(isize),
+    Float(f64),
+    Integer(BigInt),
+    Rational(BigRational),
+    Tuple(Vec<Value>),
+    List(Vec<Value>),
+    Data(ExistsIdent, Vec<Value>),
+    Function(Callable, Vec<Value>),
+    Dict(Rc<Instance>),
+}
+
+/// Something that can be applied, together with the arguments applied so far
+/// in `Value::Function`.
+#[derive(Debug, Clone)]
+pub enum Callable {
+    Function(Ident),
+    Handler(HandlerIdent),
+    Inst(InstIdent),
+    Constructor(ExistsIdent),
+    /// `resume` in a handler, may be called any number of times.
+    Resume(Resumption),
+    /// A method of a trait, dispatched on its first dictionary.
+    Method(Ident),
+    /// A method as an impl defines it.
+    Implementation(ImplKey, Ident),
+    Closure(Closure),
+    /// A function of a where-clause.
+    Local(Local),
+}
+
+/// An impl with the dictionaries of its constraints.
+#[derive(Debug)]
+pub struct Instance {
+    pub key: ImplKey,
+    pub args: Vec<Value>,
+}
+
+/// The frames between a handler and the effect it handles.
+#[derive(Debug, Clone)]
+pub struct Resumption(pub(super) Rc<Vec<Frame>>);
+
+/// A lambda with the values of the variables it captures.
+#[derive(Debug, Clone)]
+pub struct Closure(pub(super) Rc<Lambda>, pub(super) Rc<Scope>);
+
+/// A function of a where-clause with the scope of the equation it belongs
+/// to, the other functions of the where-clause are defined again when it is
+/// called.
+#[derive(Debug, Clone)]
+pub struct Local(
+    pub(super) Rc<Locals>,
+    pub(super) Ident,
+    pub(super) Rc<Scope>,
+);
+
+impl Value {
+    pub fn unit() -> Self {
+        Value::Tuple(Vec::new())
+    }
+
+    /// A list, a text when it is of characters. `Text` is `[Char]`, and is
+    /// written as a text.
+    pub fn list(items: Vec<Value>) -> Self {
+        let chars = items
+            .iter()
+            .map(|item| match item {
+                Value::Char(c) => Some(*c),
+                _ => None,
+            })
+            .collect::<Option<String>>();
+
+        match chars {
+            Some(text) if !text.is_empty() => Value::Text(text),
+            _ => Value::List(items),
+        }
+    }
+}
+
+fn join(values: &[Value], sep: &str) -> String {
+    values
+        .iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<_>>()
+        .join(sep)
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Char(value) => write!(f, "{value}"),
+            Value::Text(value) => write!(f, "{value}"),
+            Value::Int(value) => write!(f, "{value}"),
+            Value::Float(value) => write!(f, "{value:?}"),
+            Value::Integer(value) => write!(f, "{value}"),
+            Value::Rational(value) => write!(f, "{value}"),
+            Value::Tuple(values) => write!(f, "({})", join(values, ", ")),
+            Value::List(values) => write!(f, "[{}]", join(values, " ")),
+            Value::Data(ident, values) if values.is_empty() => write!(f, "{}", ident.0),
+            Value::Data(ident, values) => write!(f, "({} {})", ident.0, join(values, " ")),
+            Value::Function(callable, _) => write!(f, "<{callable}>"),
+            Value::Dict(instance) => write!(f, "<impl {}>", instance.key),
+        }
+    }
+}
+
+impl Display for Callable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Callable::Function(ident) => write!(f, "{ident}"),
+            Callable::Handler(ident) => write!(f, "{}", ident.0),
+            Callable::Inst(ident) => write!(f, "{}", ident.0),
+            Callable::Constructor(ident) => write!(f, "{}", ident.0),
+            Callable::Resume(_) => write!(f, "resume"),
+            Callable::Method(ident) => write!(f, "{ident}"),
+            Callable::Implementation(key, ident) => write!(f, "{ident} of impl {key}"),
+            Callable::Closure(_) => write!(f, "lambda"),
+            Callable::Local(Local(_, ident, _)) => write!(f, "{ident}"),
+        }
+    }
+}
@@ -0,0 +1,68 @@
+extern crate parser;
+
+use insta::{assert_debug_snapshot, glob};
+use parser::{ProseDownScriptAnalysis, ProseDownScriptLoader};
+use std::{fs, path::Path};
+
+#[test]
+fn analysis_test() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/analysis");
+    let loader = ProseDownScriptLoader::new(&root).with_bundled_std();
+
+    glob!("../fixtures/analysis", "*.{pds,pd}", |path| {
+        let source = fs::read_to_string(path).unwrap();
+        let analysis = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("pd") => parser::prose_down_script_analyze_document(&loader, &source),
+            _ => parser::prose_down_script_analyze(&loader, path, &source),
+        };
+
+        assert_debug_snapshot!(summary(&analysis, &root));
+    });
+}
+
+/// The analysis without the names of the standard library, and with the paths
+/// relative to the fixtures.
+fn summary(analysis: &ProseDownScriptAnalysis, root: &Path) -> Vec<String> {
+    let symbols = analysis
+        .definitions
+        .iter()
+        .chain(analysis.imports.iter().filter(|symbol| {
+            symbol
+                .path
+                .as_ref()
+                .is_some_and(|path| path.starts_with(root))
+        }))
+        .map(|symbol| {
+            format!(
+                "{} {} at {}{} :: {}",
+                symbol.namespace,
+                symbol.ident,
+                symbol
+                    .path
+                    .as_ref()
+                    .and_then(|path| path.strip_prefix(root).ok())
+                    .map(|path| format!("{}:", path.display()))
+                    .unwrap_or_default(),
+                symbol.span,
+                symbol.ty.as_deref().unwrap_or("-"),
+            )
+        });
+
+    analysis
+        .diagnostics
+        .iter()
+        .map(|diagnostic| {
+            format!(
+                "{:?} at {}: {}",
+                diagnostic.severity, diagnostic.span, diagnostic.message
+            )
+        })
+        .chain(symbols)
+        .chain(
+            analysis
+                .sections
+                .iter()
+                .map(|section| format!("section {} at {}", section.title, section.span)),
+        )
+        .collect()
+}
@@ -0,0 +1,19 @@
+extern crate parser;
+
+use insta::{assert_debug_snapshot, glob};
+use parser::ProseDownScriptLoader;
+use std::fs;
+
+#[test]
+fn document_test() {
+    let loader = ProseDownScriptLoader::default().with_bundled_std();
+
+    glob!("../fixtures/documents", "*.pd", |path| {
+        let input = fs::read_to_string(path).unwrap();
+        let document = parser::prose_down_parse(&input).unwrap();
+        let evaluated = parser::prose_down_script_evaluate(&loader, &document)
+            .map_err(|err| format!("{err:#}"));
+
+        assert_debug_snapshot!(evaluated);
+    });
+}
@@ -0,0 +1,39 @@
+extern crate parser;
+
+use insta::{assert_snapshot, glob};
+use serde_json::Value;
+use std::fs;
+
+#[test]
+fn format_test() {
+    glob!("../fixtures", "{pds,pds-errors,format}/*.pds", |path| {
+        let source = fs::read_to_string(path).unwrap();
+        let formatted = parser::prose_down_script_format(&source).unwrap();
+
+        assert_eq!(statements(&formatted), statements(&source));
+        assert_eq!(
+            parser::prose_down_script_format(&formatted).unwrap(),
+            formatted
+        );
+        assert_snapshot!(formatted);
+    });
+}
+
+/// The statements a script parses to, without where they are.
+fn statements(source: &str) -> Value {
+    without_spans(serde_json::to_value(parser::prose_down_script_parse(source).unwrap()).unwrap())
+}
+
+fn without_spans(value: Value) -> Value {
+    match value {
+        Value::Object(fields) => Value::Object(
+            fields
+                .into_iter()
+                .filter(|(key, _)| key != "span")
+                .map(|(key, value)| (key, without_spans(value)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(without_spans).collect()),
+        value => value,
+    }
+}
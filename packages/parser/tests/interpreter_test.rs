@@ -1,14 +1,47 @@
 extern crate parser;
 
-use insta::{assert_debug_snapshot, glob};
+use insta::{assert_debug_snapshot, assert_snapshot, glob};
+use parser::ProseDownScriptLoader;
 use std::fs;
 
 #[test]
 fn run_test() {
     glob!("../fixtures/pds", "*.pds", |path| {
         let input = fs::read_to_string(path).unwrap();
-        let value = parser::prose_down_script_run(&input).unwrap();
+        let mut stdout = Vec::new();
+        let value =
+            parser::prose_down_script_run_with_io(&input, &mut "".as_bytes(), &mut stdout).unwrap();
 
-        assert_debug_snapshot!(value);
+        assert_debug_snapshot!((value, String::from_utf8(stdout).unwrap()));
+    });
+}
+
+#[test]
+fn run_std_test() {
+    let loader = ProseDownScriptLoader::default().with_bundled_std();
+
+    glob!("../fixtures/pds", "*.pds", |path| {
+        let mut stdout = Vec::new();
+        let value = parser::prose_down_script_run_file_with_io(
+            &loader,
+            path,
+            &mut "".as_bytes(),
+            &mut stdout,
+        )
+        .unwrap();
+
+        assert_debug_snapshot!((value, String::from_utf8(stdout).unwrap()));
+    });
+}
+
+#[test]
+fn run_error_test() {
+    glob!("../fixtures/pds-errors", "*.pds", |path| {
+        let input = fs::read_to_string(path).unwrap();
+        let err =
+            parser::prose_down_script_run_with_io(&input, &mut "".as_bytes(), &mut Vec::new())
+                .unwrap_err();
+
+        assert_snapshot!(format!("{err:#}"));
     });
 }
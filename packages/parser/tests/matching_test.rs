@@ -0,0 +1,26 @@
+extern crate parser;
+
+use insta::{assert_debug_snapshot, glob};
+use std::{fs, path::Path};
+
+#[test]
+fn matching_test() {
+    let test = |path: &Path| {
+        let input = fs::read_to_string(path).unwrap();
+        let ast = parser::prose_down_script_parse(&input).unwrap();
+        let ir1 = parser::ir::ir1::transform1(&ast);
+        let warnings = parser::ir::ir2::transform2(ir1, ast)
+            .map(|ir2| {
+                parser::ir::matching::check_matches(&ir2)
+                    .iter()
+                    .map(|warning| warning.to_string())
+                    .collect::<Vec<_>>()
+            })
+            .map_err(|e| format!("{e:#}"));
+
+        assert_debug_snapshot!(warnings);
+    };
+
+    glob!("../fixtures/pds", "*.pds", test);
+    glob!("../fixtures/pds-errors", "*.pds", test);
+}
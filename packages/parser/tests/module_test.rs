@@ -0,0 +1,24 @@
+extern crate parser;
+
+use insta::{assert_debug_snapshot, glob};
+use parser::ProseDownScriptLoader;
+use std::path::Path;
+
+#[test]
+fn module_test() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/modules");
+    let loader = ProseDownScriptLoader::new(&root).with_std(root.join("std"));
+
+    glob!("../fixtures/modules", "*.pds", |path| {
+        let mut stdout = Vec::new();
+        let value = parser::prose_down_script_run_file_with_io(
+            &loader,
+            path,
+            &mut "".as_bytes(),
+            &mut stdout,
+        )
+        .map_err(|err| format!("{err:#}"));
+
+        assert_debug_snapshot!((value, String::from_utf8(stdout).unwrap()));
+    });
+}
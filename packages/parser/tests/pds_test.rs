@@ -1,15 +1,67 @@
 extern crate parser;
 
 use insta::{assert_debug_snapshot, glob};
-use std::fs;
+use std::{fs, path::Path};
 
 #[test]
 fn pds_test() {
-    glob!("../fixtures/pds", "*.pds", |path| {
+    let test = |path: &Path| {
         dbg!(&path);
         let input = fs::read_to_string(path).unwrap();
         let ast = parser::prose_down_script_parse(&input).unwrap();
 
         assert_debug_snapshot!(ast);
-    });
+    };
+
+    glob!("../fixtures/pds", "*.pds", test);
+    glob!("../fixtures/pds-errors", "*.pds", test);
+}
+
+#[test]
+fn nesting_test() {
+    use parser::ast::{Expr, Statement};
+
+    // each level used to be parsed again for every way the one around it
+    // could start, which doubled the time per level
+    let depth = 64;
+    let input = format!("a = {}x{}\n", "(1 + ".repeat(depth), ")".repeat(depth));
+    let module = parser::prose_down_script_parse(&input).unwrap();
+    let Some(Statement::Assign(assign)) = module.statements.first() else {
+        panic!("{module:?}");
+    };
+    let groups = std::iter::successors(Some(&assign.expr), |expr| match expr {
+        Expr::Group(expr) => Some(expr),
+        Expr::Apply(apply) => Some(&apply.expr),
+        _ => None,
+    })
+    .filter(|expr| matches!(expr, Expr::Group(_)))
+    .count();
+
+    assert_eq!(groups, depth);
+
+    let input = format!("a = {}x{}\n", "f (".repeat(depth), ")".repeat(depth));
+    parser::prose_down_script_parse(&input).unwrap();
+
+    let input = format!("a = {}x{}\n", "(1, ".repeat(depth), ")".repeat(depth));
+    parser::prose_down_script_parse(&input).unwrap();
+
+    let input = format!("f {}x{} = 1\n", "(".repeat(depth), ", 1)".repeat(depth));
+    parser::prose_down_script_parse(&input).unwrap();
+
+    let input = format!(
+        "a = \\{}xs{} -> x\n",
+        "[x : ".repeat(depth),
+        "]".repeat(depth)
+    );
+    parser::prose_down_script_parse(&input).unwrap();
+
+    let input = format!("a = {}x{}\n", "(\\x -> ".repeat(depth), ")".repeat(depth));
+    parser::prose_down_script_parse(&input).unwrap();
+
+    let input = format!(
+        "a = {}x{}\n",
+        "handle (".repeat(depth),
+        ") where {}".repeat(depth)
+    );
+    parser::prose_down_script_parse(&input).unwrap();
 }
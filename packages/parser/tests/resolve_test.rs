@@ -0,0 +1,23 @@
+extern crate parser;
+
+use insta::{assert_debug_snapshot, glob};
+use std::{fs, path::Path};
+
+#[test]
+fn resolve_test() {
+    let test = |path: &Path| {
+        let input = fs::read_to_string(path).unwrap();
+        let ast = parser::prose_down_script_parse(&input).unwrap();
+        let ir1 = parser::ir::ir1::transform1(&ast);
+        let (_, errors) = parser::ir::resolve::resolve(&ir1, &ast);
+        let errors = errors
+            .iter()
+            .map(|error| error.to_string())
+            .collect::<Vec<_>>();
+
+        assert_debug_snapshot!(errors);
+    };
+
+    glob!("../fixtures/pds", "*.pds", test);
+    glob!("../fixtures/pds-errors", "*.pds", test);
+}
@@ -0,0 +1,28 @@
+extern crate parser;
+
+use insta::{assert_snapshot, glob};
+use parser::{ProseDownScriptLoader, ProseDownScriptSession};
+use std::fs;
+
+#[test]
+fn session_test() {
+    glob!("../fixtures/session", "*.txt", |path| {
+        let loader = ProseDownScriptLoader::default().with_bundled_std();
+        let mut session = ProseDownScriptSession::new(loader).unwrap();
+        let transcript = fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|input| {
+                let mut stdout = Vec::new();
+                let reply = match session.input(input, &mut "".as_bytes(), &mut stdout) {
+                    Ok(reply) => reply.to_string(),
+                    Err(err) => format!("error: {err:#}\n"),
+                };
+
+                format!("> {input}\n{}{reply}", String::from_utf8(stdout).unwrap())
+            })
+            .collect::<String>();
+
+        assert_snapshot!(transcript);
+    });
+}
@@ -0,0 +1,22 @@
+extern crate parser;
+
+use insta::{assert_debug_snapshot, glob};
+use parser::ProseDownScriptLoader;
+
+#[test]
+fn std_test() {
+    let loader = ProseDownScriptLoader::default().with_bundled_std();
+
+    glob!("../fixtures/std", "*.pds", |path| {
+        let mut stdout = Vec::new();
+        let value = parser::prose_down_script_run_file_with_io(
+            &loader,
+            path,
+            &mut "".as_bytes(),
+            &mut stdout,
+        )
+        .map_err(|err| format!("{err:#}"));
+
+        assert_debug_snapshot!((value, String::from_utf8(stdout).unwrap()));
+    });
+}
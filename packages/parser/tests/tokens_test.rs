@@ -0,0 +1,47 @@
+extern crate parser;
+
+use insta::{assert_snapshot, glob};
+use parser::ProseDownScriptToken;
+use std::fs;
+
+#[test]
+fn tokens_test() {
+    glob!("../fixtures/tokens", "*.{pds,pd}", |path| {
+        let source = fs::read_to_string(path).unwrap();
+        let tokens = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("pd") => parser::prose_down_document_tokens(&source),
+            _ => parser::prose_down_script_tokens(&source),
+        }
+        .unwrap();
+
+        assert_snapshot!(listing(&source, &tokens));
+    });
+}
+
+#[test]
+fn highlight_test() {
+    glob!("../fixtures/tokens", "*.pds", |path| {
+        let source = fs::read_to_string(path).unwrap();
+
+        assert_snapshot!(parser::prose_down_script_highlight(&source).unwrap());
+    });
+}
+
+/// A token on each line, `line:column kind text`.
+fn listing(source: &str, tokens: &[ProseDownScriptToken]) -> String {
+    let lines = source.lines().collect::<Vec<_>>();
+
+    tokens
+        .iter()
+        .map(|token| {
+            let (line, start) = token.span.start;
+            let text = lines[line - 1]
+                .chars()
+                .skip(start - 1)
+                .take(token.span.end.1 - start)
+                .collect::<String>();
+
+            format!("{} {} {text}\n", token.span, token.kind)
+        })
+        .collect()
+}
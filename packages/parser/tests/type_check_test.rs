@@ -1,17 +1,32 @@
 extern crate parser;
 
 use insta::{assert_debug_snapshot, glob};
-use std::fs;
+use std::{fs, path::Path};
 
 #[test]
 fn type_check_test() {
-    glob!("../fixtures/pds", "*.pds", |path| {
+    let test = |path: &Path| {
         dbg!(&path);
         let input = fs::read_to_string(path).unwrap();
         let ast = parser::prose_down_script_parse(&input).unwrap();
         let ir1 = parser::ir::ir1::transform1(&ast);
-        let ir2 = parser::ir::ir2::transform2(ir1, ast);
+        let verdict = parser::ir::ir2::transform2(ir1, ast)
+            .map(|ir2| {
+                let (env, errors) = parser::ir::type_check::type_check(&ir2);
+                let schemes = env
+                    .to_string()
+                    .lines()
+                    .map(String::from)
+                    .collect::<Vec<_>>();
+                let errors = errors.iter().map(|e| e.to_string()).collect::<Vec<_>>();
 
-        assert_debug_snapshot!(ir2);
-    });
+                (schemes, errors)
+            })
+            .map_err(|e| format!("{e:#}"));
+
+        assert_debug_snapshot!(verdict);
+    };
+
+    glob!("../fixtures/pds", "*.pds", test);
+    glob!("../fixtures/pds-errors", "*.pds", test);
 }
@@ -41,7 +41,7 @@ impl From<Block> for Pds0Ast {
                 name: "themantic_break".to_string(),
                 data: BTreeMap::new(),
             }),
-            Block::PdsScript(value) => Pds0Ast::Node(Pds0Node {
+            Block::PdsScript(value, _) => Pds0Ast::Node(Pds0Node {
                 name: "pds_script".to_string(),
                 data: {
                     let mut map = BTreeMap::new();
@@ -75,7 +75,7 @@ impl From<Inline> for Pds0Ast {
                     value: JsonValue::String(value),
                 })],
             }),
-            Inline::Expr(value) => Pds0Ast::Literal(Pds0Literal {
+            Inline::Expr(value, _) => Pds0Ast::Literal(Pds0Literal {
                 name: "expr".to_string(),
                 data: {
                     let mut map = BTreeMap::new();
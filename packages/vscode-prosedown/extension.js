@@ -0,0 +1,29 @@
+const vscode = require("vscode");
+const { LanguageClient } = require("vscode-languageclient/node");
+
+let client;
+
+// starts `gokuraku lsp` for scripts and documents
+function activate(context) {
+  const command = vscode.workspace.getConfiguration("gokuraku").get("path");
+  client = new LanguageClient(
+    "gokuraku",
+    "gokuraku",
+    { command, args: ["lsp"] },
+    {
+      documentSelector: [
+        { scheme: "file", language: "prosedown" },
+        { scheme: "file", language: "prosedown-document" },
+      ],
+    }
+  );
+  context.subscriptions.push(client);
+
+  return client.start();
+}
+
+function deactivate() {
+  return client && client.stop();
+}
+
+module.exports = { activate, deactivate };